@@ -0,0 +1,51 @@
+//! Measures the cached JSON Pointer of a `Jqesque` in a tight loop.
+//!
+//! Run with `cargo bench --bench pointer_cache`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use jqesque::{tokens_to_pointer, Jqesque};
+use serde_json::json;
+
+const DOCUMENTS: usize = 10_000;
+
+fn time(name: &str, run: impl Fn()) {
+    // Warm up once, then keep the best of a few runs to reduce noise
+    run();
+    let best = (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap();
+    println!("{:<40} {:>10.2?}", name, best);
+}
+
+fn main() {
+    let jqesque = ">servers[0].\"host.name\".tags.primary=example.com"
+        .parse::<Jqesque>()
+        .unwrap();
+    let documents = vec![json!({"servers": [{"host.name": {"tags": {}}}]}); DOCUMENTS];
+
+    println!("{} iterations each", DOCUMENTS);
+    time("tokens_to_pointer (uncached)", || {
+        for _ in 0..DOCUMENTS {
+            black_box(tokens_to_pointer(black_box(&jqesque.tokens)));
+        }
+    });
+    time("Jqesque::to_pointer (cached)", || {
+        for _ in 0..DOCUMENTS {
+            black_box(black_box(&jqesque).to_pointer());
+        }
+    });
+
+    time("apply_to, one Jqesque for all documents", || {
+        for mut document in documents.clone() {
+            jqesque.apply_to(&mut document).unwrap();
+            black_box(document);
+        }
+    });
+}
@@ -0,0 +1,295 @@
+use std::time::SystemTime;
+
+use jsonptr::{Pointer, PointerBuf};
+use serde_json::Value;
+
+use crate::manipulators::expand_wildcards;
+use crate::options::ApplyOptions;
+use crate::pointer::tokens_to_pointer;
+use crate::types::{Jqesque, JqesqueError, Operation};
+
+/// A record of a single mutation performed by `Jqesque::apply_to_logging`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    /// The operation that was performed, with `Auto` resolved to the operation it fell back to
+    pub operation: Operation,
+    /// The JSON Pointer of the path that was touched
+    pub pointer: String,
+    /// When the operation was applied, if known
+    pub timestamp: Option<SystemTime>,
+    /// The value at the path before the operation was applied, or `None` if the path did not exist
+    pub before: Option<Value>,
+    /// The value at the path after the operation was applied, or `None` if the path is gone
+    pub after: Option<Value>,
+}
+
+/// A description of what `Jqesque::apply_to_with_report` did.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApplyReport {
+    /// The operation that was performed, with `Auto` resolved to the operation it fell back to
+    pub operation: Operation,
+    /// The JSON Pointer of the path that was touched
+    pub pointer: String,
+    /// The value at the path before the operation was applied, or `None` if the path did not
+    /// exist. For `Remove`, this is the removed value.
+    pub previous: Option<Value>,
+}
+
+/// What happened to a node on the way to the target of `Jqesque::apply_to_with_observer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeEvent {
+    /// The node did not exist and was created, either as a container on the way to the target,
+    /// as `null` padding of an array, or as the target itself
+    Created,
+    /// The node was an object or array already and was walked through or merged into
+    Visited,
+    /// The node existed and was replaced, either by the new value or by a container because it
+    /// was of the wrong type for the path
+    Overwritten,
+    /// The node was removed, as merging a `null` does under `MergeStrategy::DeleteNull`
+    Removed,
+}
+
+impl Jqesque {
+    /// Applies the parsed structure to a JSON object, reporting what was done.
+    ///
+    /// This behaves like `apply_to`, but also captures the value at the path before the mutation,
+    /// so that callers can log a before/after of the change. A path with wildcards touches any
+    /// number of values, which a single report cannot describe, so it fails with a
+    /// `WildcardError`; use `apply_to_logging` for those.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object to apply the operation to
+    ///
+    /// ## Returns
+    ///
+    /// Returns an `ApplyReport`, a `WildcardError` if the path has wildcards, or a JqesqueError if
+    /// an error occurred.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Operation};
+    /// use serde_json::json;
+    ///
+    /// let mut json_obj = json!({"foo": {"bar": 1}});
+    ///
+    /// let jqesque = "-foo.bar".parse::<Jqesque>().unwrap();
+    /// let report = jqesque.apply_to_with_report(&mut json_obj).unwrap();
+    ///
+    /// assert_eq!(report.operation, Operation::Remove);
+    /// assert_eq!(report.pointer, "/foo/bar");
+    /// assert_eq!(report.previous, Some(json!(1)));
+    /// ```
+    pub fn apply_to_with_report(&self, json: &mut Value) -> Result<ApplyReport, JqesqueError> {
+        self.apply_reported(json).map(|(report, _)| report)
+    }
+
+    // Apply as `apply_to_with_report` does, also returning the pointer the report is for
+    fn apply_reported(&self, json: &mut Value) -> Result<(ApplyReport, PointerBuf), JqesqueError> {
+        if self.has_wildcard() {
+            return Err(JqesqueError::WildcardError(self.operation.clone()));
+        }
+        let pointer_buf = self.resolved_pointer(json, true)?;
+        let pointer: &Pointer = &pointer_buf;
+
+        let previous = pointer.resolve(json).ok().cloned();
+        let operation = self.apply_to(json)?;
+
+        let report = ApplyReport {
+            operation,
+            pointer: pointer_buf.to_string(),
+            previous,
+        };
+        Ok((report, pointer_buf))
+    }
+
+    /// Applies the parsed structure to a JSON object, recording an audit trail of the change.
+    ///
+    /// This behaves like `apply_to`, but on success an `AuditEntry` describing the operation, the
+    /// pointer that was touched, and the values before and after the change is pushed onto `log`.
+    /// Nothing is logged if the operation fails.
+    ///
+    /// A `Remove` or `Test` with wildcards logs one entry for each value the wildcards match, in
+    /// document order, and nothing if they match no values.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object to apply the operation to
+    /// * `log` - The audit log to append to
+    ///
+    /// ## Returns
+    ///
+    /// Returns the operation that was performed or a JqesqueError if an error occurred.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Operation};
+    /// use serde_json::json;
+    ///
+    /// let mut json_obj = json!({"foo": "old"});
+    /// let mut log = Vec::new();
+    ///
+    /// let jqesque = "foo=new".parse::<Jqesque>().unwrap();
+    /// jqesque.apply_to_logging(&mut json_obj, &mut log).unwrap();
+    ///
+    /// assert_eq!(log[0].operation, Operation::Replace);
+    /// assert_eq!(log[0].pointer, "/foo");
+    /// assert_eq!(log[0].before, Some(json!("old")));
+    /// assert_eq!(log[0].after, Some(json!("new")));
+    /// ```
+    pub fn apply_to_logging(
+        &self,
+        json: &mut Value,
+        log: &mut Vec<AuditEntry>,
+    ) -> Result<Operation, JqesqueError> {
+        if self.has_wildcard() {
+            return self.apply_wildcard_logging(json, log);
+        }
+
+        let (report, pointer_buf) = self.apply_reported(json)?;
+        let pointer: &Pointer = &pointer_buf;
+        let after = pointer.resolve(json).ok().cloned();
+
+        log.push(AuditEntry {
+            operation: report.operation.clone(),
+            pointer: report.pointer,
+            timestamp: Some(SystemTime::now()),
+            before: report.previous,
+            after,
+        });
+
+        Ok(report.operation)
+    }
+
+    // Apply a path with wildcards, logging an entry for each value they match
+    fn apply_wildcard_logging(
+        &self,
+        json: &mut Value,
+        log: &mut Vec<AuditEntry>,
+    ) -> Result<Operation, JqesqueError> {
+        let pointers: Vec<PointerBuf> = expand_wildcards(json, &self.tokens)
+            .iter()
+            .map(|tokens| tokens_to_pointer(tokens))
+            .collect();
+        let before: Vec<Option<Value>> = pointers
+            .iter()
+            .map(|pointer| pointer.resolve(json).ok().cloned())
+            .collect();
+
+        let operation = self.apply_to(json)?;
+
+        let timestamp = SystemTime::now();
+        for (pointer, before) in pointers.into_iter().zip(before) {
+            log.push(AuditEntry {
+                operation: operation.clone(),
+                after: pointer.resolve(json).ok().cloned(),
+                pointer: pointer.to_string(),
+                timestamp: Some(timestamp),
+                before,
+            });
+        }
+
+        Ok(operation)
+    }
+
+    /// Applies the parsed structure to a JSON object, telling `observer` about every node changed
+    /// or walked through on the way.
+    ///
+    /// This behaves like `apply_to`. For the operations that build missing structure (`Insert`,
+    /// `Merge` and `Default`, also when chosen by `Auto`), `observer` is called with the JSON
+    /// Pointer and the `NodeEvent` of each object or array level traversed, of each `null` an
+    /// array is padded with, and of the target itself. For a merge, every node merged into is
+    /// reported as well. Parents are reported before their children. The other operations work
+    /// on existing paths only and report nothing.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object to apply the operation to
+    /// * `observer` - The callback to tell about each node
+    ///
+    /// ## Returns
+    ///
+    /// Returns the operation that was performed or a JqesqueError if an error occurred.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, NodeEvent};
+    /// use serde_json::json;
+    ///
+    /// let mut json_obj = json!({"foo": {}});
+    /// let mut events = Vec::new();
+    ///
+    /// let jqesque = ">foo.bar[1]=true".parse::<Jqesque>().unwrap();
+    /// jqesque
+    ///     .apply_to_with_observer(&mut json_obj, &mut |pointer, event| {
+    ///         events.push((pointer.to_string(), event))
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(json_obj, json!({"foo": {"bar": [null, true]}}));
+    /// assert_eq!(
+    ///     events,
+    ///     vec![
+    ///         ("".to_string(), NodeEvent::Visited),
+    ///         ("/foo".to_string(), NodeEvent::Visited),
+    ///         ("/foo/bar".to_string(), NodeEvent::Created),
+    ///         ("/foo/bar/0".to_string(), NodeEvent::Created),
+    ///         ("/foo/bar/1".to_string(), NodeEvent::Created),
+    ///     ]
+    /// );
+    /// ```
+    pub fn apply_to_with_observer(
+        &self,
+        json: &mut Value,
+        observer: &mut dyn FnMut(&Pointer, NodeEvent),
+    ) -> Result<Operation, JqesqueError> {
+        self.apply_observed(json, &ApplyOptions::default(), observer)
+            .map(|outcome| outcome.operation())
+    }
+
+    /// Applies the parsed structure to a JSON object, counting the nodes it created.
+    ///
+    /// This behaves like `apply_to`, and counts the nodes `apply_to_with_observer` reports as
+    /// `NodeEvent::Created`: the objects and arrays created on the way to the path, the `null`
+    /// elements an array is padded with, and the value itself if it is new. Overwriting an
+    /// existing value creates nothing, so a large count flags an edit that fabricates a lot of
+    /// structure, such as a huge array index. As with the observer, only `Insert`, `Merge` and
+    /// `Default` are counted.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object to apply the operation to
+    ///
+    /// ## Returns
+    ///
+    /// Returns the operation that was performed and the number of nodes created, or a
+    /// JqesqueError if an error occurred.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Operation};
+    /// use serde_json::json;
+    ///
+    /// let mut json_obj = json!({});
+    /// let jqesque = ">a.b[2]=1".parse::<Jqesque>().unwrap();
+    ///
+    /// // `a`, `b`, the padding `b[0]` and `b[1]`, and `b[2]` itself
+    /// assert_eq!(jqesque.apply_to_counted(&mut json_obj).unwrap(), (Operation::Insert, 5));
+    /// // Setting it again only overwrites
+    /// assert_eq!(jqesque.apply_to_counted(&mut json_obj).unwrap(), (Operation::Insert, 0));
+    /// ```
+    pub fn apply_to_counted(&self, json: &mut Value) -> Result<(Operation, usize), JqesqueError> {
+        let mut created = 0;
+        let operation = self.apply_to_with_observer(json, &mut |_, event| {
+            if event == NodeEvent::Created {
+                created += 1;
+            }
+        })?;
+        Ok((operation, created))
+    }
+}
@@ -0,0 +1,783 @@
+use serde_json::{Map, Value};
+
+use crate::manipulators::merge_json;
+use crate::options::{ApplyOptions, MergeOptions, ParseOptions};
+use crate::parse::{parse_input, parse_input_with_options};
+use crate::types::{ApplyOutcome, Jqesque, JqesqueError, Operation, PathToken, Separator};
+
+impl Jqesque {
+    /// Parses a batch of assignments into `Jqesque` structures using the specified separator.
+    ///
+    /// Assignments are split as by `split_assignments`, on newlines and `;`, and each one is
+    /// parsed as by `from_str_with_separator`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `input` - The batch input to parse, e.g. `foo.bar=1;baz[0]=true;-old.key`
+    /// * `separator` - The separator to use between keys
+    ///
+    /// ## Returns
+    ///
+    /// Returns the parsed assignments in input order, or a `BatchError` holding the index of the
+    /// first assignment that failed to parse.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Separator};
+    /// use serde_json::json;
+    ///
+    /// let jqesques =
+    ///     Jqesque::parse_many("foo.bar=1;baz[0]=true;-old.key", Separator::Dot).unwrap();
+    /// assert_eq!(jqesques.len(), 3);
+    ///
+    /// let mut json_obj = json!({"old": {"key": 1}});
+    /// Jqesque::apply_all(&jqesques, &mut json_obj).unwrap();
+    /// assert_eq!(json_obj, json!({"foo": {"bar": 1}, "baz": [true], "old": {}}));
+    /// ```
+    pub fn parse_many(input: &str, separator: Separator) -> Result<Vec<Self>, JqesqueError> {
+        Self::parse_many_with_delimiter(input, separator, ';')
+    }
+
+    /// Parses a batch of assignments, split on newlines and `delimiter` instead of `;`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `input` - The batch input to parse
+    /// * `separator` - The separator to use between keys
+    /// * `delimiter` - The delimiter between assignments, in addition to newlines
+    ///
+    /// ## Returns
+    ///
+    /// Returns the parsed assignments in input order, or a `BatchError` holding the index of the
+    /// first assignment that failed to parse.
+    pub fn parse_many_with_delimiter(
+        input: &str,
+        separator: Separator,
+        delimiter: char,
+    ) -> Result<Vec<Self>, JqesqueError> {
+        split_assignments_on(input, delimiter)
+            .into_iter()
+            .enumerate()
+            .map(|(index, assignment)| {
+                parse_input(assignment, separator.clone()).map_err(|e| batch_error(index, e))
+            })
+            .collect()
+    }
+
+    /// Lazily parses assignments from an iterator of lines, one assignment per line.
+    ///
+    /// Lines are parsed only as the returned iterator is advanced, so arbitrarily large scripts
+    /// can be processed without holding them in memory. Blank lines and comments are skipped, with
+    /// the same comment rules as `split_assignments`: a line starting with `#` is a comment, and
+    /// a `#` after whitespace starts a trailing comment.
+    ///
+    /// ## Arguments
+    ///
+    /// * `lines` - The lines to parse, e.g. from `BufRead::lines`
+    /// * `options` - The options controlling how each assignment is parsed
+    ///
+    /// ## Returns
+    ///
+    /// Returns an iterator over the parsed assignments. An assignment that fails to parse yields
+    /// a `LineError` holding its one-based line number, and parsing continues with the next line.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, JqesqueError, ParseOptions};
+    ///
+    /// let lines = ["a=1", "", "# comment", "b..c=2", "d=3"].map(String::from);
+    /// let options = ParseOptions::new();
+    /// let results: Vec<_> = Jqesque::parse_lines(lines.into_iter(), &options).collect();
+    ///
+    /// assert_eq!(results.len(), 3);
+    /// assert!(matches!(results[1], Err(JqesqueError::LineError { line: 4, .. })));
+    /// ```
+    pub fn parse_lines<'a, I>(
+        lines: I,
+        options: &'a ParseOptions,
+    ) -> impl Iterator<Item = Result<Jqesque, JqesqueError>> + 'a
+    where
+        I: Iterator<Item = String> + 'a,
+    {
+        lines.enumerate().filter_map(move |(index, line)| {
+            let assignment = split_assignments_on(&line, '\n').into_iter().next()?;
+            Some(parse_input_with_options(assignment, options).map_err(|e| {
+                JqesqueError::LineError {
+                    line: index + 1,
+                    source: Box::new(e),
+                }
+            }))
+        })
+    }
+
+    /// Applies a batch of assignments to a JSON object, in order.
+    ///
+    /// Application stops at the first assignment that fails, leaving the effects of the
+    /// preceding assignments in place.
+    ///
+    /// ## Arguments
+    ///
+    /// * `jqesques` - The assignments to apply
+    /// * `json` - The JSON object to apply them to
+    ///
+    /// ## Returns
+    ///
+    /// Returns `Ok(())` if all assignments were applied, or a `BatchError` holding the index of
+    /// the assignment that failed.
+    pub fn apply_all(jqesques: &[Jqesque], json: &mut Value) -> Result<(), JqesqueError> {
+        for (index, jqesque) in jqesques.iter().enumerate() {
+            jqesque.apply_to(json).map_err(|e| batch_error(index, e))?;
+        }
+        Ok(())
+    }
+
+    /// Applies a batch of assignments to a JSON object, in order, with the given options.
+    ///
+    /// This behaves like `apply_all`, but reports the outcome of each assignment, so that with
+    /// `ApplyOptions::on_missing` set to `MissingPolicy::Skip`, the skipped ones can be found.
+    ///
+    /// ## Arguments
+    ///
+    /// * `jqesques` - The assignments to apply
+    /// * `json` - The JSON object to apply them to
+    /// * `options` - The options controlling how each assignment is applied
+    ///
+    /// ## Returns
+    ///
+    /// Returns the outcome of each assignment, in order, or a `BatchError` holding the index of
+    /// the assignment that failed.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{ApplyOptions, ApplyOutcome, Jqesque, MissingPolicy, Operation, Separator};
+    /// use serde_json::json;
+    ///
+    /// let jqesques = Jqesque::parse_many("-old;-gone;>new=1", Separator::Dot).unwrap();
+    /// let options = ApplyOptions::new().on_missing(MissingPolicy::Skip);
+    /// let mut json_obj = json!({"old": 1});
+    ///
+    /// let outcomes = Jqesque::apply_all_with_options(&jqesques, &mut json_obj, &options).unwrap();
+    /// assert_eq!(outcomes[1], ApplyOutcome::Skipped(Operation::Remove));
+    /// assert_eq!(json_obj, json!({"new": 1}));
+    /// ```
+    pub fn apply_all_with_options(
+        jqesques: &[Jqesque],
+        json: &mut Value,
+        options: &ApplyOptions,
+    ) -> Result<Vec<ApplyOutcome>, JqesqueError> {
+        jqesques
+            .iter()
+            .enumerate()
+            .map(|(index, jqesque)| {
+                jqesque
+                    .apply_to_with_options(json, options)
+                    .map_err(|e| batch_error(index, e))
+            })
+            .collect()
+    }
+
+    /// Applies a batch of assignments to a JSON object as a single transaction.
+    ///
+    /// The assignments are applied, in order, to a copy of `json`, which is only written back if
+    /// all of them succeed. If any assignment fails, `json` is left untouched.
+    ///
+    /// ## Arguments
+    ///
+    /// * `jqesques` - The assignments to apply
+    /// * `json` - The JSON object to apply them to
+    ///
+    /// ## Returns
+    ///
+    /// Returns the operation performed by each assignment (with `Auto` resolved), or a
+    /// `BatchError` holding the index of the assignment that failed.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Separator};
+    /// use serde_json::json;
+    ///
+    /// let jqesques = Jqesque::parse_many(">a=1;=missing=2", Separator::Dot).unwrap();
+    /// let mut json_obj = json!({});
+    /// assert!(Jqesque::apply_all_atomic(&jqesques, &mut json_obj).is_err());
+    /// assert_eq!(json_obj, json!({}));
+    /// ```
+    pub fn apply_all_atomic(
+        jqesques: &[Jqesque],
+        json: &mut Value,
+    ) -> Result<Vec<Operation>, JqesqueError> {
+        let mut staged = json.clone();
+        let operations = jqesques
+            .iter()
+            .enumerate()
+            .map(|(index, jqesque)| {
+                jqesque
+                    .apply_to(&mut staged)
+                    .map_err(|e| batch_error(index, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        *json = staged;
+        Ok(operations)
+    }
+
+    /// Flattens a JSON document into the leaf assignments that would reconstruct it.
+    ///
+    /// The document is walked depth-first, in key and index order, emitting an `Insert` for each
+    /// scalar leaf. Empty objects and arrays are emitted as leaves with the value `{}` or `[]`, so
+    /// they survive the round trip, except for an empty document, which yields no assignments.
+    /// Applying the result with `apply_all` to an empty object rebuilds the document.
+    ///
+    /// ## Arguments
+    ///
+    /// * `value` - The JSON document to flatten
+    /// * `separator` - The separator the assignments are meant for, which `Display` formats
+    ///   their paths with
+    ///
+    /// ## Returns
+    ///
+    /// Returns one assignment per leaf, in document order.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Separator};
+    /// use serde_json::json;
+    ///
+    /// let document = json!({"foo": {"bar": [1, true]}, "baz": {}});
+    /// let jqesques = Jqesque::flatten(&document, Separator::Dot);
+    /// assert_eq!(jqesques.len(), 3);
+    ///
+    /// let mut json_obj = json!({});
+    /// Jqesque::apply_all(&jqesques, &mut json_obj).unwrap();
+    /// assert_eq!(json_obj, document);
+    ///
+    /// let slashed = Jqesque::flatten(&json!({"a": {"b": 1}}), Separator::Slash);
+    /// assert_eq!(slashed[0].to_string(), ">a/b=1");
+    /// ```
+    pub fn flatten(value: &Value, separator: Separator) -> Vec<Jqesque> {
+        Jqesque::iter_leaves(value)
+            .map(|(tokens, leaf)| Jqesque {
+                tokens,
+                value: Some(leaf.clone()),
+                operation: Operation::Insert,
+                required_type: None,
+                from: None,
+                expected: None,
+                separator: separator.clone(),
+                pointer_cache: Default::default(),
+            })
+            .collect()
+    }
+
+    /// Walks the leaves of a JSON document, yielding each with the path tokens leading to it.
+    ///
+    /// The leaves are those `flatten` emits assignments for: scalars, and empty objects and
+    /// arrays, except for an empty document, which has no leaves. They are yielded depth-first,
+    /// with object keys in the order of the map (sorted, unless serde_json's `preserve_order`
+    /// feature is enabled) and array elements by index. The values are borrowed from the document,
+    /// and nothing is walked until the iterator is advanced.
+    ///
+    /// ## Arguments
+    ///
+    /// * `value` - The JSON document to walk
+    ///
+    /// ## Returns
+    ///
+    /// Returns an iterator over the path tokens and value of each leaf, in document order.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, PathToken};
+    /// use serde_json::json;
+    ///
+    /// let document = json!({"foo": {"bar": [1, true]}});
+    /// let mut leaves = Jqesque::iter_leaves(&document);
+    ///
+    /// assert_eq!(
+    ///     leaves.next(),
+    ///     Some((
+    ///         vec![
+    ///             PathToken::Key("foo".to_string()),
+    ///             PathToken::Key("bar".to_string()),
+    ///             PathToken::Index(0),
+    ///         ],
+    ///         &json!(1)
+    ///     ))
+    /// );
+    /// assert_eq!(leaves.next().map(|(_, leaf)| leaf), Some(&json!(true)));
+    /// assert_eq!(leaves.next(), None);
+    /// ```
+    pub fn iter_leaves(value: &Value) -> impl Iterator<Item = (Vec<PathToken>, &Value)> {
+        Leaves {
+            root: Some(value).filter(|value| !value.as_object().is_some_and(Map::is_empty)),
+            path: Vec::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    /// Builds a document from a batch of assignments alone, without a target document.
+    ///
+    /// Starting from `null`, each `Insert`, `Merge`, `Default` and `Auto` assignment is applied in
+    /// order, combining their contributions as `as_json` would show them individually: `a.b=1`
+    /// and `a.c=2` give `{"a": {"b": 1, "c": 2}}`. The JSON Patch operations (`Add`, `Replace`,
+    /// `Remove`, `Test`, `Copy` and `Move`) describe changes to an existing document, so they are
+    /// skipped. An assignment that cannot be applied, such as a negative index into a missing
+    /// array, is skipped as well.
+    ///
+    /// ## Arguments
+    ///
+    /// * `jqesques` - The assignments to combine
+    ///
+    /// ## Returns
+    ///
+    /// Returns the combined document, or `null` if no assignment contributed to it.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Separator};
+    /// use serde_json::json;
+    ///
+    /// let jqesques =
+    ///     Jqesque::parse_many("a.b=1;a.c=2;~a={\"d\":3};-a.b", Separator::Dot).unwrap();
+    /// assert_eq!(Jqesque::combined_json(&jqesques), json!({"a": {"b": 1, "c": 2, "d": 3}}));
+    /// ```
+    pub fn combined_json(jqesques: &[Jqesque]) -> Value {
+        let mut json = Value::Null;
+        for jqesque in jqesques {
+            if !jqesque.operation.requires_existing_path() {
+                let _ = jqesque.apply_to(&mut json);
+            }
+        }
+        json
+    }
+
+    /// Checks whether applying this assignment and `other` in either order may give different
+    /// results.
+    ///
+    /// Each assignment reads or writes the value at its path, including everything below it:
+    /// `Test` reads, every other operation writes, and `Copy` and `Move` also read the path taken
+    /// from, which `Move` writes as well. An `Add`, `Auto`, `Remove` or `RemoveIf` of an array
+    /// element, and a `Move` from one, shift the elements after it, so they write the whole array.
+    ///
+    /// Two assignments conflict if either writes a path the other reads or writes, where two paths
+    /// overlap unless they lead to different keys or different indices at some depth. Paths
+    /// diverging at a key and an index overlap, as they need an object and an array at the same
+    /// place, and wildcards, length-relative indices and append tokens overlap any index. Two
+    /// `Test`s never conflict.
+    ///
+    /// ## Arguments
+    ///
+    /// * `other` - The assignment to check against
+    ///
+    /// ## Returns
+    ///
+    /// Returns `true` if the assignments conflict.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    ///
+    /// let write = ">a.b=1".parse::<Jqesque>().unwrap();
+    /// let remove = "-a".parse::<Jqesque>().unwrap();
+    /// let other = ">a.c=2".parse::<Jqesque>().unwrap();
+    ///
+    /// assert!(write.conflicts_with(&remove));
+    /// assert!(!write.conflicts_with(&other));
+    /// ```
+    pub fn conflicts_with(&self, other: &Jqesque) -> bool {
+        let theirs = accessed_paths(other);
+        accessed_paths(self).iter().any(|(path, writes)| {
+            theirs.iter().any(|(other_path, other_writes)| {
+                (*writes || *other_writes) && !commutes(path, other_path)
+            })
+        })
+    }
+
+    /// Deduplicates a batch of assignments and orders parent paths before their children.
+    ///
+    /// Assignments to the same path are combined by these rules, in input order:
+    ///
+    /// - A write (`Insert`, `Replace`, `Add`, `Auto` or `Remove`) replaces any earlier assignment
+    ///   to the same path or below it, so the last writer wins and a `Remove` after a write
+    ///   cancels the write. The `Remove` itself is kept, to remove any value the document already
+    ///   held. So that it cannot fail where the input would not, it is preceded by an `Insert` of
+    ///   `null` to its path, which creates the parents the cancelled writes would have created.
+    /// - A `Merge` after a `Merge` to the same path is combined into one `Merge` of both values.
+    /// - A `Merge` after a write with a value is folded into that value, and a `Merge` after a
+    ///   `Remove` becomes an `Insert` of the merged value.
+    ///
+    /// Values are combined with the default `MergeOptions`, and assignments with a type annotation
+    /// are never folded into. The result is then sorted by path, as ordered by `PathToken`, so
+    /// parents come before children.
+    ///
+    /// Assignments whose effect depends on their position are kept as they are, and nothing is
+    /// moved or combined across them: `Test`, `Copy`, `Move`, `Default`, `RemoveIf` and
+    /// `TestReplace`, paths with length-relative indices, append tokens or wildcards, and `Add`,
+    /// `Auto` and `Remove` with an array index (which shift the elements after it). Likewise, an
+    /// assignment is not moved before an earlier one it would then overwrite (e.g. a `Merge` of a
+    /// parent after a write to a child, or `a.b` after `a[0]`). As a result, applying the
+    /// normalized batch gives the same document as applying the input, provided every assignment
+    /// succeeds; assignments that would have failed may be dropped.
+    ///
+    /// ## Arguments
+    ///
+    /// * `jqesques` - The assignments to normalize, in the order they would be applied
+    ///
+    /// ## Returns
+    ///
+    /// Returns the normalized assignments, in the order they should be applied.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Separator};
+    ///
+    /// let jqesques = Jqesque::parse_many(">a.b.c=1;>b=2;>a.b={};>b=3", Separator::Dot).unwrap();
+    /// let normalized: Vec<String> = Jqesque::normalize(jqesques)
+    ///     .iter()
+    ///     .map(|jqesque| jqesque.to_string())
+    ///     .collect();
+    /// assert_eq!(normalized, vec![">a.b={}", ">b=3"]);
+    /// ```
+    pub fn normalize(jqesques: Vec<Jqesque>) -> Vec<Jqesque> {
+        let mut normalized = Vec::with_capacity(jqesques.len());
+        // The assignments that may still be combined and reordered among themselves
+        let mut segment: Vec<Jqesque> = Vec::new();
+
+        for jqesque in jqesques {
+            if is_positional(&jqesque) {
+                flush_segment(&mut segment, &mut normalized);
+                normalized.push(jqesque);
+                continue;
+            }
+
+            if jqesque.operation != Operation::Merge {
+                let cancelled = segment.iter().any(|earlier| {
+                    earlier.operation != Operation::Remove
+                        && earlier.tokens.starts_with(&jqesque.tokens)
+                });
+                segment.retain(|earlier| !earlier.tokens.starts_with(&jqesque.tokens));
+                // Without the cancelled writes the path may not exist, so give it a value to
+                // remove rather than have the `Remove` fail
+                if cancelled && jqesque.operation == Operation::Remove {
+                    segment.push(removal_placeholder(&jqesque));
+                }
+            }
+
+            // Sorting must not move the assignment before an earlier one it does not commute
+            // with, and neither may folding a `Merge` into an earlier assignment
+            if segment.iter().any(|earlier| {
+                jqesque.tokens < earlier.tokens && !commutes(&jqesque.tokens, &earlier.tokens)
+            }) {
+                flush_segment(&mut segment, &mut normalized);
+            } else if let Some(position) = segment
+                .iter()
+                .rposition(|earlier| earlier.tokens == jqesque.tokens)
+                .filter(|&position| {
+                    jqesque.operation == Operation::Merge
+                        && segment[position].required_type.is_none()
+                        && jqesque.required_type.is_none()
+                })
+            {
+                let mut value = jqesque.value.unwrap_or(Value::Null);
+                if segment[position].operation == Operation::Remove {
+                    let earlier = &mut segment[position];
+                    earlier.operation = Operation::Insert;
+                    earlier.value = Some(value);
+                    // The `Insert` creates the parents itself, so any placeholder is not needed
+                    if position > 0
+                        && segment[position - 1] == removal_placeholder(&segment[position])
+                    {
+                        segment.remove(position - 1);
+                    }
+                } else {
+                    let earlier = &mut segment[position];
+                    let target = earlier.value.get_or_insert(Value::Null);
+                    merge_json(target, &mut value, &MergeOptions::default());
+                }
+                continue;
+            }
+            segment.push(jqesque);
+        }
+
+        flush_segment(&mut segment, &mut normalized);
+        normalized
+    }
+}
+
+// The `Insert` of `null` put before a `Remove` that cancels earlier writes
+fn removal_placeholder(remove: &Jqesque) -> Jqesque {
+    Jqesque {
+        tokens: remove.tokens.clone(),
+        value: Some(Value::Null),
+        operation: Operation::Insert,
+        required_type: None,
+        from: None,
+        expected: None,
+        separator: remove.separator.clone(),
+        pointer_cache: Default::default(),
+    }
+}
+
+// Whether the effect of an assignment depends on its position among the others
+fn is_positional(jqesque: &Jqesque) -> bool {
+    let relative = |tokens: &[PathToken]| {
+        tokens.iter().any(|token| {
+            matches!(
+                token,
+                PathToken::LengthRelative(_) | PathToken::Append | PathToken::Wildcard
+            )
+        })
+    };
+    let shifting = matches!(
+        jqesque.operation,
+        Operation::Add | Operation::Auto | Operation::Remove
+    ) && jqesque
+        .tokens
+        .iter()
+        .any(|token| matches!(token, PathToken::Index(_)));
+
+    matches!(
+        jqesque.operation,
+        Operation::Test
+            | Operation::TestLen
+            | Operation::Copy
+            | Operation::Move
+            | Operation::Default
+            | Operation::RemoveIf
+            | Operation::TestReplace
+    ) || shifting
+        || relative(&jqesque.tokens)
+        || jqesque.from.as_deref().is_some_and(relative)
+}
+
+// The paths an assignment accesses, along with whether it writes them
+fn accessed_paths(jqesque: &Jqesque) -> Vec<(&[PathToken], bool)> {
+    let mut paths = match jqesque.operation {
+        Operation::Test | Operation::TestLen => vec![(jqesque.tokens.as_slice(), false)],
+        Operation::Add | Operation::Auto | Operation::Remove | Operation::RemoveIf => {
+            vec![(shifted(&jqesque.tokens), true)]
+        }
+        _ => vec![(jqesque.tokens.as_slice(), true)],
+    };
+    if let Some(from) = &jqesque.from {
+        match jqesque.operation {
+            Operation::Move => paths.push((shifted(from), true)),
+            _ => paths.push((from.as_slice(), false)),
+        }
+    }
+    paths
+}
+
+// The path written by inserting or removing the value at `tokens`: an array element shifts its
+// siblings, so it writes the whole array
+fn shifted(tokens: &[PathToken]) -> &[PathToken] {
+    match tokens.split_last() {
+        Some((PathToken::Index(_) | PathToken::LengthRelative(_) | PathToken::Append, parent)) => {
+            parent
+        }
+        _ => tokens,
+    }
+}
+
+// Whether two paths refer to separate values, so that assignments to them can be swapped. Paths
+// that diverge at a key and an index do not, as one turns the value into an object and the other
+// into an array.
+fn commutes(a: &[PathToken], b: &[PathToken]) -> bool {
+    matches!(
+        a.iter().zip(b).find(|(a, b)| a != b),
+        Some((PathToken::Key(_), PathToken::Key(_)) | (PathToken::Index(_), PathToken::Index(_)))
+    )
+}
+
+fn flush_segment(segment: &mut Vec<Jqesque>, normalized: &mut Vec<Jqesque>) {
+    segment.sort_by(|a, b| a.tokens.cmp(&b.tokens));
+    normalized.append(segment);
+}
+
+/// The iterator returned by `Jqesque::iter_leaves`.
+struct Leaves<'a> {
+    // The document, until the first call to `next`
+    root: Option<&'a Value>,
+    // The tokens of the container being walked at the top of the stack
+    path: Vec<PathToken>,
+    // The children still to walk of each container on the way to the current one
+    stack: Vec<Children<'a>>,
+}
+
+enum Children<'a> {
+    Object(serde_json::map::Iter<'a>),
+    Array(std::iter::Enumerate<std::slice::Iter<'a, Value>>),
+}
+
+impl<'a> Children<'a> {
+    /// Returns the children of a non-empty object or array, or `None` if `value` is a leaf.
+    fn of(value: &'a Value) -> Option<Self> {
+        match value {
+            Value::Object(map) if !map.is_empty() => Some(Children::Object(map.iter())),
+            Value::Array(array) if !array.is_empty() => {
+                Some(Children::Array(array.iter().enumerate()))
+            }
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Option<(PathToken, &'a Value)> {
+        match self {
+            Children::Object(iter) => iter
+                .next()
+                .map(|(key, child)| (PathToken::Key(key.clone()), child)),
+            Children::Array(iter) => iter
+                .next()
+                .map(|(index, child)| (PathToken::Index(index), child)),
+        }
+    }
+}
+
+impl<'a> Iterator for Leaves<'a> {
+    type Item = (Vec<PathToken>, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.root.take() {
+            match Children::of(root) {
+                Some(children) => self.stack.push(children),
+                None => return Some((Vec::new(), root)),
+            }
+        }
+
+        loop {
+            let Some((token, child)) = self.stack.last_mut()?.next() else {
+                // The container is done, so continue with its parent
+                self.stack.pop();
+                self.path.pop();
+                continue;
+            };
+            self.path.push(token);
+            match Children::of(child) {
+                Some(children) => self.stack.push(children),
+                None => {
+                    let tokens = self.path.clone();
+                    self.path.pop();
+                    return Some((tokens, child));
+                }
+            }
+        }
+    }
+}
+
+fn batch_error(index: usize, source: JqesqueError) -> JqesqueError {
+    JqesqueError::BatchError {
+        index,
+        source: Box::new(source),
+    }
+}
+
+/// Splits a batch input into its constituent assignment strings, without parsing them.
+///
+/// Assignments are separated by newlines or `;`. Separators inside quoted strings or inside JSON
+/// objects and arrays (e.g. `x={"a":";"}` or `'a;b'=1`) do not split. Single quotes only quote
+/// keys, so a `'` starts a quoted string only at the start of a key, before the `=` of the
+/// value: in `msg=rock 'n roll;b=2` and `name=O'Brien` it is taken literally. Each assignment
+/// is trimmed of surrounding whitespace, and empty assignments are skipped.
+///
+/// A `#` at the start of an assignment or after whitespace starts a comment, which runs to the
+/// end of the line and is dropped, so lines can be annotated (`foo=bar # set foo`) or commented
+/// out entirely. A `#` elsewhere, such as in `color=#fff`, in a quoted string (`'a #b'=1`) or in
+/// JSON, is kept.
+///
+/// ## Arguments
+///
+/// * `input` - The batch input to split
+///
+/// ## Returns
+///
+/// Returns the raw assignment strings, in input order.
+///
+/// ## Example
+///
+/// ```rust
+/// use jqesque::split_assignments;
+///
+/// let assignments = split_assignments("foo.bar=1; baz[0]=true\n# cleanup\n-old.key # unused");
+/// assert_eq!(assignments, vec!["foo.bar=1", "baz[0]=true", "-old.key"]);
+/// ```
+pub fn split_assignments(input: &str) -> Vec<&str> {
+    split_assignments_on(input, ';')
+}
+
+/// Splits a batch input on newlines and `delimiter`, with the same rules as `split_assignments`.
+pub(crate) fn split_assignments_on(input: &str, delimiter: char) -> Vec<&str> {
+    let mut assignments = Vec::new();
+
+    let mut start = 0;
+    let mut depth = 0usize;
+    // The quote character of the quoted string being read, if any
+    let mut in_quotes = None;
+    let mut escaped = false;
+    let mut in_comment = false;
+    // Whether the `=` before the value of the current assignment has been seen
+    let mut in_value = false;
+    let mut previous = None;
+
+    for (offset, c) in input.char_indices() {
+        let after_whitespace = previous.is_some_and(char::is_whitespace);
+        let after_alphanumeric = previous.is_some_and(char::is_alphanumeric);
+        previous = Some(c);
+
+        if in_comment {
+            if c == '\n' {
+                in_comment = false;
+                in_value = false;
+                start = offset + c.len_utf8();
+            }
+            continue;
+        }
+
+        if let Some(quote) = in_quotes {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if c == quote => in_quotes = None,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_quotes = Some(c),
+            '\'' if !in_value && !after_alphanumeric => in_quotes = Some(c),
+            // Not the `=` and `?=` operators, which come before the path
+            '=' if depth == 0 && !matches!(input[start..offset].trim_start(), "" | "?") => {
+                in_value = true;
+            }
+            '#' if depth == 0 && (after_whitespace || input[start..offset].trim().is_empty()) => {
+                assignments.push(&input[start..offset]);
+                in_comment = true;
+            }
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && (c == '\n' || c == delimiter) => {
+                assignments.push(&input[start..offset]);
+                in_value = false;
+                start = offset + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    if !in_comment {
+        assignments.push(&input[start..]);
+    }
+
+    assignments
+        .into_iter()
+        .map(str::trim)
+        .filter(|assignment| !assignment.is_empty())
+        .collect()
+}
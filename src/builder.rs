@@ -0,0 +1,113 @@
+use serde_json::Value;
+
+use crate::types::{Jqesque, JqesqueError, Operation, PathToken, Separator};
+
+/// A builder for constructing a `Jqesque` programmatically, without going through a string.
+///
+/// Keys are taken as-is, so they need no quoting or escaping even if they contain separators.
+/// The operation defaults to `Operation::Auto`, as for an assignment without an operator.
+///
+/// ```rust
+/// use jqesque::{JqesqueBuilder, Operation};
+/// use serde_json::json;
+///
+/// let jqesque = JqesqueBuilder::new()
+///     .key("servers")
+///     .index(0)
+///     .key("host.name")
+///     .value("example.com")
+///     .operation(Operation::Insert)
+///     .build()
+///     .unwrap();
+/// assert_eq!(jqesque.as_json(), json!({"servers": [{"host.name": "example.com"}]}));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JqesqueBuilder {
+    tokens: Vec<PathToken>,
+    value: Option<Value>,
+    from: Option<Vec<PathToken>>,
+    expected: Option<Value>,
+    operation: Option<Operation>,
+}
+
+impl JqesqueBuilder {
+    /// Creates a new builder with an empty path and no value.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an object key to the path.
+    pub fn key(mut self, key: &str) -> Self {
+        self.tokens.push(PathToken::Key(key.to_string()));
+        self
+    }
+
+    /// Appends an array index to the path.
+    pub fn index(mut self, index: usize) -> Self {
+        self.tokens.push(PathToken::Index(index));
+        self
+    }
+
+    /// Sets the value to assign.
+    pub fn value(mut self, value: impl Into<Value>) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+
+    /// Sets the path to take the value from, for `Operation::Copy` and `Operation::Move`.
+    pub fn from(mut self, from: Vec<PathToken>) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Sets the value the path must hold before it is replaced, for `Operation::TestReplace`.
+    pub fn expected(mut self, expected: impl Into<Value>) -> Self {
+        self.expected = Some(expected.into());
+        self
+    }
+
+    /// Sets the operation to perform. Defaults to `Operation::Auto`.
+    pub fn operation(mut self, operation: Operation) -> Self {
+        self.operation = Some(operation);
+        self
+    }
+
+    /// Builds the `Jqesque`.
+    ///
+    /// ## Returns
+    ///
+    /// Returns the `Jqesque`, a `MissingValueError` if the operation needs a value (i.e. is not
+    /// `Remove`, `Copy` or `Move`) and none was set, a `MissingFromError` for `Copy` and `Move`
+    /// without a path to take the value from, or a `MissingExpectedError` for `TestReplace`
+    /// without an expected value.
+    pub fn build(self) -> Result<Jqesque, JqesqueError> {
+        let operation = self.operation.unwrap_or(Operation::Auto);
+        let copies = matches!(operation, Operation::Copy | Operation::Move);
+        if copies && self.from.is_none() {
+            return Err(JqesqueError::MissingFromError(operation));
+        }
+        if self.value.is_none() && !copies && operation != Operation::Remove {
+            return Err(JqesqueError::MissingValueError(operation));
+        }
+        if self.expected.is_none() && operation == Operation::TestReplace {
+            return Err(JqesqueError::MissingExpectedError(operation));
+        }
+        // Copy and Move take their value from the document, so only the source path is kept
+        let value = self.value.filter(|_| !copies);
+        let from = self.from.filter(|_| copies);
+        let expected = self
+            .expected
+            .filter(|_| operation == Operation::TestReplace);
+
+        Ok(Jqesque {
+            tokens: self.tokens,
+            value,
+            operation,
+            required_type: None,
+            from,
+            expected,
+            separator: Separator::Dot,
+            pointer_cache: Default::default(),
+        })
+    }
+}
@@ -0,0 +1,26 @@
+use serde_json::Value;
+
+use crate::types::{ErrorSource, JqesqueError};
+
+/// Reads the value of an `@path` file value.
+///
+/// With type inference, the contents must be valid JSON. Without it, they are taken verbatim
+/// as a string, as an inline value would be.
+pub(crate) fn read_value(path: &str, infer_types: bool) -> Result<Value, JqesqueError> {
+    let contents = std::fs::read_to_string(path).map_err(|e| value_source_error(path, e))?;
+    if !infer_types {
+        return Ok(Value::String(contents));
+    }
+    serde_json::from_str(&contents).map_err(|e| value_source_error(path, e))
+}
+
+fn value_source_error(
+    path: &str,
+    error: impl std::error::Error + Send + Sync + 'static,
+) -> JqesqueError {
+    JqesqueError::ValueSourceError {
+        path: path.to_string(),
+        message: error.to_string(),
+        source: ErrorSource::new(error),
+    }
+}
@@ -0,0 +1,160 @@
+use serde_json::{Map, Value};
+
+use crate::manipulators::insert_value;
+use crate::options::DEFAULT_MAX_ARRAY_GROW;
+use crate::types::{Jqesque, JqesqueError, Operation};
+
+/// A JSON document that assignments can be applied to.
+///
+/// This abstracts over the JSON value type, so that documents need not be held as a
+/// `serde_json::Value`. It is implemented for `serde_json::Value`, which remains the type used
+/// by `Jqesque::apply_to` and friends.
+///
+/// Only inserting is generic: `insert_value` and `Jqesque::apply_to_json_like` take any
+/// `JsonLike` document, and the trait has just the methods they need. Merging and the JSON Patch
+/// operations still require a `serde_json::Value`.
+///
+/// The accessors for objects return `None` if the value is not an object, and the accessors for
+/// arrays return `None` if it is not an array. The mutators leave a value of the wrong type
+/// alone, so callers should replace it with `empty_object` or `empty_array` first.
+///
+/// ## Example
+///
+/// ```rust
+/// use jqesque::JsonLike;
+/// use serde_json::{json, Value};
+///
+/// let mut doc = Value::empty_object();
+/// doc.object_insert("list".to_string(), Value::empty_array());
+/// doc.object_get_mut("list").unwrap().array_push(Value::from_json(json!(1)));
+///
+/// assert_eq!(doc, json!({"list": [1]}));
+/// assert_eq!(doc.object_get("list").and_then(|list| list.array_len()), Some(1));
+/// ```
+pub trait JsonLike: Sized {
+    /// Returns a `null` value.
+    fn null() -> Self;
+
+    /// Returns an object without any keys.
+    fn empty_object() -> Self;
+
+    /// Returns an array without any elements.
+    fn empty_array() -> Self;
+
+    /// Converts a `serde_json::Value`, such as the value of an assignment, into this type.
+    fn from_json(value: Value) -> Self;
+
+    /// Returns whether the value is an object.
+    fn is_object(&self) -> bool;
+
+    /// Returns whether the value is an array.
+    fn is_array(&self) -> bool;
+
+    /// Returns the value of `key`, if the value is an object with that key.
+    fn object_get(&self, key: &str) -> Option<&Self>;
+
+    /// Returns the value of `key` mutably, if the value is an object with that key.
+    fn object_get_mut(&mut self, key: &str) -> Option<&mut Self>;
+
+    /// Sets `key` to `value` if the value is an object, returning the value it replaced.
+    fn object_insert(&mut self, key: String, value: Self) -> Option<Self>;
+
+    /// Returns the number of elements, if the value is an array.
+    fn array_len(&self) -> Option<usize>;
+
+    /// Returns the element at `index` mutably, if the value is an array that long.
+    fn array_get_mut(&mut self, index: usize) -> Option<&mut Self>;
+
+    /// Appends `value` if the value is an array.
+    fn array_push(&mut self, value: Self);
+}
+
+impl JsonLike for Value {
+    fn null() -> Self {
+        Value::Null
+    }
+
+    fn empty_object() -> Self {
+        Value::Object(Map::new())
+    }
+
+    fn empty_array() -> Self {
+        Value::Array(Vec::new())
+    }
+
+    fn from_json(value: Value) -> Self {
+        value
+    }
+
+    fn is_object(&self) -> bool {
+        Value::is_object(self)
+    }
+
+    fn is_array(&self) -> bool {
+        Value::is_array(self)
+    }
+
+    fn object_get(&self, key: &str) -> Option<&Self> {
+        self.as_object()?.get(key)
+    }
+
+    fn object_get_mut(&mut self, key: &str) -> Option<&mut Self> {
+        self.as_object_mut()?.get_mut(key)
+    }
+
+    fn object_insert(&mut self, key: String, value: Self) -> Option<Self> {
+        self.as_object_mut()?.insert(key, value)
+    }
+
+    fn array_len(&self) -> Option<usize> {
+        self.as_array().map(Vec::len)
+    }
+
+    fn array_get_mut(&mut self, index: usize) -> Option<&mut Self> {
+        self.as_array_mut()?.get_mut(index)
+    }
+
+    fn array_push(&mut self, value: Self) {
+        if let Some(array) = self.as_array_mut() {
+            array.push(value);
+        }
+    }
+}
+
+impl Jqesque {
+    /// Applies an `Insert` to a JSON document of any type implementing `JsonLike`.
+    ///
+    /// This behaves like `apply_to` does for an `Insert`, creating any missing objects and arrays
+    /// on the way to the path.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON document to apply the operation to
+    ///
+    /// ## Returns
+    ///
+    /// Returns the operation that was performed, an `UnsupportedDocumentOperationError` for
+    /// operations other than `Insert`, or a JqesqueError if the insert fails.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Operation};
+    /// use serde_json::json;
+    ///
+    /// let mut json_obj = json!({"foo": {}});
+    /// let jqesque = ">foo.bar[1]=true".parse::<Jqesque>().unwrap();
+    ///
+    /// assert_eq!(jqesque.apply_to_json_like(&mut json_obj).unwrap(), Operation::Insert);
+    /// assert_eq!(json_obj, json!({"foo": {"bar": [null, true]}}));
+    /// ```
+    pub fn apply_to_json_like<J: JsonLike>(&self, json: &mut J) -> Result<Operation, JqesqueError> {
+        if self.operation != Operation::Insert {
+            return Err(JqesqueError::UnsupportedDocumentOperationError(
+                self.operation.clone(),
+            ));
+        }
+        insert_value(json, &self.tokens, &self.value, DEFAULT_MAX_ARRAY_GROW)?;
+        Ok(Operation::Insert)
+    }
+}
@@ -9,7 +9,7 @@
 //! - **Nested Objects:** Supports nested objects (e.g., `foo.bar.baz=true`).
 //! - **Arrays with Indices:** Supports arrays with indices (e.g., `foo[0].bar=zoot`, where the index must be a positive number).
 //! - **Boolean, Number, and Null Values:** Automatically parses values as booleans, numbers, or null if possible. By default, the value is a string unless serde can parse it as a boolean, number, or null.
-//! - **Custom Separators:** Scopes can be separated by `Separator::Dot` (`.`), `Separator::Slash` (`/`), or `Separator::Custom(char)` (custom character).
+//! - **Custom Separators:** Scopes can be separated by `Separator::Dot` (`.`), `Separator::Slash` (`/`), or `Separator::Custom(char)` (custom character), or `Separator::CustomStr(String)` (custom multi-character separator, e.g. `::` or `->`), or `Separator::AnyOf(Vec<char>)` to accept several at once (e.g. both `/` and `.`, so `a/b.c` is three keys). A separator can also be parsed from a string, e.g. `"/".parse::<Separator>()`.
 //!
 //! ## Syntax
 //!
@@ -19,20 +19,28 @@
 //! [<operation>]<path>=[<value>]
 //! ```
 //!
-//! - `<operation>`: An optional operation to perform. Supported operations are Add (+), Replace (=), Remove (-), Test (?), Insert (>), and Merge (~).
+//! - `<operation>`: An optional operation to perform. Supported operations are Add (+), Replace (=), Remove (-), Test (?), Copy (&), Move (@), Insert (>), Merge (~), Default (:), RemoveIf (-?), TestLen (?#), and TestReplace (?=).
 //! - `<path>`: The path to the JSON key. The path can be nested and can include array indices. The path can be separated by a dot (`.`), a slash (`/`), or a custom character.
 //! - `<value>`: A JSON value. Note that the Remove operation does not require a value.
 //!
+//! Whitespace around the assignment, around separators and around the `=` is ignored, so ` foo . bar = value ` is the same as `foo.bar=value`. Whitespace inside quoted keys and inside the value is kept.
+//!
 //! ### Operations
 //!
-//! Add, Remove, Replace, and Test operations are done as per the JSON Patch specification in [RFC6902](https://datatracker.ietf.org/doc/html/rfc6902/).
+//! Add, Remove, Replace, Test, Copy, and Move operations are done as per the JSON Patch specification in [RFC6902](https://datatracker.ietf.org/doc/html/rfc6902/).
 //!
 //! - **Add (+):** Adds a new key-value pair to the JSON structure. If the key already exists, the operation fails. If the key is an array index, the operation appends the value to the array.
 //! - **Remove (-):** Removes the key from the JSON structure.
 //! - **Replace (=):** Replaces the value of an existing key. If the key does not exist, the operation fails.
 //! - **Test (?):** Tests if the key-value pair exists in the JSON structure.
+//! - **Copy (&):** Copies the value at another path to the key, written as `&dest<source` (e.g. `&backup.theme<settings.theme`). It takes no value.
+//! - **Move (@):** Moves the value at another path to the key, written as `@dest<source` (e.g. `@settings.theme<theme`). It takes no value. Moving a value into one of its own descendants is an error.
 //! - **Insert (>):** Inserts a new key-value pair into the JSON structure. If the key already exists, the operation overwrites the value.
-//! - **Merge (~):** Preforms a deep merge of the value into the existing JSON structure. null values are preserved in the existing structure. Note that this behavior **differs** from from JSON Merge Patch defined in [RFC7396](https://datatracker.ietf.org/doc/html/rfc7396).
+//! - **Merge (~):** Preforms a deep merge of the value into the existing JSON structure. null values are preserved in the existing structure. Note that this behavior **differs** from from JSON Merge Patch defined in [RFC7396](https://datatracker.ietf.org/doc/html/rfc7396). See `MergeStrategy` for how incoming null values can be handled (including RFC7396 semantics, where null deletes the key) or strings appended to existing strings, and `ArrayMergeStrategy` for how arrays are combined (by index by default).
+//! - **Default (:):** Sets the value only if the key does not exist yet, e.g. `:settings.theme=light`. An existing value, including `null`, is left untouched, and this is not an error. Missing parents are created, as with Insert.
+//! - **RemoveIf (-?):** Removes the key only if its value equals the given value, e.g. `-?lock.owner=me`. If the value differs, the operation fails with a `TestFailedError` and the document is left unchanged, which makes it a safe compare-and-delete.
+//! - **TestLen (?#):** Tests the number of elements of the array or object at the path, e.g. `?#items=3`. A different length fails with a `TestFailedError`, and a scalar fails with an `InvalidPathError`.
+//! - **TestReplace (?=):** Replaces the value only if it currently equals the expected value, written between `=` and `=>`, e.g. `?=config.version=3=>4`. If the value differs, the operation fails with a `TestFailedError` and the document is left unchanged, which makes it a safe compare-and-swap. A quoted expected value may itself contain `=>`, e.g. `?=arrow="a=>b"=>c`.
 //!
 //! For more information, see the Operation enum itself.
 //!
@@ -40,9 +48,45 @@
 //!
 //! Paths can be nested and can include array indices. The path can be separated by a dot (`.`), a slash (`/`), or a custom character.
 //!
+//! Keys containing the separator or other special characters can be quoted with double or single quotes, e.g. `"key.with.dots"=1` or `'key with spaces'=value`. Inside the quotes, a backslash escapes the enclosing quote character (`'it\'s'`). A `\uXXXX` escape gives the Unicode character with that hexadecimal code, as in JSON, so `"caf\u00e9"=1` sets the key `café`; characters beyond `\uFFFF` are written as a surrogate pair such as `\ud83d\ude00`, and a lone surrogate is a parse error. A quoted key can also be written in brackets, JSONPath style, and mixed with indices: `foo["bar.baz"][0]=1`. To build an assignment from keys that may contain any of these, `jqesque::quote_key` quotes and escapes a key only where needed.
+//!
+//! For a key that only contains the separator, the separator can instead be escaped with a backslash: `a\.b.c=1` sets `c` in the key `a.b`. Use `\\` for a literal backslash. A key may also start with an escaped operator character, so that it is not taken as the operation: `\+weird=1` sets the key `+weird` (with `Auto`), and `>a.\~b=1` the key `~b` in `a`. Escaping any other character is an error.
+//!
+//! Array indices can also be relative to the length of the array at the time the assignment is applied: `[#]` refers to one past the last element (so `>log[#]=entry` appends), and `[#-1]` refers to the last element. Negative indices are shorthand for the latter form, so `-arr[-1]` removes the last element. An `Insert` or `Merge` with a negative index into an empty or missing array fails, as there is no element to refer to.
+//!
+//! The JSON Pointer append token `-` can be used as a path segment (e.g. `>log/-=entry` or `>log.-=entry`, or equivalently `>log[-]=entry` or `>log[]=entry`) to push onto the array with any operation. In the middle of a path, it pushes a new element to continue into: `>a[].b=1` appends `{"b": 1}` to `a`. Quote it (`"-"`) to use it as an object key instead.
+//!
+//! A numeric key written with the separator, such as `years.2024`, is an object key, while `years[2024]` is an array index. For documents with numeric object keys, `ParseOptions::force_object_keys` makes bracketed indices object keys as well.
+//!
+//! The separator on its own is the path to the document root, so `.=[1,2,3]` replaces the whole document and `~.={"a":1}` merges into it. The root cannot be removed.
+//!
+//! `Remove` and `Test` paths may contain the wildcard `*` (or `[*]`), which matches every key of an object or element of an array: `-users[*].temp` removes `temp` from each user that has it, and `?flags.*=true` tests that every flag is `true`. The operation is applied to each existing path that matches. A `Test` fails if any match differs or if nothing matches, while a `Remove` with no matches leaves the document unchanged. Other operations need a concrete target, so wildcards in their paths are an error.
+//!
+//! A path can optionally end with a type annotation, e.g. `count:int=5` or `name:str=bob`. The value must then be of the given type (`str`, `int`, `float`, `bool`, `null`, `array` or `object`) when the assignment is applied, or the operation fails. `float` accepts any number.
+//!
 //! ### Values
 //!
-//! Values are parsed as by serde_json. The library will attempt to parse the value as a JSON value, defaulting to string.
+//! Values are parsed as by serde_json. The library will attempt to parse the value as a JSON value, defaulting to string. To take every value literally as a string instead (e.g. for version numbers or zip codes with leading zeros), disable type inference with `ParseOptions::new().infer_types(false)`. A number may carry a leading plus sign, which JSON itself rejects: `delta=+5` stores the number `5`, while `delta=+5x` is not a number and stores the string `"+5x"`. Only the value is affected; a `+` at the start of the assignment is still the Add operation.
+//!
+//! A value that is a complete JSON string literal is decoded, so `x="a b"` stores `a b` and `name="O'Brien"` stores `O'Brien`, with JSON escapes such as `\n` and `\"` resolved. Quoting is optional: the bare `x=a b` stores `a b` as well, as a value that is not valid JSON is taken as is. Anything else containing quotes is therefore kept verbatim, quotes included: `x="a b` (unterminated), `x="a" b` (text after the closing quote) and `x='a b'` (single quotes are not JSON) all store the text exactly as written. With type inference disabled, quotes are never stripped. To catch typos such as `data={"a":}`, `ParseOptions::strict_values` makes a value that starts like JSON (with `{`, `[`, `"` or a digit) but fails to parse an `InvalidJsonValueError` instead.
+//!
+//! The value is everything after the first `=` following the path, taken verbatim whatever the separator is. It may contain the separator, further `=` signs or brackets, so `url=/api/v1` with `Separator::Slash` stores `"/api/v1"` and `query=a=b` stores `"a=b"`. A key that itself contains `=` must be quoted.
+//!
+//! To require the value to be JSON, assign it with `:=` instead of `=`: `x:=[1,2,3]` stores the array as with `=`, but `x:=notjson` is a parse error instead of the string `"notjson"`. Values assigned with `:=` are always parsed as JSON, even when type inference is disabled.
+//!
+//! A value can also be prefixed with its type to coerce it, e.g. `port=(int)8080`, `flag=(bool)yes` or `name=(str)1234`. The supported types are `str` (the text verbatim), `int`, `float`, `bool` (`true`, `false`, `yes`, `no`, `on`, `off`, `1` or `0`, in any case), `null` (`null` or nothing) and `json` (any JSON value). Text that cannot be coerced, such as `(int)abc`, is a parse error. Without a prefix a number keeps the flavor serde_json gives it, so `n=3` is an integer and `pi=3.0` a float; `(float)3` forces the float `3.0`, and `(int)3.0` or `(int)1e3` the integers `3` and `1000`. A fractional part is never truncated: `(int)1.5` is a parse error. Other parenthesized text, like `(hello)`, is an ordinary value. Unlike a type annotation on the path, which checks the value when it is applied, the prefix decides how the value is parsed.
+//!
+//! Multiline strings, such as certificates, can be given as a heredoc: `<<TAG`, a line break, the text, and a final line holding only `TAG`, which must end the input. `TAG` is made of alphanumeric characters and `_`. The text between the two line breaks is stored verbatim as a string, without type inference, so it may contain `=`, quotes and newlines. For example `cert=<<EOF\nline one\nline two\nEOF` stores `"line one\nline two"`. Since batches are split on newlines, heredocs are only supported when parsing single assignments.
+//!
+//! With the `files` feature enabled and `ParseOptions::allow_files` set, a value of the form `@path/to/file.json` is read from the named file, e.g. `>settings=@defaults.json`. The contents must be valid JSON, or are taken verbatim as a string when type inference is disabled. Failing to read or parse the file results in a `JqesqueError::ValueSourceError` naming the path. Otherwise, `@path` is an ordinary value, so parsing untrusted input never reads files unless asked to.
+//!
+//! With the `yaml` feature enabled, `Jqesque::apply_to_yaml` applies an assignment to a `serde_yaml::Value` document, using the same paths, operations and value inference as for JSON, and `Jqesque::to_yaml_string` renders the document produced by `as_json` as YAML.
+//!
+//! `Jqesque` implements `Serialize` and `Deserialize` structurally, as its tokens, value and operation. To keep configuration files readable, a field can instead be stored as its assignment string, such as `"foo.bar[0]=true"`, with `#[serde(with = "jqesque::serde_string")]`.
+//!
+//! Since numbers round-trip through serde_json, their formatting is normalized by default: `key=1.50` stores `1.5` and `key=1e3` stores `1000.0`. Enable the `arbitrary_precision` feature (which enables serde_json's feature of the same name) to keep the original numeric lexeme, so the exact text is preserved when the document is serialized again. Note that this also affects equality: with the feature enabled, `1.50` and `1.5` are different numbers.
+//!
+//! To limit the work an assignment from an untrusted source can cause, a path may have at most 128 tokens by default, and longer ones fail with an `InputTooComplexError`. The limit is set with `ParseOptions::max_depth`, and `ParseOptions::max_input_length` rejects inputs longer than a number of bytes with an `InputTooLongError` before parsing them. There is no length limit by default, so set one for untrusted input, as values are read in full.
 //!
 //! ## Examples
 //!
@@ -92,6 +136,8 @@
 //! }));
 //! ```
 //!
+//! The separator is kept, so formatting the `Jqesque` with `to_string` writes `>foo/bar[0]/baz=true` again. `Jqesque::with_separator` changes it, e.g. to print the path with dots instead.
+//!
 //! ### Inserting into an existing JSON structure
 //!
 //! ```rust
@@ -134,6 +180,7 @@
 //!
 //! If no operator is specified, the library will first try to perform a `Replace` operation. If this fails,
 //! it will attempt an `Add` operation. If this still fails, it will attempt an `Insert` operation.
+//! The operations tried, and their order, can be changed with `ApplyOptions::auto_order`.
 //!
 //! ```rust
 //! use serde_json::json;
@@ -172,8 +219,36 @@
 //!
 //! See the [LICENSE](LICENSE) file for details.
 
+mod audit;
+mod batch;
+mod builder;
+#[cfg(feature = "files")]
+mod files;
+mod json_like;
+mod lint;
 mod manipulators;
+mod options;
 mod parse;
+pub mod parser;
+mod patch;
+mod pointer;
+pub mod serde_string;
+mod spec;
 mod types;
+#[cfg(feature = "yaml")]
+mod yaml;
 
-pub use types::{Jqesque, JqesqueError, Operation, PathToken, Separator};
+pub use audit::{ApplyReport, AuditEntry, NodeEvent};
+pub use batch::split_assignments;
+pub use builder::JqesqueBuilder;
+pub use json_like::JsonLike;
+pub use lint::{lint_separators, normalize_separators, SeparatorWarning};
+pub use options::{
+    ApplyOptions, ArrayMergeStrategy, KeyOrder, MergeOptions, MergeStrategy, MissingPolicy,
+    ParseOptions,
+};
+pub use pointer::{tokens_from_pointer, tokens_to_pointer};
+pub use types::{
+    quote_key, ApplyOutcome, ErrorSource, Jqesque, JqesqueError, Operation, PathToken, Separator,
+    ValueType,
+};
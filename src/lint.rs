@@ -0,0 +1,155 @@
+use crate::types::{Operation, Separator};
+
+/// The separators the linter knows about, in addition to the one in use.
+const KNOWN_SEPARATORS: &[char] = &['.', '/'];
+
+/// A warning about a separator other than the active one appearing in the path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeparatorWarning {
+    /// The zero-based index of the path token (as written) containing the foreign separator
+    pub token_index: usize,
+    /// The byte offset of the foreign separator in the input
+    pub offset: usize,
+    /// The foreign separator that was found
+    pub found: char,
+}
+
+/// Detects separators other than the active one in the path portion of an input string.
+///
+/// A common copy-paste mistake is concatenating paths from different sources, e.g. `a.b/c` while
+/// parsing with `Separator::Dot`. This scans the path (up to the first unquoted `=`) for the known
+/// separators `.` and `/` that are not the active separator, ignoring anything inside quoted keys.
+///
+/// ## Arguments
+///
+/// * `input` - The input string to lint
+/// * `separator` - The separator the input is meant to use
+///
+/// ## Returns
+///
+/// Returns a `SeparatorWarning` for each foreign separator found, in input order.
+///
+/// ## Example
+///
+/// ```rust
+/// use jqesque::{lint_separators, Separator};
+///
+/// let warnings = lint_separators("a.b/c=1", Separator::Dot);
+/// assert_eq!(warnings.len(), 1);
+/// assert_eq!(warnings[0].token_index, 1);
+/// assert_eq!(warnings[0].found, '/');
+/// ```
+pub fn lint_separators(input: &str, separator: Separator) -> Vec<SeparatorWarning> {
+    let mut warnings = Vec::new();
+    scan_path(input, &separator, |token_index, offset, found| {
+        warnings.push(SeparatorWarning {
+            token_index,
+            offset,
+            found,
+        })
+    });
+    warnings
+}
+
+/// Rewrites foreign separators in the path portion of an input string to the active separator.
+///
+/// This is the opt-in fix for the problems reported by `lint_separators`. Quoted keys and the
+/// value are left untouched.
+///
+/// ## Arguments
+///
+/// * `input` - The input string to normalize
+/// * `separator` - The separator the input is meant to use
+///
+/// ## Returns
+///
+/// Returns the normalized input string.
+///
+/// ## Example
+///
+/// ```rust
+/// use jqesque::{normalize_separators, Separator};
+///
+/// assert_eq!(normalize_separators("a.b/c=1/2", Separator::Dot), "a.b.c=1/2");
+/// ```
+pub fn normalize_separators(input: &str, separator: Separator) -> String {
+    let mut normalized = input.to_string();
+    let sep = separator.as_str();
+
+    let mut offsets = Vec::new();
+    scan_path(input, &separator, |_, offset, _| offsets.push(offset));
+
+    // Replace from the back so earlier offsets stay valid
+    for offset in offsets.into_iter().rev() {
+        normalized.replace_range(offset..offset + 1, &sep);
+    }
+    normalized
+}
+
+/// Walks the path portion of the input, calling `on_foreign` for each foreign separator found.
+fn scan_path(input: &str, separator: &Separator, mut on_foreign: impl FnMut(usize, usize, char)) {
+    let alternatives = separator.alternatives();
+    // The active separator (one of the alternatives) that `text` starts with, if any
+    let active = |text: &str| {
+        alternatives
+            .iter()
+            .find(|alternative| !alternative.is_empty() && text.starts_with(alternative.as_str()))
+            .map(String::len)
+    };
+    // The end of the active separator being skipped over, if any
+    let mut skip_until = 0;
+
+    let mut chars = input.char_indices().peekable();
+    if let Some((_, c)) = chars.peek() {
+        if Operation::from_operator(*c).is_some() {
+            chars.next();
+        }
+    }
+
+    let mut token_index = 0;
+    let mut at_token_start = true;
+    let mut in_quotes: Option<char> = None;
+    let mut escaped = false;
+
+    for (offset, c) in chars {
+        if offset < skip_until {
+            continue;
+        }
+        if let Some(quote) = in_quotes {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if c == quote => in_quotes = None,
+                _ => {}
+            }
+            continue;
+        }
+
+        let separator_len = active(&input[offset..]);
+        match c {
+            '=' => break,
+            '"' | '\'' => in_quotes = Some(c),
+            // An escaped separator (or backslash) is part of the key
+            '\\' => {
+                let escaped = &input[offset + 1..];
+                skip_until = offset
+                    + 1
+                    + match (active(escaped), escaped.chars().next()) {
+                        (Some(len), _) => len,
+                        (None, Some(next)) => next.len_utf8(),
+                        (None, None) => 0,
+                    };
+            }
+            _ if separator_len.is_some() => {
+                skip_until = offset + separator_len.unwrap_or_default();
+                token_index += 1;
+                at_token_start = true;
+                continue;
+            }
+            '[' if !at_token_start => token_index += 1,
+            _ if KNOWN_SEPARATORS.contains(&c) => on_foreign(token_index, offset, c),
+            _ => {}
+        }
+        at_token_start = false;
+    }
+}
@@ -1,47 +1,507 @@
-use std::borrow::BorrowMut;
+use std::borrow::{BorrowMut, Cow};
 
-use crate::types::PathToken;
+use crate::audit::NodeEvent;
+use crate::json_like::JsonLike;
+use crate::options::{ArrayMergeStrategy, MergeOptions, MergeStrategy};
+use crate::types::{JqesqueError, PathToken};
+use jsonptr::{Pointer, PointerBuf, Token};
 use serde_json::{Map, Value};
 
+/// A callback told about every node a mutation walks through, creates or overwrites.
+pub(crate) type Observer<'o> = dyn FnMut(&Pointer, NodeEvent) + 'o;
+
 /// Inserts a value into the JSON object at the specified path tokens.
 ///
+/// Length-relative indices are resolved against the array found at that point, treating a
+/// missing array as empty. `PathToken::Append` pushes a new element onto the array.
+///
 /// # Arguments
 ///
 /// * `json_obj` - The JSON object to insert into.
 /// * `tokens` - The path tokens representing where to insert.
 /// * `value` - The value to insert.
-pub fn insert_value(json_obj: &mut Value, tokens: &[PathToken], value: &Option<Value>) {
-    let value = value.as_ref().unwrap_or(&Value::Null);
+/// * `max_array_grow` - The most elements an array may be extended by to reach an index.
+///
+/// # Errors
+///
+/// Returns `JqesqueError::RelativeIndexError` if a length-relative index falls before the start
+/// of its array, and `JqesqueError::IndexTooLargeError` if reaching an index would extend an
+/// array by more than `max_array_grow` elements.
+pub fn insert_value<J: JsonLike>(
+    json_obj: &mut J,
+    tokens: &[PathToken],
+    value: &Option<Value>,
+    max_array_grow: usize,
+) -> Result<(), JqesqueError> {
+    insert_value_observed(json_obj, tokens, value, max_array_grow, &mut |_, _| {})
+}
 
-    if tokens.is_empty() {
-        *json_obj = value.clone();
-        return;
-    }
+/// Inserts a value as `insert_value` does, telling `observer` about every node on the way.
+pub(crate) fn insert_value_observed<J: JsonLike>(
+    json_obj: &mut J,
+    tokens: &[PathToken],
+    value: &Option<Value>,
+    max_array_grow: usize,
+    observer: &mut Observer,
+) -> Result<(), JqesqueError> {
+    let mut pointer = PointerBuf::new();
+    let (entry, created) = entry_mut(
+        json_obj,
+        tokens,
+        max_array_grow,
+        &mut pointer,
+        false,
+        observer,
+    )?;
+    observer(
+        &pointer,
+        if created {
+            NodeEvent::Created
+        } else {
+            NodeEvent::Overwritten
+        },
+    );
+    *entry = J::from_json(value.clone().unwrap_or(Value::Null));
+    Ok(())
+}
 
-    match &tokens[0] {
+/// Returns the value at the specified path tokens, creating it as `insert_value` does.
+///
+/// A value that did not exist before is `Value::Null`. Values in the way of the path that are not
+/// of the right type (an object for keys, an array for indices) are replaced. `observer` is told
+/// about every object or array on the way, including the `null` elements an array is padded with.
+///
+/// # Arguments
+///
+/// * `json_obj` - The JSON object to walk.
+/// * `tokens` - The path tokens to walk.
+/// * `max_array_grow` - The most elements an array may be extended by to reach an index.
+/// * `pointer` - The location of `json_obj` on entry, and of the returned value on return.
+/// * `created` - Whether `json_obj` was just created.
+/// * `observer` - The callback to tell about each node on the way.
+///
+/// Along with the value, returns whether it was just created. The value itself is not reported
+/// to `observer`.
+///
+/// # Errors
+///
+/// Returns `JqesqueError::RelativeIndexError` if a length-relative index falls before the start
+/// of its array, and `JqesqueError::IndexTooLargeError` if reaching an index would extend an
+/// array by more than `max_array_grow` elements.
+pub(crate) fn entry_mut<'a, J: JsonLike>(
+    json_obj: &'a mut J,
+    tokens: &[PathToken],
+    max_array_grow: usize,
+    pointer: &mut PointerBuf,
+    created: bool,
+    observer: &mut Observer,
+) -> Result<(&'a mut J, bool), JqesqueError> {
+    let Some(token) = tokens.first() else {
+        return Ok((json_obj, created));
+    };
+
+    // The node at `pointer` is a container of the right type, or is about to become one
+    let is_container = match token {
+        PathToken::Key(_) => json_obj.is_object(),
+        PathToken::Wildcard => true,
+        _ => json_obj.is_array(),
+    };
+    let event = match (created, is_container) {
+        (true, _) => NodeEvent::Created,
+        (false, true) => NodeEvent::Visited,
+        (false, false) => NodeEvent::Overwritten,
+    };
+
+    match token {
         PathToken::Key(key) => {
+            observer(pointer, event);
             if !json_obj.is_object() {
-                *json_obj = Value::Object(Map::new());
+                *json_obj = J::empty_object();
+            }
+            let created = json_obj.object_get(key).is_none();
+            if created {
+                json_obj.object_insert(key.clone(), J::null());
             }
-            let entry = json_obj
-                .as_object_mut()
-                .unwrap()
-                .entry(key.clone())
-                .or_insert(Value::Null);
-            insert_value(entry, &tokens[1..], &Some(value.clone()));
-        }
-        PathToken::Index(index) => {
+            let entry = json_obj.object_get_mut(key).unwrap();
+            pointer.push_back(Token::new(key));
+            entry_mut(
+                entry,
+                &tokens[1..],
+                max_array_grow,
+                pointer,
+                created,
+                observer,
+            )
+        }
+        PathToken::Wildcard => Err(JqesqueError::InvalidPathError(
+            "a wildcard cannot be used to create a value".to_string(),
+            None,
+        )),
+        PathToken::Index(_) | PathToken::LengthRelative(_) | PathToken::Append => {
+            observer(pointer, event);
             if !json_obj.is_array() {
-                *json_obj = Value::Array(vec![]);
+                *json_obj = J::empty_array();
             }
-            let array = json_obj.as_array_mut().unwrap();
-            // Extend the array if necessary
-            if *index >= array.len() {
-                array.resize(*index + 1, Value::Null);
+            let len = json_obj.array_len().unwrap();
+            let index = match token {
+                PathToken::LengthRelative(offset) => relative_index(len, *offset)?,
+                PathToken::Index(index) => *index,
+                PathToken::Append => len,
+                _ => unreachable!(),
+            };
+            // Extend the array if necessary, within the limit
+            if index >= len {
+                if index - len >= max_array_grow {
+                    return Err(JqesqueError::IndexTooLargeError {
+                        index,
+                        limit: max_array_grow,
+                    });
+                }
+                for padding in len..index {
+                    pointer.push_back(Token::new(padding.to_string()));
+                    observer(pointer, NodeEvent::Created);
+                    pointer.pop_back();
+                }
+            }
+            let created = index >= len;
+            for _ in len..=index {
+                json_obj.array_push(J::null());
+            }
+            pointer.push_back(Token::new(index.to_string()));
+            entry_mut(
+                json_obj.array_get_mut(index).unwrap(),
+                &tokens[1..],
+                max_array_grow,
+                pointer,
+                created,
+                observer,
+            )
+        }
+    }
+}
+
+/// Sorts the keys of every object in the JSON value, recursively.
+///
+/// Without serde_json's `preserve_order` feature, objects are always kept sorted and this has no
+/// visible effect; with it, objects keep their insertion order until sorted.
+pub fn sort_keys(json_obj: &mut Value) {
+    match json_obj {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = std::mem::take(map).into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (_, value) in entries.iter_mut() {
+                sort_keys(value);
+            }
+            *map = entries.into_iter().collect();
+        }
+        Value::Array(array) => array.iter_mut().for_each(sort_keys),
+        _ => {}
+    }
+}
+
+/// Resolves a length-relative index against an array of length `len`.
+///
+/// # Errors
+///
+/// Returns `JqesqueError::RelativeIndexError` if the resulting index is negative or overflows.
+pub fn relative_index(len: usize, offset: isize) -> Result<usize, JqesqueError> {
+    match (len as isize).checked_add(offset) {
+        Some(index) if index >= 0 => Ok(index as usize),
+        _ => Err(JqesqueError::RelativeIndexError { offset, len }),
+    }
+}
+
+/// Resolves any length-relative indices and append tokens in the path tokens against a JSON object.
+///
+/// Tokens without either are returned as-is. Otherwise, each length-relative index is replaced
+/// by the concrete index it refers to in the array at that point of the path, and each append
+/// token by the length of that array.
+///
+/// # Arguments
+///
+/// * `json_obj` - The JSON object to resolve against.
+/// * `tokens` - The path tokens to resolve.
+/// * `create_missing` - Whether a missing array counts as empty (as for `Insert` and `Merge`,
+///   which create it) rather than being an error (as for the JSON Patch operations).
+pub fn resolve_relative_tokens<'a>(
+    json_obj: &Value,
+    tokens: &'a [PathToken],
+    create_missing: bool,
+) -> Result<Cow<'a, [PathToken]>, JqesqueError> {
+    if !tokens
+        .iter()
+        .any(|token| matches!(token, PathToken::LengthRelative(_) | PathToken::Append))
+    {
+        return Ok(Cow::Borrowed(tokens));
+    }
+
+    let mut node = Some(json_obj);
+    let mut resolved = Vec::with_capacity(tokens.len());
+
+    for (position, token) in tokens.iter().enumerate() {
+        let token = match token {
+            PathToken::LengthRelative(_) | PathToken::Append => {
+                let len = match node.and_then(Value::as_array) {
+                    Some(array) => array.len(),
+                    None if create_missing => 0,
+                    None => {
+                        return Err(JqesqueError::InvalidPathError(
+                            format!(
+                                "no array to resolve length-relative index at token {}",
+                                position
+                            ),
+                            None,
+                        ))
+                    }
+                };
+                match token {
+                    PathToken::LengthRelative(offset) => {
+                        PathToken::Index(relative_index(len, *offset)?)
+                    }
+                    _ => PathToken::Index(len),
+                }
             }
-            insert_value(&mut array[*index], &tokens[1..], &Some(value.clone()));
+            token => token.clone(),
+        };
+
+        node = match (&token, node) {
+            (PathToken::Key(key), Some(value)) => value.get(key),
+            (PathToken::Index(index), Some(value)) => value.get(*index),
+            _ => None,
+        };
+        resolved.push(token);
+    }
+
+    Ok(Cow::Owned(resolved))
+}
+
+/// Replaces the keys in the path tokens with the keys of a JSON object they match ignoring ASCII
+/// case.
+///
+/// A key that exists exactly as written is kept. Matching stops at the first key that matches
+/// nothing, or at a token other than a key or an index, leaving the rest of the path as written.
+///
+/// # Arguments
+///
+/// * `json_obj` - The JSON object to match against.
+/// * `tokens` - The path tokens to match.
+///
+/// # Errors
+///
+/// Returns `JqesqueError::AmbiguousKeyError` if a key matches several keys of an object ignoring
+/// case, and none of them exactly.
+pub fn match_key_case<'a>(
+    json_obj: &Value,
+    tokens: &'a [PathToken],
+) -> Result<Cow<'a, [PathToken]>, JqesqueError> {
+    let mut node = json_obj;
+    let mut matched: Option<Vec<PathToken>> = None;
+
+    for (position, token) in tokens.iter().enumerate() {
+        let next = match (token, node) {
+            (PathToken::Key(key), Value::Object(map)) => match map.get_key_value(key) {
+                Some((_, child)) => Some(child),
+                None => {
+                    let candidates: Vec<(&String, &Value)> = map
+                        .iter()
+                        .filter(|(candidate, _)| candidate.eq_ignore_ascii_case(key))
+                        .collect();
+                    match candidates.as_slice() {
+                        [] => None,
+                        [(candidate, child)] => {
+                            let matched = matched.get_or_insert_with(|| tokens.to_vec());
+                            matched[position] = PathToken::Key((*candidate).clone());
+                            Some(*child)
+                        }
+                        _ => {
+                            return Err(JqesqueError::AmbiguousKeyError {
+                                key: key.clone(),
+                                matches: candidates
+                                    .into_iter()
+                                    .map(|(candidate, _)| candidate.clone())
+                                    .collect(),
+                            })
+                        }
+                    }
+                }
+            },
+            (PathToken::Index(index), Value::Array(array)) => array.get(*index),
+            _ => None,
+        };
+        match next {
+            Some(child) => node = child,
+            None => break,
         }
     }
+
+    Ok(match matched {
+        Some(tokens) => Cow::Owned(tokens),
+        None => Cow::Borrowed(tokens),
+    })
+}
+
+/// Expands the wildcards in the path tokens into every existing path they match in a JSON object.
+///
+/// A wildcard matches each key of an object or each element of an array. Only paths that exist
+/// in full are returned, in document order, with length-relative indices resolved along the way.
+///
+/// # Arguments
+///
+/// * `json_obj` - The JSON object to match against.
+/// * `tokens` - The path tokens to expand.
+pub fn expand_wildcards(json_obj: &Value, tokens: &[PathToken]) -> Vec<Vec<PathToken>> {
+    let mut matches = Vec::new();
+    expand_into(json_obj, tokens, &mut Vec::new(), &mut matches);
+    matches
+}
+
+fn expand_into(
+    node: &Value,
+    tokens: &[PathToken],
+    prefix: &mut Vec<PathToken>,
+    matches: &mut Vec<Vec<PathToken>>,
+) {
+    let Some((token, rest)) = tokens.split_first() else {
+        matches.push(prefix.clone());
+        return;
+    };
+
+    let children: Vec<(PathToken, &Value)> = match (token, node) {
+        (PathToken::Wildcard, Value::Object(map)) => map
+            .iter()
+            .map(|(key, child)| (PathToken::Key(key.clone()), child))
+            .collect(),
+        (PathToken::Wildcard, Value::Array(array)) => array
+            .iter()
+            .enumerate()
+            .map(|(index, child)| (PathToken::Index(index), child))
+            .collect(),
+        (PathToken::Key(key), Value::Object(map)) => map
+            .get(key)
+            .map(|child| (token.clone(), child))
+            .into_iter()
+            .collect(),
+        (PathToken::Index(index), Value::Array(array)) => array
+            .get(*index)
+            .map(|child| (token.clone(), child))
+            .into_iter()
+            .collect(),
+        (PathToken::LengthRelative(offset), Value::Array(array)) => {
+            relative_index(array.len(), *offset)
+                .ok()
+                .and_then(|index| {
+                    array
+                        .get(index)
+                        .map(|child| (PathToken::Index(index), child))
+                })
+                .into_iter()
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    for (token, child) in children {
+        prefix.push(token);
+        expand_into(child, rest, prefix, matches);
+        prefix.pop();
+    }
+}
+
+/// Removes the key at the specified path tokens from its parent object, if it exists.
+///
+/// Nothing is removed if the path does not end in a key of an existing object.
+///
+/// # Arguments
+///
+/// * `json_obj` - The JSON object to remove from.
+/// * `tokens` - The path tokens to the key, with length-relative indices already resolved.
+pub fn remove_key(json_obj: &mut Value, tokens: &[PathToken]) {
+    let Some((PathToken::Key(key), parent_tokens)) = tokens.split_last() else {
+        return;
+    };
+
+    let mut node = json_obj;
+    for token in parent_tokens {
+        let next = match token {
+            PathToken::Key(key) => node.as_object_mut().and_then(|map| map.get_mut(key)),
+            PathToken::Index(index) => node.as_array_mut().and_then(|array| array.get_mut(*index)),
+            PathToken::LengthRelative(_) | PathToken::Append | PathToken::Wildcard => None,
+        };
+        match next {
+            Some(value) => node = value,
+            None => return,
+        }
+    }
+
+    if let Some(map) = node.as_object_mut() {
+        map.remove(key);
+    }
+}
+
+/// Finds the first path token that cannot be resolved against a JSON object.
+///
+/// # Arguments
+///
+/// * `json_obj` - The JSON object to resolve against.
+/// * `tokens` - The path tokens to resolve, with length-relative indices already resolved.
+///
+/// # Returns
+///
+/// Returns the zero-based index of the first token that does not exist, or `None` if the whole
+/// path resolves.
+pub fn first_unresolvable_token(json_obj: &Value, tokens: &[PathToken]) -> Option<usize> {
+    let mut node = json_obj;
+    for (position, token) in tokens.iter().enumerate() {
+        let next = match token {
+            PathToken::Key(key) => node.as_object().and_then(|map| map.get(key)),
+            PathToken::Index(index) => node.as_array().and_then(|array| array.get(*index)),
+            PathToken::LengthRelative(_) | PathToken::Append | PathToken::Wildcard => None,
+        };
+        match next {
+            Some(value) => node = value,
+            None => return Some(position),
+        }
+    }
+    None
+}
+
+/// Converts the scalars in a value to the types of the existing values they will overwrite.
+///
+/// A number or boolean overwriting a string becomes a string, and a string overwriting a number
+/// or boolean becomes one too if it is a valid number or `true`/`false`. Objects are converted key
+/// by key, recursively. Arrays, `null` and values that cannot be converted are left as they are.
+///
+/// # Arguments
+///
+/// * `existing` - The value that will be overwritten.
+/// * `value` - The value to convert.
+pub fn coerce_to_existing(existing: &Value, value: &mut Value) {
+    let coerced = match (existing, &mut *value) {
+        (Value::Object(existing), Value::Object(map)) => {
+            for (key, child) in map.iter_mut() {
+                if let Some(existing) = existing.get(key) {
+                    coerce_to_existing(existing, child);
+                }
+            }
+            None
+        }
+        (Value::String(_), Value::Number(number)) => Some(Value::String(number.to_string())),
+        (Value::String(_), Value::Bool(flag)) => Some(Value::String(flag.to_string())),
+        (Value::Number(_), Value::String(text)) => text
+            .trim()
+            .parse::<serde_json::Number>()
+            .ok()
+            .map(Value::Number),
+        (Value::Bool(_), Value::String(text)) => match text.as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        _ => None,
+    };
+    if let Some(coerced) = coerced {
+        *value = coerced;
+    }
 }
 
 /// Merges two JSON values.
@@ -50,24 +510,108 @@ pub fn insert_value(json_obj: &mut Value, tokens: &[PathToken], value: &Option<V
 ///
 /// * `a` - The original JSON value.
 /// * `b` - The new JSON value to merge in.
-pub fn merge_json(a: &mut Value, b: &mut Value) {
-    match (a.borrow_mut(), b) {
-        (Value::Object(a_map), Value::Object(b_map)) => {
-            for (k, v) in b_map.iter_mut() {
-                merge_json(a_map.entry(k.clone()).or_insert(Value::Null), v);
+/// * `options` - How to handle incoming `null` values and arrays.
+pub fn merge_json(a: &mut Value, b: &mut Value, options: &MergeOptions) {
+    merge_json_observed(a, b, options, &mut PointerBuf::new(), false, &mut |_, _| {});
+}
+
+/// Merges `b` into `a` as `merge_json` does, telling `observer` about every node merged into.
+///
+/// `pointer` is the location of `a`, and `created` says whether `a` was just created.
+pub(crate) fn merge_json_observed(
+    a: &mut Value,
+    b: &mut Value,
+    options: &MergeOptions,
+    pointer: &mut PointerBuf,
+    created: bool,
+    observer: &mut Observer,
+) {
+    let skip_null = options.strategy == MergeStrategy::SkipNull;
+    let delete_null = options.strategy == MergeStrategy::DeleteNull;
+    if skip_null && b.is_null() {
+        return;
+    }
+
+    // Objects are merged key by key, and so are arrays unless they are replaced wholesale.
+    // Dropping null leaves means merging an object into an empty one rather than taking it as-is.
+    let merge_objects = b.is_object() && (a.is_object() || skip_null || delete_null);
+    let merge_arrays = a.is_array() && b.is_array();
+    let event = if created {
+        NodeEvent::Created
+    } else if (merge_objects && a.is_object())
+        || (merge_arrays && options.arrays != ArrayMergeStrategy::Replace)
+    {
+        NodeEvent::Visited
+    } else {
+        NodeEvent::Overwritten
+    };
+    observer(pointer, event);
+
+    if merge_objects {
+        if !a.is_object() {
+            *a = Value::Object(Map::new());
+        }
+        let a_map = a.as_object_mut().unwrap();
+        for (k, v) in b.as_object_mut().unwrap().iter_mut() {
+            pointer.push_back(Token::new(k));
+            if delete_null && v.is_null() {
+                if a_map.remove(k).is_some() {
+                    observer(pointer, NodeEvent::Removed);
+                }
+            } else if !(skip_null && v.is_null()) {
+                let created = !a_map.contains_key(k);
+                let entry = a_map.entry(k.clone()).or_insert(Value::Null);
+                merge_json_observed(entry, v, options, pointer, created, observer);
+            }
+            pointer.pop_back();
+        }
+        return;
+    }
+
+    if !merge_arrays {
+        match (a, b) {
+            (Value::String(a_string), Value::String(b_string))
+                if options.strategy == MergeStrategy::StringConcat =>
+            {
+                a_string.push_str(b_string)
             }
+            (a, b) => *a = b.take(),
         }
-        (Value::Array(a_array), Value::Array(b_array)) => {
+        return;
+    }
+    let (Value::Array(a_array), Value::Array(b_array)) = (a.borrow_mut(), b) else {
+        unreachable!()
+    };
+    fn push(a_array: &mut Vec<Value>, v: Value, pointer: &mut PointerBuf, observer: &mut Observer) {
+        pointer.push_back(Token::new(a_array.len().to_string()));
+        observer(pointer, NodeEvent::Created);
+        pointer.pop_back();
+        a_array.push(v);
+    }
+    match options.arrays {
+        ArrayMergeStrategy::ByIndex => {
             for (i, v) in b_array.iter_mut().enumerate() {
                 if i < a_array.len() {
-                    merge_json(&mut a_array[i], v);
+                    pointer.push_back(Token::new(i.to_string()));
+                    merge_json_observed(&mut a_array[i], v, options, pointer, false, observer);
+                    pointer.pop_back();
                 } else {
-                    a_array.push(v.take());
+                    push(a_array, v.take(), pointer, observer);
                 }
             }
         }
-        (_, b_value) => {
-            *a = b_value.take();
+        ArrayMergeStrategy::Concat => {
+            for v in b_array.drain(..) {
+                push(a_array, v, pointer, observer);
+            }
+        }
+        ArrayMergeStrategy::Replace => *a_array = std::mem::take(b_array),
+        ArrayMergeStrategy::Union => {
+            for v in b_array.drain(..) {
+                if !a_array.contains(&v) {
+                    push(a_array, v, pointer, observer);
+                }
+            }
         }
     }
 }
@@ -75,6 +619,10 @@ pub fn merge_json(a: &mut Value, b: &mut Value) {
 mod test {
     #[allow(unused_imports)]
     use super::{insert_value, merge_json};
+    #[allow(unused_imports)]
+    use crate::options::DEFAULT_MAX_ARRAY_GROW;
+    #[allow(unused_imports)]
+    use crate::{ArrayMergeStrategy, MergeOptions, MergeStrategy};
     use serde_json::json;
     use yare::parameterized;
 
@@ -96,11 +644,73 @@ mod test {
     fn test_merge_json_ok(new_data: serde_json::Value, expected: serde_json::Value) {
         let mut json_obj = base_json();
         let mut new_data = new_data;
-        merge_json(&mut json_obj, &mut new_data);
+        merge_json(&mut json_obj, &mut new_data, &MergeOptions::default());
+
+        assert_eq!(json_obj, expected);
+    }
+
+    #[parameterized(
+        null_existing_key = { json!({"key": null}), json!({"key": "value"}) },
+        null_new_key = { json!({"key2": null}), json!({"key": "value"}) },
+        mixed = { json!({"key": null, "key2": "value2"}), json!({"key": "value", "key2": "value2"}) },
+        nested_null = { json!({"parent": {"child": null, "other": 1}}), json!({"key": "value", "parent": {"other": 1}}) },
+        top_level_null = { json!(null), json!({"key": "value"}) },
+    )]
+    fn test_merge_json_skip_null(new_data: serde_json::Value, expected: serde_json::Value) {
+        let mut json_obj = base_json();
+        let mut new_data = new_data;
+        let options = MergeOptions::new().strategy(MergeStrategy::SkipNull);
+        merge_json(&mut json_obj, &mut new_data, &options);
+
+        assert_eq!(json_obj, expected);
+    }
+
+    #[parameterized(
+        null_existing_key = { json!({"key": null}), json!({}) },
+        null_new_key = { json!({"key2": null}), json!({"key": "value"}) },
+        nested_null = { json!({"parent": {"child": null, "other": 1}}), json!({"key": "value", "parent": {"other": 1}}) },
+        array_null_kept = { json!({"array": [null]}), json!({"key": "value", "array": [null]}) },
+    )]
+    fn test_merge_json_delete_null(new_data: serde_json::Value, expected: serde_json::Value) {
+        let mut json_obj = base_json();
+        let mut new_data = new_data;
+        let options = MergeOptions::new().strategy(MergeStrategy::DeleteNull);
+        merge_json(&mut json_obj, &mut new_data, &options);
+
+        assert_eq!(json_obj, expected);
+    }
+
+    #[parameterized(
+        string_into_string = { json!({"key": " more"}), json!({"key": "value more"}) },
+        string_into_missing = { json!({"key2": "new"}), json!({"key": "value", "key2": "new"}) },
+        number_into_string = { json!({"key": 1}), json!({"key": 1}) },
+        null_into_string = { json!({"key": null}), json!({"key": null}) },
+        top_level_string = { json!("text"), json!("text") },
+    )]
+    fn test_merge_json_string_concat(new_data: serde_json::Value, expected: serde_json::Value) {
+        let mut json_obj = base_json();
+        let mut new_data = new_data;
+        let options = MergeOptions::new().strategy(MergeStrategy::StringConcat);
+        merge_json(&mut json_obj, &mut new_data, &options);
 
         assert_eq!(json_obj, expected);
     }
 
+    #[parameterized(
+        by_index = { ArrayMergeStrategy::ByIndex, json!([2, 3]) },
+        concat = { ArrayMergeStrategy::Concat, json!([1, 2, 2, 3]) },
+        replace = { ArrayMergeStrategy::Replace, json!([2, 3]) },
+        union = { ArrayMergeStrategy::Union, json!([1, 2, 3]) },
+    )]
+    fn test_merge_json_arrays(arrays: ArrayMergeStrategy, expected: serde_json::Value) {
+        let mut json_obj = json!({"array": [1, 2]});
+        let mut new_data = json!({"array": [2, 3]});
+        let options = MergeOptions::new().arrays(arrays);
+        merge_json(&mut json_obj, &mut new_data, &options);
+
+        assert_eq!(json_obj, json!({"array": expected}));
+    }
+
     #[parameterized(
         empty_path = { vec![], json!("value"), json!("value") },
         single_key = { vec!["key"], json!("value"), json!({"key": "value"}) },
@@ -117,18 +727,33 @@ mod test {
             .map(|s| s.to_string())
             .map(PathToken::Key)
             .collect();
-        insert_value(&mut json_obj, &tokens, &Some(value));
+        insert_value(&mut json_obj, &tokens, &Some(value), DEFAULT_MAX_ARRAY_GROW).unwrap();
 
         assert_eq!(json_obj, expected);
     }
 
+    #[allow(dead_code)]
+    fn parse_error(offset: usize, column: usize, message: &str) -> JqesqueError {
+        JqesqueError::ParseError {
+            offset,
+            column,
+            message: message.to_string(),
+        }
+    }
+
     #[parameterized(
-    negative_index = { "arr[-1]=value", Separator::Dot, JqesqueError::NomError("Parsing Error: VerboseError { errors: [(\"[-1]=value\", Char('='))] }".to_string()) },
-    invalid_index = { "arr[invalid]=value", Separator::Dot, JqesqueError::NomError("Parsing Error: VerboseError { errors: [(\"[invalid]=value\", Char('='))] }".to_string())}, 
-    missing_value = { "key=", Separator::Dot, JqesqueError::NomError("Parsing Error: VerboseError { errors: [(\"\", Nom(IsNot))] }".to_string()) },
-    missing_key = { "=value", Separator::Dot, JqesqueError::NomError("Parsing Error: VerboseError { errors: [(\"\", Char('='))] }".to_string()) },
-    missing_assignment = { "key", Separator::Dot, JqesqueError::NomError("Parsing Error: VerboseError { errors: [(\"\", Char('='))] }".to_string()) },
-    illegal_operator = { "!key=value", Separator::Dot, JqesqueError::NomError("Parsing Error: VerboseError { errors: [(\"!key=value\", Nom(TakeWhile1)), (\"!key=value\", Nom(Alt)), (\"!key=value\", Nom(Alt))] }".to_string()) },
+    zero_negative_index = { "arr[-0]=value", Separator::Dot, parse_error(3, 4, "expected '=' at column 4") },
+    invalid_index = { "arr[invalid]=value", Separator::Dot, parse_error(3, 4, "expected '=' at column 4") },
+    missing_value = { "key=", Separator::Dot, parse_error(4, 5, "expected a value at column 5") },
+    missing_key = { "=value", Separator::Dot, parse_error(6, 7, "expected '=' at column 7") },
+    missing_assignment = { "key", Separator::Dot, parse_error(3, 4, "expected '=' at column 4") },
+    illegal_operator = { "!key=value", Separator::Dot, parse_error(0, 1, "expected a key at column 1") },
+    double_separator = { "key..subkey=value", Separator::Dot, parse_error(4, 5, "expected a key at column 5") },
+    escaped_non_separator = { "a\\x.b=1", Separator::Dot, parse_error(1, 2, "expected an escaped separator or backslash at column 2") },
+    escaped_foreign_separator = { "a\\.b/c=1", Separator::Slash, parse_error(1, 2, "expected an escaped separator or backslash at column 2") },
+    unterminated_heredoc = { "a=<<EOF\nabc", Separator::Dot, parse_error(8, 9, "expected a heredoc terminator at column 9") },
+    heredoc_terminator_not_alone = { "a=<<EOF\nabcEOF", Separator::Dot, parse_error(8, 9, "expected a heredoc terminator at column 9") },
+    trailing_backslash = { "a\\=1", Separator::Dot, parse_error(1, 2, "expected an escaped separator or backslash at column 2") },
 )]
     fn test_parse_input_err(input: &str, separator: Separator, expected: JqesqueError) {
         let result = Jqesque::from_str_with_separator(input, separator);
@@ -137,7 +762,13 @@ mod test {
             Ok(_) => {
                 let parsed = result.unwrap();
                 let mut json_obj = serde_json::Value::Null;
-                insert_value(&mut json_obj, parsed.tokens(), parsed.value());
+                insert_value(
+                    &mut json_obj,
+                    parsed.tokens(),
+                    parsed.value(),
+                    DEFAULT_MAX_ARRAY_GROW,
+                )
+                .unwrap();
                 panic!(
                     "Expected an error, but got Ok (tokens: {:?} -> json_obj: {})",
                     parsed.tokens(),
@@ -0,0 +1,457 @@
+use crate::types::{Operation, Separator};
+
+/// The default for `ApplyOptions::max_array_grow`.
+pub(crate) const DEFAULT_MAX_ARRAY_GROW: usize = 100_000;
+
+/// The default for `ParseOptions::max_depth`, the nesting limit serde_json uses for its own input.
+pub(crate) const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Options controlling how a `Jqesque` is applied to a JSON object.
+///
+/// Construct with `ApplyOptions::new()` (or `ApplyOptions::default()`) and chain the setters:
+///
+/// ```rust
+/// use jqesque::ApplyOptions;
+///
+/// let options = ApplyOptions::new().remove_if_exists(true);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApplyOptions {
+    pub(crate) remove_if_exists: bool,
+    pub(crate) merge: MergeOptions,
+    pub(crate) auto_order: Vec<Operation>,
+    pub(crate) max_array_grow: usize,
+    pub(crate) key_order: KeyOrder,
+    pub(crate) case_insensitive_keys: bool,
+    pub(crate) coerce_to_existing_type: bool,
+    pub(crate) on_missing: MissingPolicy,
+}
+
+impl Default for ApplyOptions {
+    fn default() -> Self {
+        Self {
+            remove_if_exists: false,
+            merge: MergeOptions::default(),
+            auto_order: vec![Operation::Replace, Operation::Add, Operation::Insert],
+            max_array_grow: DEFAULT_MAX_ARRAY_GROW,
+            key_order: KeyOrder::default(),
+            case_insensitive_keys: false,
+            coerce_to_existing_type: false,
+            on_missing: MissingPolicy::default(),
+        }
+    }
+}
+
+impl ApplyOptions {
+    /// Creates a new set of options with the default behavior.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes `Remove` succeed without changes if the path does not exist, instead of failing.
+    ///
+    /// The outcome of such a removal is `ApplyOutcome::Unchanged(Operation::Remove)`. This makes
+    /// cleanup scripts safely re-runnable. Defaults to `false`.
+    pub fn remove_if_exists(mut self, remove_if_exists: bool) -> Self {
+        self.remove_if_exists = remove_if_exists;
+        self
+    }
+
+    /// Sets the options used by `Merge`. Defaults to `MergeOptions::default()`.
+    pub fn merge(mut self, merge: MergeOptions) -> Self {
+        self.merge = merge;
+        self
+    }
+
+    /// Sets the operations `Auto` tries, in order, until one succeeds.
+    ///
+    /// Only `Replace`, `Add`, `Insert` and `Merge` may be used, and at least one is required;
+    /// otherwise applying an `Auto` assignment fails with an `InvalidAutoOrderError`. If every
+    /// operation fails, the error of the last one is returned. Defaults to `Replace`, `Add`,
+    /// `Insert`.
+    ///
+    /// ```rust
+    /// use jqesque::{ApplyOptions, Jqesque, Operation};
+    /// use serde_json::json;
+    ///
+    /// // Merge objects into existing values, rather than replacing them
+    /// let options = ApplyOptions::new().auto_order([Operation::Merge]);
+    /// let mut json_obj = json!({"foo": {"a": 1}});
+    ///
+    /// let jqesque = "foo={\"b\":2}".parse::<Jqesque>().unwrap();
+    /// jqesque.apply_to_with_options(&mut json_obj, &options).unwrap();
+    /// assert_eq!(json_obj, json!({"foo": {"a": 1, "b": 2}}));
+    /// ```
+    pub fn auto_order(mut self, order: impl IntoIterator<Item = Operation>) -> Self {
+        self.auto_order = order.into_iter().collect();
+        self
+    }
+
+    /// Sets the most elements `Insert` and `Merge` may add to an array, as `null` padding and the
+    /// value itself, to reach an index past its end.
+    ///
+    /// Going further fails with an `IndexTooLargeError` instead of allocating the array, which
+    /// protects against input such as `a[100000000]=1` from untrusted sources. Defaults to
+    /// 100,000.
+    ///
+    /// ```rust
+    /// use jqesque::{ApplyOptions, Jqesque, JqesqueError};
+    /// use serde_json::json;
+    ///
+    /// let options = ApplyOptions::new().max_array_grow(10);
+    /// let mut json_obj = json!({});
+    ///
+    /// let jqesque = ">a[10]=1".parse::<Jqesque>().unwrap();
+    /// assert_eq!(
+    ///     jqesque.apply_to_with_options(&mut json_obj, &options),
+    ///     Err(JqesqueError::IndexTooLargeError { index: 10, limit: 10 })
+    /// );
+    /// ```
+    pub fn max_array_grow(mut self, max_array_grow: usize) -> Self {
+        self.max_array_grow = max_array_grow;
+        self
+    }
+
+    /// Sets how the keys of objects in the document are ordered after applying. Defaults to
+    /// `KeyOrder::AsIs`.
+    pub fn key_order(mut self, key_order: KeyOrder) -> Self {
+        self.key_order = key_order;
+        self
+    }
+
+    /// Makes `Test`, `Remove`, `Replace`, `RemoveIf` and `TestReplace` ignore ASCII key case.
+    ///
+    /// A key that exists exactly as written is always used. Otherwise the one key that matches
+    /// ignoring case is used, and if several keys do (e.g. both `Name` and `NAME` for `name`), the
+    /// operation fails with an `AmbiguousKeyError`. Other operations, and the keys they create,
+    /// are unaffected. Defaults to `false`.
+    ///
+    /// ```rust
+    /// use jqesque::{ApplyOptions, Jqesque};
+    /// use serde_json::json;
+    ///
+    /// let options = ApplyOptions::new().case_insensitive_keys(true);
+    /// let mut json_obj = json!({"Name": 1});
+    ///
+    /// let jqesque = "?name=1".parse::<Jqesque>().unwrap();
+    /// assert!(jqesque.apply_to_with_options(&mut json_obj, &options).is_ok());
+    /// ```
+    pub fn case_insensitive_keys(mut self, case_insensitive_keys: bool) -> Self {
+        self.case_insensitive_keys = case_insensitive_keys;
+        self
+    }
+
+    /// Makes values written over existing values take the type of the value they overwrite.
+    ///
+    /// With it enabled, `Insert`, `Merge`, `Replace`, `Add` and `Auto` convert a number or boolean
+    /// to a string if the existing value is a string, and a string to a number or boolean if the
+    /// existing value is one and the string is a valid number or `true`/`false`. Objects are
+    /// converted key by key, so `~server={"port":8080}` keeps a string `port` a string.
+    ///
+    /// The existing type takes precedence over the parsed type, including for values with a
+    /// `(type)` prefix such as `(int)8080`. Assignments with a type annotation (`port:int=8080`)
+    /// are never converted, nor are arrays, `null`s or strings that do not convert. Defaults to
+    /// `false`.
+    ///
+    /// ```rust
+    /// use jqesque::{ApplyOptions, Jqesque};
+    /// use serde_json::json;
+    ///
+    /// let options = ApplyOptions::new().coerce_to_existing_type(true);
+    /// let mut json_obj = json!({"port": "80"});
+    ///
+    /// let jqesque = "~port=8080".parse::<Jqesque>().unwrap();
+    /// jqesque.apply_to_with_options(&mut json_obj, &options).unwrap();
+    /// assert_eq!(json_obj, json!({"port": "8080"}));
+    /// ```
+    pub fn coerce_to_existing_type(mut self, coerce_to_existing_type: bool) -> Self {
+        self.coerce_to_existing_type = coerce_to_existing_type;
+        self
+    }
+
+    /// Sets what `Remove`, `Test`, `TestLen`, `RemoveIf` and `TestReplace` do on a missing path.
+    ///
+    /// With `MissingPolicy::Skip`, they succeed without changes and the outcome is
+    /// `ApplyOutcome::Skipped`, which `Jqesque::apply_all_with_options` reports per assignment.
+    /// A `Test` of a path that exists with a different value still fails. This takes precedence
+    /// over `remove_if_exists`. Defaults to `MissingPolicy::Error`.
+    ///
+    /// ```rust
+    /// use jqesque::{ApplyOptions, ApplyOutcome, Jqesque, MissingPolicy, Operation};
+    /// use serde_json::json;
+    ///
+    /// let options = ApplyOptions::new().on_missing(MissingPolicy::Skip);
+    /// let mut json_obj = json!({});
+    ///
+    /// let jqesque = "?missing=1".parse::<Jqesque>().unwrap();
+    /// assert_eq!(
+    ///     jqesque.apply_to_with_options(&mut json_obj, &options),
+    ///     Ok(ApplyOutcome::Skipped(Operation::Test))
+    /// );
+    /// ```
+    pub fn on_missing(mut self, on_missing: MissingPolicy) -> Self {
+        self.on_missing = on_missing;
+        self
+    }
+}
+
+/// What to do with an operation that needs its path to exist, when it does not.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingPolicy {
+    /// Fail with the error of the operation, e.g. an `InvalidPathError` or `PatchError`.
+    #[default]
+    Error,
+
+    /// Skip the operation, leaving the document unchanged.
+    Skip,
+}
+
+/// Options controlling how `Merge` combines the incoming value with the existing JSON object.
+///
+/// ```rust
+/// use jqesque::{ApplyOptions, MergeOptions, MergeStrategy};
+///
+/// let options = ApplyOptions::new().merge(MergeOptions::new().strategy(MergeStrategy::SkipNull));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MergeOptions {
+    pub(crate) strategy: MergeStrategy,
+    pub(crate) arrays: ArrayMergeStrategy,
+}
+
+impl MergeOptions {
+    /// Creates a new set of merge options with the default behavior.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how incoming leaves are combined with the existing ones, such as whether a `null`
+    /// deletes a key or a string is appended to. Defaults to `MergeStrategy::Overwrite`.
+    pub fn strategy(mut self, strategy: MergeStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Sets how arrays are merged. Defaults to `ArrayMergeStrategy::ByIndex`.
+    pub fn arrays(mut self, arrays: ArrayMergeStrategy) -> Self {
+        self.arrays = arrays;
+        self
+    }
+}
+
+/// How `Merge` handles `null` values and other scalars in the incoming value.
+///
+/// | Strategy      | Incoming `null` for an existing key | Incoming `null` for a missing key |
+/// |---------------|-------------------------------------|-----------------------------------|
+/// | `Overwrite`   | The existing value becomes `null`   | The key is added as `null`        |
+/// | `SkipNull`    | The existing value is kept          | The key is not added              |
+/// | `DeleteNull`  | The key is removed                  | The key is not added              |
+/// | `StringConcat`| The existing value becomes `null`   | The key is added as `null`        |
+///
+/// `DeleteNull` gives the semantics of JSON Merge Patch
+/// ([RFC7396](https://datatracker.ietf.org/doc/html/rfc7396)) for objects. Arrays are still merged
+/// as set by `ArrayMergeStrategy`, and a `null` array element is kept as-is by both `Overwrite` and
+/// `DeleteNull`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Incoming values, including `null`, overwrite existing leaves.
+    #[default]
+    Overwrite,
+
+    /// Incoming `null` leaves are ignored entirely, so a partial update where absent fields are
+    /// serialized as `null` only changes the fields that are present.
+    ///
+    /// `null` elements that only pad an array up to a later index are still appended, so that
+    /// the indices of the following elements are kept.
+    SkipNull,
+
+    /// Incoming `null` values delete the key from the existing object, as in JSON Merge Patch.
+    ///
+    /// This also applies to the value being merged, so `~obj.a=null` removes `a` from `obj`, and
+    /// never writes a `null` if there is nothing to remove.
+    DeleteNull,
+
+    /// An incoming string is appended to an existing string, so merging `" done"` into `"ok"`
+    /// gives `"ok done"`. Any other leaf overwrites, as with `Overwrite`.
+    StringConcat,
+}
+
+/// Options controlling how an assignment is parsed.
+///
+/// Construct with `ParseOptions::new()` (or `ParseOptions::default()`) and chain the setters:
+///
+/// ```rust
+/// use jqesque::{ParseOptions, Separator};
+///
+/// let options = ParseOptions::new().separator(Separator::Slash).infer_types(false);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    pub(crate) separator: Separator,
+    pub(crate) infer_types: bool,
+    pub(crate) force_object_keys: bool,
+    pub(crate) strict_values: bool,
+    pub(crate) max_depth: usize,
+    pub(crate) max_input_length: usize,
+    pub(crate) allow_files: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            separator: Separator::Dot,
+            infer_types: true,
+            force_object_keys: false,
+            strict_values: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_input_length: usize::MAX,
+            allow_files: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Creates a new set of options with the default behavior.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the separator to use between keys. Defaults to `Separator::Dot`.
+    pub fn separator(mut self, separator: Separator) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Sets whether values are parsed as JSON, falling back to a string, or always taken literally
+    /// as a string.
+    ///
+    /// With type inference disabled, `version=1.10` keeps the string `"1.10"` rather than the
+    /// number `1.1`, and `zip=01234` keeps its leading zero. The value is taken verbatim, so quotes
+    /// are part of the string as well. Defaults to `true`.
+    pub fn infer_types(mut self, infer_types: bool) -> Self {
+        self.infer_types = infer_types;
+        self
+    }
+
+    /// Sets whether bracketed indices such as `[2024]` are parsed as object keys rather than
+    /// array indices.
+    ///
+    /// This is for documents with numeric object keys, like `{"2024": {...}}`: with it enabled,
+    /// `>years[2024]=x` stores `{"years": {"2024": "x"}}` instead of an array padded with 2024
+    /// nulls. Other array syntax, such as `[#]`, `[-1]` and `-`, is unaffected. The key is the
+    /// index in decimal, so `[007]` is the key `7`. Defaults to `false`.
+    pub fn force_object_keys(mut self, force_object_keys: bool) -> Self {
+        self.force_object_keys = force_object_keys;
+        self
+    }
+
+    /// Sets whether values that look like JSON must be valid JSON, rather than falling back to a
+    /// string.
+    ///
+    /// A value looks like JSON if it starts with `{`, `[`, `"` or a digit. With strict values, such
+    /// a value that fails to parse, e.g. `data={"a":}`, is an `InvalidJsonValueError` instead of
+    /// silently becoming the string `{"a":}`. Bare words such as `hello` still become strings.
+    /// Has no effect without type inference. Defaults to `false`.
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, JqesqueError, ParseOptions};
+    ///
+    /// let options = ParseOptions::new().strict_values(true);
+    /// assert!(matches!(
+    ///     Jqesque::parse_with_options("x={bad}", &options),
+    ///     Err(JqesqueError::InvalidJsonValueError { .. })
+    /// ));
+    /// ```
+    pub fn strict_values(mut self, strict_values: bool) -> Self {
+        self.strict_values = strict_values;
+        self
+    }
+
+    /// Sets the most path tokens an assignment may have, in its path or in the path it takes
+    /// from, before it is rejected with an `InputTooComplexError`.
+    ///
+    /// Applying an assignment walks the document once per token, so this bounds the work (and
+    /// stack) an assignment from an untrusted source can demand. Parsing stops at the first token
+    /// over the limit, so the `depth` reported is then `max_depth + 1`. Defaults to 128.
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, JqesqueError, ParseOptions};
+    ///
+    /// let options = ParseOptions::new().max_depth(2);
+    /// assert_eq!(
+    ///     Jqesque::parse_with_options("a.b.c=1", &options),
+    ///     Err(JqesqueError::InputTooComplexError { depth: 3, limit: 2 })
+    /// );
+    /// ```
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the most bytes an assignment may be long before it is rejected with an
+    /// `InputTooLongError`, without being parsed. Defaults to no limit.
+    ///
+    /// Parsing stops at the `max_depth` limit, but a value (or a long key) is still read in full,
+    /// so set this as well when parsing assignments from an untrusted source.
+    pub fn max_input_length(mut self, max_input_length: usize) -> Self {
+        self.max_input_length = max_input_length;
+        self
+    }
+
+    /// Sets whether a value of the form `@path` is read from the named file, with the `files`
+    /// feature enabled.
+    ///
+    /// This is off by default, so that `email=@handle` stores the string `"@handle"`, and an
+    /// assignment from an untrusted source cannot read local files even if another crate in the
+    /// build enables the feature. Has no effect without the feature. Defaults to `false`.
+    pub fn allow_files(mut self, allow_files: bool) -> Self {
+        self.allow_files = allow_files;
+        self
+    }
+}
+
+/// How `Merge` combines an incoming array with an existing array.
+///
+/// For example, merging `[2, 3]` into `[1, 2]` gives:
+///
+/// | Strategy  | Result         |
+/// |-----------|----------------|
+/// | `ByIndex` | `[2, 3]`       |
+/// | `Concat`  | `[1, 2, 2, 3]` |
+/// | `Replace` | `[2, 3]`       |
+/// | `Union`   | `[1, 2, 3]`    |
+///
+/// `ByIndex` and `Replace` differ when the existing array is longer, or holds objects: `ByIndex`
+/// keeps the trailing elements and merges objects at the same index, `Replace` does neither.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArrayMergeStrategy {
+    /// Elements are merged with the existing element at the same index, and elements past the
+    /// end of the existing array are appended.
+    #[default]
+    ByIndex,
+
+    /// All incoming elements are appended to the existing array.
+    Concat,
+
+    /// The incoming array replaces the existing array as a whole.
+    Replace,
+
+    /// Incoming elements are appended unless an equal element is already in the array.
+    Union,
+}
+
+/// How the keys of objects are ordered after applying, as set with `ApplyOptions::key_order`.
+///
+/// Objects are `serde_json` maps, so their order depends on whether serde_json's
+/// `preserve_order` feature is enabled anywhere in the dependency graph: without it keys are
+/// always sorted, with it they are kept in insertion order. `Sorted` gives the same output either
+/// way, e.g. for reproducible configuration files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// Keys are left in the order serde_json keeps them in.
+    #[default]
+    AsIs,
+
+    /// The keys of every object in the document are sorted after applying.
+    Sorted,
+}
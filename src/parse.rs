@@ -1,17 +1,22 @@
-use crate::types::{Jqesque, JqesqueError, Operation, PathToken, Separator};
+use crate::options::ParseOptions;
+use crate::types::{
+    ErrorSource, Jqesque, JqesqueError, Operation, PathToken, Separator, ValueType,
+};
 use nom::{
     branch::alt,
-    bytes::complete::{escaped_transform, is_not, take_while1},
-    character::complete::{char, digit1, none_of, one_of},
-    combinator::{all_consuming, map, map_res, opt},
-    error::VerboseError,
-    multi::{many1, separated_list1},
-    sequence::delimited,
+    bytes::complete::{escaped_transform, is_not, tag, take_while1, take_while_m_n},
+    character::complete::{
+        alpha1, char, digit1, line_ending, multispace0, none_of, one_of, space0,
+    },
+    combinator::{cut, map, map_res, opt, peek, recognize},
+    error::{ErrorKind, ParseError, VerboseError, VerboseErrorKind},
+    sequence::{delimited, pair, preceded},
     IResult,
 };
 use serde_json::Value;
 
-type Res<T, U> = IResult<T, U, VerboseError<T>>;
+/// The result type of all parser combinators, using nom's `VerboseError` for error reporting.
+pub type Res<T, U> = IResult<T, U, VerboseError<T>>;
 
 /// Parses the input string into path tokens and a serde_json::Value.
 ///
@@ -24,80 +29,564 @@ type Res<T, U> = IResult<T, U, VerboseError<T>>;
 ///
 /// Returns a `Jqesque` structure if successful, or a `JqesqueError` if parsing fails.
 pub fn parse_input(input: &str, separator: Separator) -> Result<Jqesque, JqesqueError> {
-    let sep_char = separator.as_char();
-    let res = all_consuming(|i| jqesque(i, sep_char))(input);
+    parse_input_with_options(input, &ParseOptions::new().separator(separator))
+}
+
+/// Parses the input string into path tokens and a serde_json::Value, with the given options.
+///
+/// ## Arguments
+///
+/// * `input` - The input string, e.g., "foo.bar[0].baz=true"
+/// * `options` - The options controlling how the input is parsed, including the separator.
+///
+/// ## Returns
+///
+/// Returns a `Jqesque` structure if successful, an `InputTooLongError` or `InputTooComplexError`
+/// if the input exceeds the limits of `options`, or a `JqesqueError` if parsing fails.
+pub fn parse_input_with_options(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<Jqesque, JqesqueError> {
+    // Check the length first, as it bounds the work and memory spent parsing
+    if input.len() > options.max_input_length {
+        return Err(JqesqueError::InputTooLongError {
+            length: input.len(),
+            limit: options.max_input_length,
+        });
+    }
+
+    let alternatives = options.separator.alternatives();
+    let separators: Vec<&str> = alternatives.iter().map(String::as_str).collect();
+    // Leading whitespace is skipped by the grammar, so that error offsets match the input
+    let input = input.trim_end();
+    let res = jqesque(input, &separators, options);
+    if let Ok((_, (jqesque, _))) = &res {
+        // Before any value file is read, and before the input left after a path that was cut
+        // short by the limit is reported
+        check_depth(jqesque, options.max_depth)?;
+    }
     match res {
-        Ok((_, jqesque)) => Ok(jqesque),
-        Err(err) => Err(JqesqueError::NomError(format!("{}", err))),
+        Ok((remaining, _)) if !remaining.is_empty() => Err(trailing_input_error(input, remaining)),
+        Ok((_, (jqesque, None))) => Ok(jqesque),
+        Ok((_, (mut jqesque, Some(PendingValue::Json(text))))) => {
+            let value =
+                serde_json::from_str(text).map_err(|e| JqesqueError::InvalidJsonValueError {
+                    input: text.to_string(),
+                    message: e.to_string(),
+                    source: ErrorSource::new(e),
+                })?;
+            jqesque.value = Some(value);
+            Ok(jqesque)
+        }
+        #[cfg(feature = "files")]
+        Ok((_, (mut jqesque, Some(PendingValue::File(path))))) => {
+            jqesque.value = Some(crate::files::read_value(path, options.infer_types)?);
+            Ok(jqesque)
+        }
+        #[cfg(not(feature = "files"))]
+        Ok((_, (_, Some(PendingValue::File(_))))) => {
+            unreachable!("file values are only parsed with `files`")
+        }
+        Err(err) => Err(parse_error(input, err)),
+    }
+}
+
+// Check that neither the path nor the path taken from is deeper than `limit`, as applying walks
+// the document recursively once per token
+fn check_depth(jqesque: &Jqesque, limit: usize) -> Result<(), JqesqueError> {
+    let depth = jqesque
+        .tokens
+        .len()
+        .max(jqesque.from.as_ref().map_or(0, Vec::len));
+    if depth > limit {
+        return Err(JqesqueError::InputTooComplexError { depth, limit });
     }
+    Ok(())
 }
 
-fn jqesque(input: &str, separator: char) -> Res<&str, Jqesque> {
+/// Parses the input string as a bare path, without an operation or value.
+///
+/// ## Arguments
+///
+/// * `input` - The input string, e.g., "foo.bar[0].baz"
+/// * `separator` - The separator to use between keys, a Separator enum variant.
+///
+/// ## Returns
+///
+/// Returns the path tokens if successful, or a `JqesqueError` if parsing fails.
+pub fn parse_path(input: &str, separator: &Separator) -> Result<Vec<PathToken>, JqesqueError> {
+    let alternatives = separator.alternatives();
+    let separators: Vec<&str> = alternatives.iter().map(String::as_str).collect();
+    let input = input.trim_end();
+    let res = preceded(multispace0, |i| path_or_root(i, &separators, usize::MAX))(input);
+    match res {
+        Ok((remaining, _)) if !remaining.is_empty() => Err(trailing_input_error(input, remaining)),
+        Ok((_, tokens)) => Ok(tokens),
+        Err(err) => Err(parse_error(input, err)),
+    }
+}
+
+/// Reports the input left over after a successful parse as a `JqesqueError::TrailingInputError`.
+///
+/// Whitespace between the two parts is counted as remaining input, so that it is shown along with
+/// whatever was not understood.
+fn trailing_input_error(input: &str, remaining: &str) -> JqesqueError {
+    let consumed = input[..input.len() - remaining.len()].trim_end();
+    JqesqueError::TrailingInputError {
+        consumed: consumed.to_string(),
+        remaining: input[consumed.len()..].to_string(),
+    }
+}
+
+/// Converts a nom error into a `JqesqueError::ParseError`, locating where in `input` it occurred.
+///
+/// The innermost error is reported, as it is the one closest to the actual problem.
+fn parse_error(input: &str, err: nom::Err<VerboseError<&str>>) -> JqesqueError {
+    let (remaining, kind) = match err {
+        nom::Err::Error(e) | nom::Err::Failure(e) => match e.errors.into_iter().next() {
+            Some((remaining, kind)) => (remaining, Some(kind)),
+            None => (input, None),
+        },
+        nom::Err::Incomplete(_) => ("", None),
+    };
+
+    let offset = input.len() - remaining.len();
+    let column = input[..offset].chars().count() + 1;
+    let expected = match kind {
+        Some(VerboseErrorKind::Char(c)) => format!("expected '{}'", c),
+        Some(VerboseErrorKind::Nom(ErrorKind::TakeWhile1)) => "expected a key".to_string(),
+        Some(VerboseErrorKind::Nom(ErrorKind::IsNot)) => "expected a value".to_string(),
+        Some(VerboseErrorKind::Context(context)) => format!("expected {}", context),
+        _ => "unexpected input".to_string(),
+    };
+
+    JqesqueError::ParseError {
+        offset,
+        column,
+        message: format!("{} at column {}", expected, column),
+    }
+}
+
+// Parse an assignment, along with the value still to be read or checked, if any
+fn jqesque<'a>(
+    input: &'a str,
+    separators: &[&str],
+    options: &ParseOptions,
+) -> Res<&'a str, (Jqesque, Option<PendingValue<'a>>)> {
+    let (input, _) = multispace0(input)?;
     let (input, operation) = opt(operation_prefix)(input)?;
     let operation = operation.unwrap_or(Operation::Auto);
 
-    let (input, (tokens, value)) = assignment(input, separator, &operation)?;
+    let (input, assignment) = assignment(input, separators, &operation, options)?;
 
     Ok((
         input,
-        Jqesque {
-            operation,
-            tokens,
-            value,
-        },
+        (
+            Jqesque {
+                operation,
+                tokens: assignment.tokens,
+                value: assignment.value,
+                required_type: assignment.required_type,
+                from: assignment.from,
+                expected: assignment.expected,
+                separator: options.separator.clone(),
+                pointer_cache: Default::default(),
+            },
+            assignment.pending,
+        ),
     ))
 }
 
 fn operation_prefix(input: &str) -> Res<&str, Operation> {
+    if let Ok((input, _)) = tag::<_, _, VerboseError<&str>>("-?")(input) {
+        return Ok((input, Operation::RemoveIf));
+    }
+    if let Ok((input, _)) = tag::<_, _, VerboseError<&str>>("?#")(input) {
+        return Ok((input, Operation::TestLen));
+    }
+    if let Ok((input, _)) = tag::<_, _, VerboseError<&str>>("?=")(input) {
+        return Ok((input, Operation::TestReplace));
+    }
     let (input, op_char) = one_of(Operation::operators())(input)?;
     let operation =
         Operation::from_operator(op_char).expect("operator should be valid since we used one_of");
     Ok((input, operation))
 }
 
+/// The parts of an assignment following the operation prefix.
+struct Assignment<'a> {
+    tokens: Vec<PathToken>,
+    required_type: Option<ValueType>,
+    value: Option<Value>,
+    from: Option<Vec<PathToken>>,
+    expected: Option<Value>,
+    // The value still to be read or checked once parsed, if any
+    pending: Option<PendingValue<'a>>,
+}
+
+impl Assignment<'_> {
+    // An assignment whose path was cut short by `ParseOptions::max_depth`, and is to be rejected
+    // without parsing the rest of the input
+    fn too_deep(tokens: Vec<PathToken>, from: Option<Vec<PathToken>>) -> Self {
+        Assignment {
+            tokens,
+            required_type: None,
+            value: None,
+            from,
+            expected: None,
+            pending: None,
+        }
+    }
+}
+
+/// A value that is resolved after parsing, as it can fail with an error other than a parse error.
+enum PendingValue<'a> {
+    /// The file to read the value from, for an `@path` value
+    #[cfg_attr(not(feature = "files"), allow(dead_code))]
+    File(&'a str),
+    /// Text that must be valid JSON, with `ParseOptions::strict_values`
+    Json(&'a str),
+}
+
 fn assignment<'a>(
     input: &'a str,
-    separator: char,
+    separators: &[&str],
     operation: &Operation,
-) -> Res<&'a str, (Vec<PathToken>, Option<Value>)> {
-    let (input, tokens) = path(input, separator)?;
+    options: &ParseOptions,
+) -> Res<&'a str, Assignment<'a>> {
+    let (input, tokens) = path_or_root(input, separators, options.max_depth)?;
+    let tokens = object_keys(tokens, options);
+    if tokens.len() > options.max_depth {
+        return Ok((input, Assignment::too_deep(tokens, None)));
+    }
+    let (input, required_type) = opt(type_annotation)(input)?;
+    let (input, _) = space0(input)?;
 
-    let (input, value_opt) = match operation {
-        Operation::Remove => (input, None),
-        _ => {
+    let (input, from) = match operation {
+        Operation::Copy | Operation::Move => {
+            let (input, _) = char('<')(input)?;
+            let (input, _) = space0(input)?;
+            let (input, from) = cut(|i| path_or_root(i, separators, options.max_depth))(input)?;
+            let from = object_keys(from, options);
+            if from.len() > options.max_depth {
+                return Ok((input, Assignment::too_deep(tokens, Some(from))));
+            }
+            (input, Some(from))
+        }
+        _ => (input, None),
+    };
+
+    let mut expected = None;
+    let (input, value_opt, pending) = match operation {
+        Operation::Remove | Operation::Copy | Operation::Move => (input, None, None),
+        Operation::TestLen => {
+            let (input, _) = char('=')(input)?;
+            let (input, _) = space0(input)?;
+            let (input, len) = length_value(input)?;
+            (input, Some(len), None)
+        }
+        Operation::TestReplace => {
             let (input, _) = char('=')(input)?;
-            let (input, _) = opt(char(' '))(input)?;
-            let (input, value) = json_value(input)?;
-            (input, Some(value))
+            let (input, _) = space0(input)?;
+            let (input, value) = expected_value(input, options)?;
+            expected = Some(value);
+            let (input, _) = tag("=>")(input)?;
+            let (input, _) = space0(input)?;
+            let (input, (value, pending)) = assigned_value(input, options)?;
+            (input, value, pending)
+        }
+        _ => {
+            let (input, raw) = alt((map(tag(":="), |_| true), map(char('='), |_| false)))(input)?;
+            let (input, _) = space0(input)?;
+            if raw {
+                let (input, value) = raw_json_value(input)?;
+                (input, Some(value), None)
+            } else {
+                let (input, (value, pending)) = assigned_value(input, options)?;
+                (input, value, pending)
+            }
+        }
+    };
+
+    Ok((
+        input,
+        Assignment {
+            tokens,
+            required_type,
+            value: value_opt,
+            from,
+            expected,
+            pending,
+        },
+    ))
+}
+
+// Parse the value after `=`, or the value still to be read or checked
+fn assigned_value<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> Res<&'a str, (Option<Value>, Option<PendingValue<'a>>)> {
+    let (input, value_pending) = match opt(|i| file_value(i, options))(input)? {
+        (input, Some(path)) => (input, (None, Some(PendingValue::File(path)))),
+        (input, None) => {
+            if let (input, Some(value)) = opt(heredoc_value)(input)? {
+                (input, (Some(value), None))
+            } else if let (input, Some(value)) = opt(typed_value)(input)? {
+                (input, (Some(value), None))
+            } else if options.infer_types && options.strict_values && looks_like_json(input) {
+                let (input, text) = is_not("")(input)?;
+                (input, (None, Some(PendingValue::Json(text))))
+            } else if options.infer_types {
+                let (input, value) = json_value(input)?;
+                (input, (Some(value), None))
+            } else {
+                let (input, value) = string_value(input)?;
+                (input, (Some(value), None))
+            }
+        }
+    };
+    Ok((input, value_pending))
+}
+
+// Parse the expected value of a `?=` assignment, up to the `=>` before the new value.
+//
+// A JSON value is taken whole, so a quoted string may itself contain `=>`; anything else runs up
+// to the first `=>`, typed as a value after `=` would be. Without type inference, a quoted string
+// is still taken whole, but kept verbatim with its quotes, as a value after `=` would be.
+fn expected_value<'a>(input: &'a str, options: &ParseOptions) -> Res<&'a str, Value> {
+    if options.infer_types {
+        let mut stream = serde_json::Deserializer::from_str(input).into_iter::<Value>();
+        if let Some(Ok(value)) = stream.next() {
+            let rest = input[stream.byte_offset()..].trim_start_matches([' ', '\t']);
+            if rest.starts_with("=>") {
+                return Ok((rest, value));
+            }
         }
+    } else if let Some(len) = quoted_len(input) {
+        let rest = input[len..].trim_start_matches([' ', '\t']);
+        if rest.starts_with("=>") {
+            return Ok((rest, Value::String(input[..len].to_string())));
+        }
+    }
+
+    let missing = || {
+        nom::Err::Failure(VerboseError {
+            errors: vec![(
+                input,
+                VerboseErrorKind::Context("an expected value followed by '=>'"),
+            )],
+        })
     };
+    let end = input.find("=>").ok_or_else(missing)?;
+    let text = input[..end].trim_end();
+    if text.is_empty() {
+        return Err(missing());
+    }
+    let (_, value) = if let (_, Some(value)) = opt(typed_value)(text)? {
+        ("", value)
+    } else if options.infer_types {
+        json_value(text)?
+    } else {
+        string_value(text)?
+    };
+    Ok((&input[end..], value))
+}
+
+// The length of the double-quoted string `input` starts with, up to and including the closing
+// quote, skipping quotes escaped with a backslash
+fn quoted_len(input: &str) -> Option<usize> {
+    let mut chars = input.char_indices();
+    if chars.next()?.1 != '"' {
+        return None;
+    }
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
 
-    Ok((input, (tokens, value_opt)))
+// Turn indices into object keys, if `ParseOptions::force_object_keys` asks for it
+fn object_keys(tokens: Vec<PathToken>, options: &ParseOptions) -> Vec<PathToken> {
+    if !options.force_object_keys {
+        return tokens;
+    }
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            PathToken::Index(index) => PathToken::Key(index.to_string()),
+            token => token,
+        })
+        .collect()
 }
 
-fn path(input: &str, separator: char) -> Res<&str, Vec<PathToken>> {
-    let (input, token_vecs) =
-        separated_list1(char(separator), alt((array_access, key_segment)))(input)?;
+fn type_annotation(input: &str) -> Res<&str, ValueType> {
+    let (input, _) = char(':')(input)?;
+    map_res(alpha1, |s: &str| s.parse::<ValueType>())(input)
+}
 
-    let tokens = token_vecs.into_iter().flatten().collect();
+/// Parses a path of keys and array indices, separated by `separator`.
+///
+/// At least one segment is required. Parsing stops at the first character that cannot continue
+/// the path (e.g. the `=` of an assignment).
+pub fn path(input: &str, separator: char) -> Res<&str, Vec<PathToken>> {
+    path_with_separator(input, separator.encode_utf8(&mut [0; 4]))
+}
+
+/// Parses a path of keys and array indices, separated by the (possibly multi-character) separator.
+///
+/// Whitespace around the separator is skipped, so `a . b` is the same path as `a.b`.
+///
+/// Unquoted keys end where the separator starts, so separators such as `->` may share
+/// characters with keys. A separator must be followed by another segment: `a..b` fails at the
+/// second separator rather than stopping after `a`.
+pub fn path_with_separator<'a>(input: &'a str, separator: &str) -> Res<&'a str, Vec<PathToken>> {
+    path_with_separators(input, &[separator])
+}
+
+/// Parses a path of keys and array indices, where keys may be separated by any of `separators`.
+///
+/// This is `path_with_separator` with several separators accepted at once, as for
+/// `Separator::AnyOf`: with `&["/", "."]`, `a/b.c` is the path of the keys `a`, `b` and `c`.
+pub fn path_with_separators<'a>(
+    input: &'a str,
+    separators: &[&str],
+) -> Res<&'a str, Vec<PathToken>> {
+    path_with_limit(input, separators, usize::MAX)
+}
+
+// Parse a path, stopping as soon as it has more than `limit` tokens, so that an overlong path
+// is not tokenized in full only to be rejected
+fn path_with_limit<'a>(
+    input: &'a str,
+    separators: &[&str],
+    limit: usize,
+) -> Res<&'a str, Vec<PathToken>> {
+    let segment = |i, tokens: &[PathToken]| {
+        alt((
+            |i| array_access_before(i, separators, limit.saturating_sub(tokens.len())),
+            |i| key_segment_before(i, separators),
+        ))(i)
+    };
+
+    let mut tokens = Vec::new();
+    let (mut input, first) = segment(input, &tokens)?;
+    tokens.extend(first);
+    while tokens.len() <= limit {
+        let rest = match padded_separator(input, separators) {
+            Ok((rest, _)) => rest,
+            Err(nom::Err::Error(_)) => break,
+            Err(e) => return Err(e),
+        };
+        let (rest, more) = cut(|i| preceded(space0, |i| segment(i, &tokens))(i))(rest)?;
+        tokens.extend(more);
+        input = rest;
+    }
 
     Ok((input, tokens))
 }
 
-fn key_segment(input: &str) -> Res<&str, Vec<PathToken>> {
-    map(alt((quoted_string, valid_identifier)), |s: String| {
-        vec![PathToken::Key(s)]
-    })(input)
+// Parse a path, or a separator on its own (e.g. `.`) as the empty path to the document root
+fn path_or_root<'a>(
+    input: &'a str,
+    separators: &[&str],
+    limit: usize,
+) -> Res<&'a str, Vec<PathToken>> {
+    match path_with_limit(input, separators, limit) {
+        // Report why the path failed, rather than that the input is not a lone separator
+        Err(nom::Err::Error(e)) => match separators
+            .iter()
+            .filter(|separator| !separator.is_empty())
+            .find_map(|separator| input.strip_prefix(separator))
+        {
+            Some(rest) => Ok((rest, Vec::new())),
+            None => Err(nom::Err::Error(e)),
+        },
+        res => res,
+    }
+}
+
+// Parse one of the separators, along with any whitespace before it, unless the separator is
+// itself made of whitespace
+fn padded_separator<'a>(input: &'a str, separators: &[&str]) -> Res<&'a str, &'a str> {
+    let mut result = Err(nom::Err::Error(VerboseError::from_error_kind(
+        input,
+        ErrorKind::Tag,
+    )));
+    for separator in separators {
+        result = if separator.trim().is_empty() {
+            tag(*separator)(input)
+        } else {
+            preceded(space0, tag(*separator))(input)
+        };
+        if result.is_ok() {
+            break;
+        }
+    }
+    result
+}
+
+// The separator that `input` starts with, if any
+fn separator_at<'s>(input: &str, separators: &[&'s str]) -> Option<&'s str> {
+    separators
+        .iter()
+        .find(|separator| !separator.is_empty() && input.starts_with(**separator))
+        .copied()
+}
+
+/// Parses a single key segment of a path: a quoted key, a bare key, `*` or the append token `-`.
+///
+/// A bare key ends where `separator` starts, so `key_segment("foo.bar", '.')` stops before the
+/// `.` and leaves `.bar` unparsed.
+pub fn key_segment(input: &str, separator: char) -> Res<&str, Vec<PathToken>> {
+    key_segment_before(input, &[separator.encode_utf8(&mut [0; 4])])
+}
+
+// Parse a key segment whose unquoted key ends where one of the separators starts
+fn key_segment_before<'a>(input: &'a str, separators: &[&str]) -> Res<&'a str, Vec<PathToken>> {
+    alt((
+        map(quoted_string, |s: String| vec![PathToken::Key(s)]),
+        map(char('*'), |_| vec![PathToken::Wildcard]),
+        // A bare `-` is the JSON Pointer append token, a quoted `"-"` is a key
+        map(
+            |i| identifier_before(i, separators),
+            |s: String| match s.as_str() {
+                "-" => vec![PathToken::Append],
+                _ => vec![PathToken::Key(s)],
+            },
+        ),
+    ))(input)
 }
 
-fn array_access(input: &str) -> Res<&str, Vec<PathToken>> {
-    let (input, key_opt) = opt(alt((quoted_string, valid_identifier)))(input)?;
+/// Parses an optional key followed by one or more array indices, e.g. `foo[0][1]` or `[2]`.
+///
+/// An index is either a non-negative number or a length-relative index: `#` for the current
+/// length of the array, optionally followed by a signed offset such as `#-1`. A negative number
+/// such as `-1` is shorthand for `#-1`, and `-` on its own (or nothing at all, as in `arr[]`)
+/// appends to the array.
+///
+/// The brackets may also hold a quoted key, as in `foo["bar.baz"]`, which is equivalent to
+/// `foo."bar.baz"` and can be mixed freely with indices: `foo["bar"][0]`.
+pub fn array_access(input: &str) -> Res<&str, Vec<PathToken>> {
+    array_access_before(input, &[], usize::MAX)
+}
 
-    let (input, indices) = many1(delimited(
-        char('['),
-        map_res(digit1, |s: &str| s.parse::<usize>()),
-        char(']'),
-    ))(input)?;
+// Parse an array access whose unquoted key ends where one of the separators starts, stopping
+// as soon as it has more than `limit` tokens
+fn array_access_before<'a>(
+    input: &'a str,
+    separators: &[&str],
+    limit: usize,
+) -> Res<&'a str, Vec<PathToken>> {
+    let (input, key_opt) = opt(alt((quoted_string, |i| identifier_before(i, separators))))(input)?;
+
+    let mut bracket = delimited(char('['), bracketed, char(']'));
+    let (mut input, first) = bracket(input)?;
 
     let mut tokens = Vec::new();
 
@@ -105,30 +594,361 @@ fn array_access(input: &str) -> Res<&str, Vec<PathToken>> {
         tokens.push(PathToken::Key(key));
     }
 
-    for index in indices {
-        tokens.push(PathToken::Index(index));
+    tokens.push(first);
+    while tokens.len() <= limit {
+        match bracket(input) {
+            Ok((rest, index)) => {
+                tokens.push(index);
+                input = rest;
+            }
+            Err(nom::Err::Error(_)) => break,
+            Err(e) => return Err(e),
+        }
     }
 
     Ok((input, tokens))
 }
 
-fn valid_identifier(input: &str) -> Res<&str, String> {
+// Parse the contents of a bracket: an array index or a quoted key, as in `["a.b"]`
+fn bracketed(input: &str) -> Res<&str, PathToken> {
+    alt((
+        index,
+        map(char('*'), |_| PathToken::Wildcard),
+        map(quoted_string, PathToken::Key),
+        // Empty brackets, as in `arr[]`, append like `[-]`
+        map(peek(char(']')), |_| PathToken::Append),
+    ))(input)
+}
+
+fn index(input: &str) -> Res<&str, PathToken> {
+    alt((
+        map(
+            map_res(digit1, |s: &str| s.parse::<usize>()),
+            PathToken::Index,
+        ),
+        map(length_relative_index, PathToken::LengthRelative),
+        map(negative_index, PathToken::LengthRelative),
+        map(char('-'), |_| PathToken::Append),
+    ))(input)
+}
+
+// A negative index such as `-1`, shorthand for the length-relative `#-1`
+fn negative_index(input: &str) -> Res<&str, isize> {
+    map_res(recognize(pair(char('-'), digit1)), |s: &str| {
+        match s.parse::<isize>() {
+            Ok(0) => Err("negative index must not be zero".to_string()),
+            Ok(offset) => Ok(offset),
+            Err(e) => Err(e.to_string()),
+        }
+    })(input)
+}
+
+fn length_relative_index(input: &str) -> Res<&str, isize> {
+    let (input, _) = char('#')(input)?;
+    let (input, offset) = opt(map_res(recognize(pair(one_of("+-"), digit1)), |s: &str| {
+        s.parse::<isize>()
+    }))(input)?;
+    Ok((input, offset.unwrap_or(0)))
+}
+
+/// Parses an unquoted key made up of alphanumeric characters, `_` and `-`.
+pub fn valid_identifier(input: &str) -> Res<&str, String> {
     map(
         take_while1(|c: char| c.is_alphanumeric() || c == '_' || c == '-'),
         |s: &str| s.to_string(),
     )(input)
 }
 
-fn quoted_string(input: &str) -> Res<&str, String> {
-    delimited(
-        char('"'),
-        escaped_transform(none_of("\\\""), '\\', one_of("\\\"nrt")),
-        char('"'),
+// Parse an unquoted key as `valid_identifier` does, but stop where one of the (non-empty)
+// separators starts. A backslash escapes a separator or another backslash, so `a\.b` is the key
+// `a.b`.
+fn identifier_before<'a>(input: &'a str, separators: &[&str]) -> Res<&'a str, String> {
+    let mut key = String::new();
+    let mut rest = input;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            let escaped = &rest[1..];
+            let literal = if let Some(separator) = separator_at(escaped, separators) {
+                separator
+            } else if escaped.starts_with('\\') {
+                "\\"
+            } else if rest.len() == input.len() && escaped.starts_with(Operation::operators()) {
+                // An operator character starting a key, as in `\+key=1`, which would otherwise
+                // be taken as the operation
+                &escaped[..1]
+            } else {
+                return Err(nom::Err::Failure(VerboseError {
+                    errors: vec![(
+                        rest,
+                        VerboseErrorKind::Context("an escaped separator or backslash"),
+                    )],
+                }));
+            };
+            key.push_str(literal);
+            rest = &escaped[literal.len()..];
+            continue;
+        }
+        if !(c.is_alphanumeric() || c == '_' || c == '-')
+            || separator_at(rest, separators).is_some()
+        {
+            break;
+        }
+        key.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    if rest.len() == input.len() {
+        return Err(nom::Err::Error(VerboseError::from_error_kind(
+            input,
+            ErrorKind::TakeWhile1,
+        )));
+    }
+    Ok((rest, key))
+}
+
+/// Parses a key quoted with double (`"key"`) or single (`'key'`) quotes. A backslash escapes a
+/// following `\\`, the enclosing quote, `n`, `r` or `t`, which is taken literally (so `\n` is a
+/// plain `n`). The other quote character needs no escaping.
+///
+/// A `\uXXXX` escape is the Unicode character with the hexadecimal code `XXXX`, as in JSON, so
+/// `"caf\u00e9"` is the key `café`. Characters outside the Basic Multilingual Plane are written
+/// as a surrogate pair, e.g. `\ud83d\ude00`. A surrogate that is not part of a pair is an error.
+///
+/// The quotes may be empty, for the empty key `""`.
+pub fn quoted_string(input: &str) -> Res<&str, String> {
+    alt((
+        delimited(
+            char('"'),
+            map(
+                opt(escaped_transform(
+                    none_of("\\\""),
+                    '\\',
+                    alt((one_of("\\\"nrt"), unicode_escape)),
+                )),
+                Option::unwrap_or_default,
+            ),
+            char('"'),
+        ),
+        delimited(
+            char('\''),
+            map(
+                opt(escaped_transform(
+                    none_of("\\'"),
+                    '\\',
+                    alt((one_of("\\'nrt"), unicode_escape)),
+                )),
+                Option::unwrap_or_default,
+            ),
+            char('\''),
+        ),
+    ))(input)
+}
+
+// Parse the `uXXXX` of a `\uXXXX` escape (or a surrogate pair of them) into its character
+fn unicode_escape(input: &str) -> Res<&str, char> {
+    let (rest, high) = preceded(char('u'), hex_code_unit)(input)?;
+    let code = match high {
+        0xD800..=0xDBFF => match preceded(tag("\\u"), hex_code_unit)(rest) {
+            Ok((after, low @ 0xDC00..=0xDFFF)) => {
+                return Ok((
+                    after,
+                    char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+                        .expect("a surrogate pair should decode to a valid character"),
+                ))
+            }
+            _ => None,
+        },
+        code => char::from_u32(code),
+    };
+    match code {
+        Some(c) => Ok((rest, c)),
+        None => Err(nom::Err::Failure(VerboseError {
+            errors: vec![(
+                input,
+                VerboseErrorKind::Context("a valid unicode escape, not a lone surrogate"),
+            )],
+        })),
+    }
+}
+
+// Parse the four hexadecimal digits of a `\uXXXX` escape
+fn hex_code_unit(input: &str) -> Res<&str, u32> {
+    map_res(
+        take_while_m_n(4, 4, |c: char| c.is_ascii_hexdigit()),
+        |hex: &str| u32::from_str_radix(hex, 16),
     )(input)
 }
 
-fn json_value(input: &str) -> Res<&str, Value> {
+// Parse a heredoc value: `<<TAG`, a line break, then raw text up to a line holding only `TAG`,
+// which must end the input. The text is kept verbatim as a string, without type inference.
+fn heredoc_value(input: &str) -> Res<&str, Value> {
+    let (body, tag_name) = preceded(
+        tag("<<"),
+        take_while1(|c: char| c.is_alphanumeric() || c == '_'),
+    )(input)?;
+    let (body, _) = line_ending(body)?;
+
+    // Once the opening line is recognised, a missing terminator is an error rather than a string
+    let end = body.strip_suffix(tag_name).and_then(|text| match text {
+        "" => Some(""),
+        _ => text
+            .strip_suffix('\n')
+            .map(|text| text.strip_suffix('\r').unwrap_or(text)),
+    });
+    match end {
+        Some(text) => Ok(("", Value::String(text.to_string()))),
+        None => Err(nom::Err::Failure(VerboseError {
+            errors: vec![(body, VerboseErrorKind::Context("a heredoc terminator"))],
+        })),
+    }
+}
+
+// Parse a file value, `@path`, into the path of the file to read the value from. Without the
+// `files` feature and `ParseOptions::allow_files`, there are no file values and `@path` is an
+// ordinary value.
+fn file_value<'a>(input: &'a str, options: &ParseOptions) -> Res<&'a str, &'a str> {
+    if !cfg!(feature = "files") || !options.allow_files {
+        return Err(nom::Err::Error(VerboseError::from_error_kind(
+            input,
+            ErrorKind::Char,
+        )));
+    }
+    preceded(char('@'), is_not(""))(input)
+}
+
+// Parse a value with a `(type)` prefix, such as `(int)8080`, coercing the rest of the input to
+// that type. Unknown prefixes are not matched, so `(hello)` is still an ordinary value.
+fn typed_value(input: &str) -> Res<&str, Value> {
+    let (text, kind) = delimited(
+        char('('),
+        alt((
+            tag("str"),
+            tag("int"),
+            tag("float"),
+            tag("bool"),
+            tag("null"),
+            tag("json"),
+        )),
+        char(')'),
+    )(input)?;
+
+    match coerce_value(text, kind) {
+        Some(value) => Ok(("", value)),
+        None => {
+            let expected = match kind {
+                "int" => "an integer after (int)",
+                "float" => "a number after (float)",
+                "bool" => "true, false, yes, no, on, off, 1 or 0 after (bool)",
+                "null" => "null or nothing after (null)",
+                _ => "a valid JSON value after (json)",
+            };
+            Err(nom::Err::Failure(VerboseError {
+                errors: vec![(text, VerboseErrorKind::Context(expected))],
+            }))
+        }
+    }
+}
+
+// Coerce `text` to the JSON type named by `kind`, or `None` if it is not a valid value of it
+fn coerce_value(text: &str, kind: &str) -> Option<Value> {
+    if kind == "str" {
+        return Some(Value::String(text.to_string()));
+    }
+
+    let text = text.trim();
+    match kind {
+        "int" => text
+            .parse::<i64>()
+            .map(Value::from)
+            .or_else(|_| text.parse::<u64>().map(Value::from))
+            .ok()
+            .or_else(|| whole_number(text)),
+        "float" => text
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number),
+        "bool" => match text.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Some(Value::Bool(true)),
+            "false" | "no" | "off" | "0" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        "null" => matches!(text, "" | "null").then_some(Value::Null),
+        _ => serde_json::from_str(text).ok(),
+    }
+}
+
+// Convert a number written as a float, such as `3.0` or `1e3`, to an integer if it has no
+// fractional part and fits one. Anything else is rejected rather than truncated.
+fn whole_number(text: &str) -> Option<Value> {
+    let float = text
+        .parse::<f64>()
+        .ok()
+        .filter(|f| f.is_finite() && f.fract() == 0.0)?;
+    if float >= i64::MIN as f64 && float < i64::MAX as f64 {
+        Some(Value::from(float as i64))
+    } else if float >= 0.0 && float < u64::MAX as f64 {
+        Some(Value::from(float as u64))
+    } else {
+        None
+    }
+}
+
+// Parse the expected length of a `TestLen`, a non-negative integer
+fn length_value(input: &str) -> Res<&str, Value> {
+    match map_res(digit1::<_, VerboseError<&str>>, |s: &str| s.parse::<u64>())(input) {
+        Ok((input, len)) => Ok((input, Value::from(len))),
+        Err(_) => Err(nom::Err::Failure(VerboseError {
+            errors: vec![(
+                input,
+                VerboseErrorKind::Context("an array or object length after '?#'"),
+            )],
+        })),
+    }
+}
+
+// Parse the rest of the input as JSON, failing rather than falling back to a string
+fn raw_json_value(input: &str) -> Res<&str, Value> {
+    match serde_json::from_str(input) {
+        Ok(value) => Ok(("", value)),
+        Err(_) => Err(nom::Err::Failure(VerboseError {
+            errors: vec![(
+                input,
+                VerboseErrorKind::Context("a valid JSON value after ':='"),
+            )],
+        })),
+    }
+}
+
+// Whether a value is meant to be JSON rather than a bare word, going by its first character
+fn looks_like_json(input: &str) -> bool {
+    input
+        .chars()
+        .next()
+        .is_some_and(|c| matches!(c, '{' | '[' | '"') || c.is_ascii_digit())
+}
+
+// Parse the rest of the input verbatim as a string, without any type inference
+fn string_value(input: &str) -> Res<&str, Value> {
+    map(is_not(""), |s: &str| Value::String(s.to_string()))(input)
+}
+
+/// Parses the rest of the input as a JSON value, falling back to a string if it is not valid JSON.
+///
+/// A number may be written with a leading unary plus, which JSON does not allow: `+5` is the
+/// number `5`. Anything else starting with `+`, such as `+5x` or `+"a"`, is kept as a string.
+pub fn json_value(input: &str) -> Res<&str, Value> {
     map(is_not(""), |s: &str| {
+        if let Some(unsigned) = s
+            .strip_prefix('+')
+            .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        {
+            return match serde_json::from_str(unsigned) {
+                Ok(Value::Number(number)) => Value::Number(number),
+                _ => Value::String(s.to_string()),
+            };
+        }
         serde_json::from_str(s).unwrap_or(Value::String(s.to_string()))
     })(input)
 }
@@ -0,0 +1,32 @@
+//! Reusable nom parser combinators.
+//!
+//! These are the building blocks of the jqesque grammar, exposed for crates that want to embed
+//! jqesque paths or values in a larger grammar (e.g. a config file format). All combinators
+//! operate on `&str` input and return a [`Res`], which uses nom's `VerboseError`.
+//!
+//! The signatures of the combinators in this module are considered stable: they may learn to
+//! accept more syntax over time, but their inputs and outputs will not change without a
+//! version bump.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use jqesque::parser::{json_value, path};
+//! use jqesque::PathToken;
+//! use serde_json::json;
+//!
+//! let (rest, tokens) = path("foo.bar[0] -> 42", '.').unwrap();
+//! assert_eq!(tokens, vec![
+//!     PathToken::Key("foo".to_string()),
+//!     PathToken::Key("bar".to_string()),
+//!     PathToken::Index(0),
+//! ]);
+//!
+//! let (_, value) = json_value(&rest[4..]).unwrap();
+//! assert_eq!(value, json!(42));
+//! ```
+
+pub use crate::parse::{
+    array_access, json_value, key_segment, path, path_with_separator, path_with_separators,
+    quoted_string, valid_identifier, Res,
+};
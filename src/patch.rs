@@ -0,0 +1,191 @@
+use json_patch::{
+    AddOperation, CopyOperation, MoveOperation, Patch, PatchOperation, RemoveOperation,
+    ReplaceOperation, TestOperation,
+};
+
+use crate::pointer::{tokens_from_pointer, tokens_to_pointer};
+use crate::types::{Jqesque, JqesqueError, Operation, PathToken, Separator};
+
+impl Jqesque {
+    /// Builds a `Jqesque` from a single RFC6902 JSON Patch operation.
+    ///
+    /// Each operation maps onto the `Operation` of the same name. The path (and `from`) is split
+    /// into its tokens: array indices (e.g. `0`) become `PathToken::Index`, the past-the-end
+    /// token `-` becomes `PathToken::Append`, and everything else becomes a key.
+    ///
+    /// ## Arguments
+    ///
+    /// * `op` - The JSON Patch operation to convert
+    ///
+    /// ## Returns
+    ///
+    /// Returns a `Jqesque` structure. Every RFC6902 operation is supported, so this cannot fail.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Operation};
+    /// use serde_json::json;
+    ///
+    /// let patch: json_patch::Patch =
+    ///     serde_json::from_value(json!([{"op": "add", "path": "/foo/0", "value": 1}])).unwrap();
+    /// let jqesque = Jqesque::from_patch_operation(&patch.0[0]);
+    ///
+    /// assert_eq!(jqesque.operation, Operation::Add);
+    /// assert_eq!(jqesque.to_string(), "+foo[0]=1");
+    /// ```
+    pub fn from_patch_operation(op: &PatchOperation) -> Self {
+        let (operation, value) = match op {
+            PatchOperation::Add(add) => (Operation::Add, Some(add.value.clone())),
+            PatchOperation::Replace(replace) => (Operation::Replace, Some(replace.value.clone())),
+            PatchOperation::Remove(_) => (Operation::Remove, None),
+            PatchOperation::Test(test) => (Operation::Test, Some(test.value.clone())),
+            PatchOperation::Copy(_) => (Operation::Copy, None),
+            PatchOperation::Move(_) => (Operation::Move, None),
+        };
+        let from = match op {
+            PatchOperation::Copy(copy) => Some(tokens_from_pointer(&copy.from)),
+            PatchOperation::Move(move_op) => Some(tokens_from_pointer(&move_op.from)),
+            _ => None,
+        };
+
+        Jqesque {
+            tokens: tokens_from_pointer(op.path()),
+            value,
+            operation,
+            required_type: None,
+            from,
+            expected: None,
+            separator: Separator::Dot,
+            pointer_cache: Default::default(),
+        }
+    }
+
+    /// Converts the parsed structure into the RFC6902 JSON Patch it applies.
+    ///
+    /// Only `Add`, `Replace`, `Remove`, `Test`, `Copy` and `Move` are JSON Patch operations, and
+    /// `RemoveIf` becomes a `test` followed by a `remove`, and `TestReplace` a `test` followed by
+    /// a `replace`. `Insert`, `Merge`, `Default` and `TestLen` have no direct patch
+    /// representation, and `Auto` only picks its operation once applied, so these return an
+    /// `UnsupportedOperationError`. Since a patch is built without a document, length-relative
+    /// indices other than `[#]` (which becomes `-`) and wildcards (which return a `WildcardError`)
+    /// cannot be represented either.
+    ///
+    /// ## Returns
+    ///
+    /// Returns a `json_patch::Patch` holding a single operation (two for `RemoveIf` and
+    /// `TestReplace`), or a JqesqueError if the structure cannot be represented as a patch.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    /// use serde_json::json;
+    ///
+    /// let mut patch = "+foo.bar=1".parse::<Jqesque>().unwrap().to_json_patch().unwrap();
+    /// patch.0.extend("-baz".parse::<Jqesque>().unwrap().to_json_patch().unwrap().0);
+    ///
+    /// let mut json_obj = json!({"foo": {}, "baz": true});
+    /// json_patch::patch(&mut json_obj, &patch).unwrap();
+    /// assert_eq!(json_obj, json!({"foo": {"bar": 1}}));
+    /// ```
+    pub fn to_json_patch(&self) -> Result<Patch, JqesqueError> {
+        let from_tokens = self.from.iter().flatten();
+        if let Some(offset) = self
+            .tokens
+            .iter()
+            .chain(from_tokens)
+            .find_map(|token| match token {
+                PathToken::LengthRelative(offset) if *offset != 0 => Some(*offset),
+                _ => None,
+            })
+        {
+            return Err(JqesqueError::InvalidPathError(
+                format!(
+                    "length-relative index #{:+} cannot be represented in a JSON Patch",
+                    offset
+                ),
+                None,
+            ));
+        }
+
+        if self.has_wildcard() {
+            return Err(JqesqueError::WildcardError(self.operation.clone()));
+        }
+
+        let path = self.to_pointer();
+        let value = || {
+            self.value
+                .clone()
+                .ok_or_else(|| JqesqueError::MissingValueError(self.operation.clone()))
+        };
+        let from = || {
+            self.from
+                .as_deref()
+                .map(tokens_to_pointer)
+                .ok_or_else(|| JqesqueError::MissingFromError(self.operation.clone()))
+        };
+
+        let patch_op = match self.operation {
+            Operation::Add => PatchOperation::Add(AddOperation {
+                path,
+                value: value()?,
+            }),
+            Operation::Replace => PatchOperation::Replace(ReplaceOperation {
+                path,
+                value: value()?,
+            }),
+            Operation::Remove => PatchOperation::Remove(RemoveOperation { path }),
+            Operation::RemoveIf => {
+                let test = PatchOperation::Test(TestOperation {
+                    path: path.clone(),
+                    value: value()?,
+                });
+                return Ok(Patch(vec![
+                    test,
+                    PatchOperation::Remove(RemoveOperation { path }),
+                ]));
+            }
+            Operation::TestReplace => {
+                let expected = self
+                    .expected
+                    .clone()
+                    .ok_or_else(|| JqesqueError::MissingExpectedError(self.operation.clone()))?;
+                let test = PatchOperation::Test(TestOperation {
+                    path: path.clone(),
+                    value: expected,
+                });
+                return Ok(Patch(vec![
+                    test,
+                    PatchOperation::Replace(ReplaceOperation {
+                        path,
+                        value: value()?,
+                    }),
+                ]));
+            }
+            Operation::Test => PatchOperation::Test(TestOperation {
+                path,
+                value: value()?,
+            }),
+            Operation::Copy => PatchOperation::Copy(CopyOperation {
+                from: from()?,
+                path,
+            }),
+            Operation::Move => PatchOperation::Move(MoveOperation {
+                from: from()?,
+                path,
+            }),
+            Operation::Insert
+            | Operation::Merge
+            | Operation::Default
+            | Operation::TestLen
+            | Operation::Auto => {
+                return Err(JqesqueError::UnsupportedOperationError(
+                    self.operation.clone(),
+                ))
+            }
+        };
+
+        Ok(Patch(vec![patch_op]))
+    }
+}
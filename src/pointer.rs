@@ -0,0 +1,106 @@
+use std::sync::OnceLock;
+
+use jsonptr::{index::Index, Pointer, PointerBuf, Token};
+
+use crate::types::PathToken;
+
+/// Converts path tokens to a JSON Pointer.
+///
+/// Keys are escaped as per [RFC6901](https://datatracker.ietf.org/doc/html/rfc6901#section-4),
+/// so `~` becomes `~0` and `/` becomes `~1`. Length-relative indices have no JSON Pointer
+/// representation until they are resolved against a document, so they are rendered as `-` (the
+/// RFC6901 past-the-end token) for `#` and as `#<offset>` (e.g. `#-1`) otherwise.
+///
+/// ## Arguments
+///
+/// * `tokens` - The path tokens to convert
+///
+/// ## Returns
+///
+/// Returns a `PointerBuf` representing the path tokens.
+///
+/// ## Example
+///
+/// ```rust
+/// use jqesque::{tokens_to_pointer, PathToken};
+///
+/// let tokens = vec![PathToken::Key("a/b".to_string()), PathToken::Index(0)];
+/// assert_eq!(tokens_to_pointer(&tokens).as_str(), "/a~1b/0");
+/// ```
+pub fn tokens_to_pointer(tokens: &[PathToken]) -> PointerBuf {
+    let tokens = tokens.iter().map(|token| match token {
+        // Token::new does the RFC6901 escaping
+        PathToken::Key(ref key) => Token::new(key),
+        PathToken::Index(idx) => Token::new(idx.to_string()),
+        PathToken::LengthRelative(0) | PathToken::Append => Token::new("-"),
+        PathToken::LengthRelative(offset) => Token::new(format!("#{:+}", offset)),
+        // JSON Pointer has no wildcards, so this refers to the key `*`
+        PathToken::Wildcard => Token::new("*"),
+    });
+
+    PointerBuf::from_tokens(tokens)
+}
+
+/// Converts a JSON Pointer to path tokens.
+///
+/// Array indices (e.g. `0`, but not `01`) become `PathToken::Index`, the past-the-end token `-`
+/// becomes `PathToken::Append`, and everything else becomes a key, with `~0` and `~1` unescaped.
+///
+/// ## Arguments
+///
+/// * `ptr` - The JSON Pointer to convert
+///
+/// ## Returns
+///
+/// Returns the path tokens, in order.
+///
+/// ## Example
+///
+/// ```rust
+/// use jqesque::{tokens_from_pointer, PathToken};
+/// use jsonptr::Pointer;
+///
+/// let tokens = tokens_from_pointer(Pointer::from_static("/a~1b/0/-"));
+/// assert_eq!(tokens, vec![
+///     PathToken::Key("a/b".to_string()),
+///     PathToken::Index(0),
+///     PathToken::Append,
+/// ]);
+/// ```
+pub fn tokens_from_pointer(ptr: &Pointer) -> Vec<PathToken> {
+    ptr.tokens()
+        .map(|token| match token.to_index() {
+            Ok(Index::Num(index)) => PathToken::Index(index),
+            Ok(Index::Next) => PathToken::Append,
+            Err(_) => PathToken::Key(token.decoded().into_owned()),
+        })
+        .collect()
+}
+
+/// The JSON Pointer of a `Jqesque`'s path, computed the first time it is needed.
+///
+/// Applying the same assignment to many documents then converts its tokens only once. The cache
+/// remembers the tokens it was computed from and is bypassed if they no longer match, since the
+/// public `tokens` field can be changed without going through `Jqesque::set_tokens`. Caches
+/// always compare equal, so that they do not affect the equality of the `Jqesque` holding them.
+#[derive(Clone, Default)]
+pub(crate) struct PointerCache(OnceLock<(Vec<PathToken>, PointerBuf)>);
+
+impl PointerCache {
+    /// Returns the JSON Pointer for `tokens`, computing and caching it if nothing is cached yet.
+    pub(crate) fn pointer(&self, tokens: &[PathToken]) -> PointerBuf {
+        match self
+            .0
+            .get_or_init(|| (tokens.to_vec(), tokens_to_pointer(tokens)))
+        {
+            (cached, pointer) if cached == tokens => pointer.clone(),
+            _ => tokens_to_pointer(tokens),
+        }
+    }
+}
+
+impl PartialEq for PointerCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
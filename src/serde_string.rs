@@ -0,0 +1,50 @@
+//! Serde support for `Jqesque` as its assignment string.
+//!
+//! `Jqesque` serializes structurally by default, as its tokens, value and operation. To write it
+//! as the single-line assignment string produced by its `Display` implementation instead (e.g.
+//! `">foo.bar[0]=true"`), and parse it back with `Separator::Dot` when deserializing, use this
+//! module with `#[serde(with = "jqesque::serde_string")]`. The string is always written with
+//! `Separator::Dot`, whatever separator the `Jqesque` was parsed with, so that it parses back:
+//!
+//! ```rust
+//! use jqesque::Jqesque;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "jqesque::serde_string")]
+//!     edit: Jqesque,
+//! }
+//!
+//! let config: Config = serde_json::from_str(r#"{"edit": ">foo.bar[0]=true"}"#).unwrap();
+//! assert_eq!(config.edit, ">foo.bar[0]=true".parse::<Jqesque>().unwrap());
+//! assert_eq!(
+//!     serde_json::to_string(&config).unwrap(),
+//!     r#"{"edit":">foo.bar[0]=true"}"#
+//! );
+//! ```
+
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+use crate::types::{Jqesque, Separator};
+
+/// Serializes a `Jqesque` as its assignment string, written with `Separator::Dot`.
+pub fn serialize<S>(jqesque: &Jqesque, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if *jqesque.separator() == Separator::Dot {
+        serializer.collect_str(jqesque)
+    } else {
+        serializer.collect_str(&jqesque.clone().with_separator(Separator::Dot))
+    }
+}
+
+/// Deserializes a `Jqesque` by parsing an assignment string.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Jqesque, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let input = String::deserialize(deserializer)?;
+    input.parse().map_err(de::Error::custom)
+}
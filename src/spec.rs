@@ -0,0 +1,167 @@
+use serde_json::{json, Map, Value};
+
+use crate::types::{Jqesque, JqesqueError, Operation, PathToken, Separator, ValueType};
+
+impl Jqesque {
+    /// Builds a `Jqesque` from a JSON description of the operation, without any string parsing.
+    ///
+    /// The description is an object with the following members:
+    ///
+    /// * `op` - The operation name, as rendered by `Operation`'s `Display` (e.g. `"insert"`).
+    ///   Optional, defaulting to `"auto"`.
+    /// * `path` - An array of path segments. Strings become keys, non-negative integers become
+    ///   array indices, `{"length_relative": n}` becomes a length-relative index and
+    ///   `{"wildcard": true}` becomes a wildcard.
+    /// * `value` - The value to assign. Optional, as some operations (e.g. `remove`) take none.
+    /// * `type` - The required type of the value, e.g. `"int"`. Optional.
+    /// * `from` - An array of path segments to take the value from, as for `path`. Required by
+    ///   `copy` and `move`, and ignored otherwise.
+    /// * `expected` - The value the path must hold before it is replaced. Required by
+    ///   `test_replace`, and ignored otherwise.
+    ///
+    /// ## Arguments
+    ///
+    /// * `spec` - The JSON description of the operation
+    ///
+    /// ## Returns
+    ///
+    /// Returns a `Jqesque` structure if successful, or an `InvalidSpecError` if the description is
+    /// malformed.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    /// use serde_json::json;
+    ///
+    /// let spec = json!({"op": "insert", "path": ["foo", "bar", 0, "baz"], "value": "hi"});
+    /// let jqesque = Jqesque::from_json_spec(&spec).unwrap();
+    ///
+    /// assert_eq!(jqesque.as_json(), json!({"foo": {"bar": [{"baz": "hi"}]}}));
+    /// assert_eq!(jqesque.to_json_spec(), spec);
+    /// ```
+    pub fn from_json_spec(spec: &Value) -> Result<Self, JqesqueError> {
+        let spec = spec
+            .as_object()
+            .ok_or_else(|| invalid_spec("expected an object"))?;
+
+        let operation = match spec.get("op") {
+            None => Operation::Auto,
+            Some(Value::String(name)) => Operation::from_name(name)
+                .ok_or_else(|| invalid_spec(format!("unknown operation `{}`", name)))?,
+            Some(_) => return Err(invalid_spec("`op` must be a string")),
+        };
+
+        let tokens = spec_path(spec.get("path"), "path")?;
+        let copies = matches!(operation, Operation::Copy | Operation::Move);
+        let from = match spec.get("from") {
+            None if copies => return Err(invalid_spec(format!("`{}` requires `from`", operation))),
+            Some(from) if copies => Some(spec_path(Some(from), "from")?),
+            _ => None,
+        };
+
+        let required_type = match spec.get("type") {
+            None => None,
+            Some(Value::String(name)) => Some(name.parse::<ValueType>()?),
+            Some(_) => return Err(invalid_spec("`type` must be a string")),
+        };
+
+        let expected = match spec.get("expected") {
+            None if operation == Operation::TestReplace => {
+                return Err(invalid_spec("`test_replace` requires `expected`"))
+            }
+            Some(expected) if operation == Operation::TestReplace => Some(expected.clone()),
+            _ => None,
+        };
+
+        Ok(Jqesque {
+            tokens,
+            value: spec.get("value").cloned(),
+            operation,
+            required_type,
+            from,
+            expected,
+            separator: Separator::Dot,
+            pointer_cache: Default::default(),
+        })
+    }
+
+    /// Converts the `Jqesque` into the JSON description accepted by `from_json_spec`.
+    ///
+    /// ## Returns
+    ///
+    /// Returns a `serde_json::Value` object with `op` and `path` members, and `value`, `type`,
+    /// `from` and `expected` members if present. `PathToken::Append` is written as
+    /// `{"length_relative": 0}`, which refers to the same position.
+    pub fn to_json_spec(&self) -> Value {
+        let mut spec = Map::new();
+        spec.insert("op".to_string(), json!(self.operation.to_string()));
+        spec.insert("path".to_string(), json_spec_path(&self.tokens));
+        if let Some(ref from) = self.from {
+            spec.insert("from".to_string(), json_spec_path(from));
+        }
+        if let Some(ref value) = self.value {
+            spec.insert("value".to_string(), value.clone());
+        }
+        if let Some(ref required_type) = self.required_type {
+            spec.insert("type".to_string(), json!(required_type.to_string()));
+        }
+        if let Some(ref expected) = self.expected {
+            spec.insert("expected".to_string(), expected.clone());
+        }
+        Value::Object(spec)
+    }
+}
+
+/// Converts the path member `name` of a JSON description into path tokens.
+fn spec_path(path: Option<&Value>, name: &str) -> Result<Vec<PathToken>, JqesqueError> {
+    path.and_then(Value::as_array)
+        .ok_or_else(|| invalid_spec(format!("`{}` must be an array", name)))?
+        .iter()
+        .map(spec_token)
+        .collect()
+}
+
+/// Converts path tokens into the path segments of a JSON description.
+fn json_spec_path(tokens: &[PathToken]) -> Value {
+    tokens
+        .iter()
+        .map(|token| match token {
+            PathToken::Key(key) => json!(key),
+            PathToken::Index(index) => json!(index),
+            PathToken::LengthRelative(offset) => json!({ "length_relative": offset }),
+            PathToken::Append => json!({ "length_relative": 0 }),
+            PathToken::Wildcard => json!({ "wildcard": true }),
+        })
+        .collect()
+}
+
+/// Converts a path segment of a JSON description into a path token.
+fn spec_token(segment: &Value) -> Result<PathToken, JqesqueError> {
+    match segment {
+        Value::String(key) => Ok(PathToken::Key(key.clone())),
+        Value::Number(number) => number
+            .as_u64()
+            .map(|index| PathToken::Index(index as usize))
+            .ok_or_else(|| {
+                invalid_spec(format!(
+                    "path index {} must be a non-negative integer",
+                    number
+                ))
+            }),
+        Value::Object(map) if map.len() == 1 && map.get("wildcard") == Some(&json!(true)) => {
+            Ok(PathToken::Wildcard)
+        }
+        Value::Object(map) => map
+            .get("length_relative")
+            .and_then(Value::as_i64)
+            .filter(|_| map.len() == 1)
+            .map(|offset| PathToken::LengthRelative(offset as isize))
+            .ok_or_else(|| invalid_spec(format!("invalid path segment {}", segment))),
+        _ => Err(invalid_spec(format!("invalid path segment {}", segment))),
+    }
+}
+
+fn invalid_spec(message: impl Into<String>) -> JqesqueError {
+    JqesqueError::InvalidSpecError(message.into())
+}
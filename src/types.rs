@@ -1,16 +1,46 @@
+use std::borrow::Cow;
 use std::fmt::Display;
 use std::str::FromStr;
 
-use json_patch::{AddOperation, Patch, PatchOperation, RemoveOperation, ReplaceOperation};
-use jsonptr::{Pointer, PointerBuf, Token};
+use json_patch::{
+    AddOperation, CopyOperation, MoveOperation, Patch, PatchErrorKind, PatchOperation,
+    RemoveOperation, ReplaceOperation,
+};
+use jsonptr::resolve::ResolveError;
+use jsonptr::{Pointer, PointerBuf};
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
 use thiserror::Error;
 
-use crate::manipulators::{insert_value, merge_json};
-use crate::parse::parse_input;
+use crate::manipulators::{
+    coerce_to_existing, entry_mut, expand_wildcards, first_unresolvable_token, insert_value,
+    insert_value_observed, match_key_case, merge_json_observed, remove_key,
+    resolve_relative_tokens, sort_keys, Observer,
+};
+use crate::options::{
+    ApplyOptions, KeyOrder, MergeStrategy, MissingPolicy, ParseOptions, DEFAULT_MAX_ARRAY_GROW,
+};
+use crate::parse::{parse_input, parse_input_with_options, parse_path};
+use crate::pointer::{tokens_to_pointer, PointerCache};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// A parsed assignment.
+///
+/// A `Jqesque` owns all of its data and holds no borrows from the input it was parsed from, so
+/// it can be stored, sent between threads, or outlive the input string freely. The same holds
+/// for `JqesqueError`, which stores its messages as owned strings.
+///
+/// The fields are public, but prefer `Jqesque::from_parts` and `Jqesque::into_parts` (or the
+/// accessors) to build and take apart a `Jqesque`, as they will keep working if fields are added.
+///
+/// The JSON Pointer of the path is computed the first time it is needed and then reused, so a
+/// `Jqesque` parsed once and applied to many documents does not convert its path every time.
+/// Paths with length-relative indices or appends are still resolved against each document.
+/// In the `pointer_cache` bench, getting the pointer of a five-token path 10k times goes from
+/// 1.6ms to 0.36ms, and applying one `Jqesque` to 10k documents saves about 0.5ms of 13.9ms.
+///
+/// The separator the path was parsed with is kept for `Display`, but two `Jqesque`s that differ
+/// only in their separator are equal.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Jqesque {
     // The path tokens representing the path to the value (the left-hand side of the assignment)
     pub tokens: Vec<PathToken>,
@@ -18,6 +48,48 @@ pub struct Jqesque {
     pub value: Option<Value>,
     // The operation to perform
     pub operation: Operation,
+    // The type the value is required to have, if annotated (e.g. `count:int=5`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_type: Option<ValueType>,
+    // The path to take the value from, for `Copy` and `Move` (e.g. `&dest<source`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<Vec<PathToken>>,
+    // The value the path must hold before it is replaced, for `TestReplace` (e.g. `?=a=1=>2`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<Value>,
+    // The separator the path was parsed with, and is formatted with by `Display`
+    #[serde(default, skip_serializing_if = "Separator::is_dot")]
+    pub(crate) separator: Separator,
+    // The JSON Pointer of `tokens`, once it has been needed
+    #[serde(skip)]
+    pub(crate) pointer_cache: PointerCache,
+}
+
+// Written out to leave the separator out, as it only changes how the path is formatted
+impl PartialEq for Jqesque {
+    fn eq(&self, other: &Self) -> bool {
+        self.tokens == other.tokens
+            && self.value == other.value
+            && self.operation == other.operation
+            && self.required_type == other.required_type
+            && self.from == other.from
+            && self.expected == other.expected
+    }
+}
+
+// Written out to leave the pointer cache out
+impl std::fmt::Debug for Jqesque {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Jqesque")
+            .field("tokens", &self.tokens)
+            .field("value", &self.value)
+            .field("operation", &self.operation)
+            .field("required_type", &self.required_type)
+            .field("from", &self.from)
+            .field("expected", &self.expected)
+            .field("separator", &self.separator)
+            .finish()
+    }
 }
 
 impl FromStr for Jqesque {
@@ -49,6 +121,55 @@ impl FromStr for Jqesque {
     }
 }
 
+impl Display for Jqesque {
+    /// Formats the `Jqesque` back into assignment syntax, using the separator it was parsed with
+    /// (see `Jqesque::separator`).
+    ///
+    /// Keys that are not plain identifiers are quoted, and the value is serialized as JSON, so
+    /// the output parses back to an equal `Jqesque` with the same separator.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Separator};
+    ///
+    /// let jqesque = ">foo.bar[0].baz=true".parse::<Jqesque>().unwrap();
+    /// assert_eq!(jqesque.to_string(), ">foo.bar[0].baz=true");
+    ///
+    /// let jqesque = Jqesque::from_str_with_separator("foo/bar=1", Separator::Slash).unwrap();
+    /// assert_eq!(jqesque.to_string(), "foo/bar=1");
+    /// assert_eq!(jqesque.with_separator(Separator::Dot).to_string(), "foo.bar=1");
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(prefix) = self.operation.prefix() {
+            write!(f, "{}", prefix)?;
+        }
+        let unprefixed = self.operation == Operation::Auto;
+        write!(
+            f,
+            "{}",
+            format_path(&self.tokens, &self.separator, unprefixed)
+        )?;
+        if let Some(ref required_type) = self.required_type {
+            write!(f, ":{}", required_type)?;
+        }
+        if let Some(ref from) = self.from {
+            write!(f, "<{}", format_path(from, &self.separator, false))?;
+        }
+        if let Some(ref expected) = self.expected {
+            write!(f, "={}=>", expected)?;
+            if let Some(ref value) = self.value {
+                write!(f, "{}", value)?;
+            }
+            return Ok(());
+        }
+        match self.value {
+            Some(ref value) if self.operation != Operation::Remove => write!(f, "={}", value),
+            _ => Ok(()),
+        }
+    }
+}
+
 impl Jqesque {
     /// Parses an input string into a `Jqesque` structure using the specified separator.
     ///
@@ -59,17 +180,28 @@ impl Jqesque {
     ///
     /// ## Input operators
     ///
-    /// The input string can optionally start with an operator character to specify the operation to perform.
-    ///
-    /// * `>` - **Insert:** Inserts the value into the JSON object at the specified path, using a custom insert operation.
-    /// * `~` - **Merge:** Merges the value into the JSON object at the specified path, using a custom merge operation.
-    /// * `+` - **Add:** Adds the value to the JSON object at the specified path, using the JSON Patch `add` operation.
-    /// * `-` - **Remove:** Removes the value from the JSON object at the specified path, using the JSON Patch `remove` operation.
-    /// * `=` - **Replace:** Replaces the value in the JSON object at the specified path, using the JSON Patch `replace` operation.
-    /// * `?` - **Test:** Tests the value in the JSON object at the specified path, using the JSON Patch `test` operation.
-    ///
-    /// If no operator is specified, the default operator is `Insert`. For details on each operation, see their respective
-    /// fields in the `Operation` enum.
+    /// The input string can optionally start with an operator to specify the operation to
+    /// perform.
+    ///
+    /// * `>` - **Insert:** Inserts the value at the path, creating any missing structure.
+    /// * `~` - **Merge:** Merges the value into the value at the path, creating any missing
+    ///   structure.
+    /// * `+` - **Add:** Adds the value at the path, using the JSON Patch `add` operation.
+    /// * `-` - **Remove:** Removes the value at the path, using the JSON Patch `remove` operation.
+    /// * `=` - **Replace:** Replaces the value at the path, using the JSON Patch `replace`
+    ///   operation.
+    /// * `?` - **Test:** Tests the value at the path, using the JSON Patch `test` operation.
+    /// * `&` - **Copy:** Copies the value at another path to the path, as in `&dest<source`.
+    /// * `@` - **Move:** Moves the value at another path to the path, as in `@dest<source`.
+    /// * `:` - **Default:** Inserts the value at the path only if the path does not exist.
+    /// * `-?` - **RemoveIf:** Removes the value at the path only if it equals the given value.
+    /// * `?#` - **TestLen:** Tests the length of the array or object at the path.
+    /// * `?=` - **TestReplace:** Replaces the value at the path only if it equals the expected
+    ///   value, as in `?=path=expected=>new`.
+    ///
+    /// If no operator is specified, the operation is `Auto`, which replaces the value if the path
+    /// exists and adds or inserts it otherwise. For details on each operation, see their
+    /// respective variants in the `Operation` enum.
     ///
     /// ## Returns
     ///
@@ -89,7 +221,209 @@ impl Jqesque {
         input: &str,
         separator: Separator,
     ) -> Result<Self, JqesqueError> {
-        parse_input(input, separator)
+        Self::parse_with_options(input, &ParseOptions::new().separator(separator))
+    }
+
+    /// Parses an input string into a `Jqesque` structure using the specified options.
+    ///
+    /// All parsing configuration, including the separator, is held by `ParseOptions`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `input` - The input string to parse
+    /// * `options` - The options controlling how the input is parsed
+    ///
+    /// ## Returns
+    ///
+    /// Returns a `Jqesque` structure if successful, or a `ParseError` if parsing fails.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, ParseOptions, Separator};
+    /// use serde_json::json;
+    ///
+    /// let options = ParseOptions::new()
+    ///     .separator(Separator::Slash)
+    ///     .infer_types(false);
+    /// let jqesque = Jqesque::parse_with_options(">app/version=1.10", &options).unwrap();
+    ///
+    /// assert_eq!(jqesque.as_json(), json!({"app": {"version": "1.10"}}));
+    /// ```
+    pub fn parse_with_options(input: &str, options: &ParseOptions) -> Result<Self, JqesqueError> {
+        parse_input_with_options(input, options)
+    }
+
+    /// Parses an input string into a `Jqesque` structure using the specified separator and options.
+    ///
+    /// The separator overrides the one set in `options`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `input` - The input string to parse
+    /// * `separator` - The separator to use between keys
+    /// * `options` - The options controlling how the input is parsed
+    ///
+    /// ## Returns
+    ///
+    /// Returns a `Jqesque` structure if successful, or a `ParseError` if parsing fails.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, ParseOptions, Separator};
+    /// use serde_json::json;
+    ///
+    /// let options = ParseOptions::new().infer_types(false);
+    /// let jqesque =
+    ///     Jqesque::from_str_with_options("version=1.10", Separator::Dot, options).unwrap();
+    ///
+    /// assert_eq!(jqesque.value, Some(json!("1.10")));
+    /// ```
+    pub fn from_str_with_options(
+        input: &str,
+        separator: Separator,
+        options: ParseOptions,
+    ) -> Result<Self, JqesqueError> {
+        Self::parse_with_options(input, &options.separator(separator))
+    }
+
+    /// Parses an owned input string into a `Jqesque` structure using the default `Separator::Dot`.
+    ///
+    /// This is a convenience for APIs that want to move the input in. Since the parsed structure
+    /// never borrows from the input, the input is simply dropped once parsing is done.
+    ///
+    /// ## Arguments
+    ///
+    /// * `input` - The input string to parse
+    ///
+    /// ## Returns
+    ///
+    /// Returns a `Jqesque` structure if successful, or a `ParseError` if parsing fails.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    ///
+    /// let input = String::from("foo.bar=baz");
+    /// let jqesque = Jqesque::parse_owned(input).unwrap();
+    /// ```
+    pub fn parse_owned(input: String) -> Result<Self, JqesqueError> {
+        parse_input(&input, Separator::Dot)
+    }
+
+    /// Builds a `Jqesque` from literal path segments, without any parsing.
+    ///
+    /// Each segment becomes a `PathToken::Key` as-is: there is no separator splitting, quoting or
+    /// escaping to worry about, and numeric-looking segments such as `"0"` stay keys. Use this
+    /// when the path already comes from structured data, e.g. a `Vec<String>`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `segments` - The keys making up the path
+    /// * `value` - The value to assign, if any
+    /// * `operation` - The operation to perform
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Operation, PathToken};
+    /// use serde_json::json;
+    ///
+    /// let jqesque = Jqesque::from_segments(["a.b", "0"], Some(json!(1)), Operation::Insert);
+    /// assert_eq!(jqesque.tokens(), &[
+    ///     PathToken::Key("a.b".to_string()),
+    ///     PathToken::Key("0".to_string()),
+    /// ]);
+    /// assert_eq!(jqesque.as_json(), json!({"a.b": {"0": 1}}));
+    /// ```
+    pub fn from_segments<I, S>(segments: I, value: Option<Value>, operation: Operation) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Jqesque {
+            tokens: segments
+                .into_iter()
+                .map(|segment| PathToken::Key(segment.into()))
+                .collect(),
+            value,
+            operation,
+            required_type: None,
+            from: None,
+            expected: None,
+            separator: Separator::Dot,
+            pointer_cache: Default::default(),
+        }
+    }
+
+    /// Builds a `Jqesque` from its operation, path tokens and value.
+    ///
+    /// This is the preferred way to construct a `Jqesque` programmatically, as it keeps working
+    /// should the fields of the struct change. The result has no type annotation.
+    ///
+    /// ## Arguments
+    ///
+    /// * `operation` - The operation to perform
+    /// * `tokens` - The path tokens representing the path to the value
+    /// * `value` - The value to assign, if any
+    ///
+    /// ## Returns
+    ///
+    /// Returns the `Jqesque`, a `MissingValueError` if the operation needs a value (i.e. is not
+    /// `Remove`) and none was given, a `MissingFromError` for `Copy` and `Move`, as they also
+    /// need a path to take the value from, or a `MissingExpectedError` for `TestReplace`, as it
+    /// also needs the value to test for.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Operation, PathToken};
+    /// use serde_json::json;
+    ///
+    /// let tokens = vec![PathToken::Key("foo".to_string())];
+    /// let jqesque = Jqesque::from_parts(Operation::Insert, tokens, Some(json!(1))).unwrap();
+    /// assert_eq!(jqesque, ">foo=1".parse::<Jqesque>().unwrap());
+    ///
+    /// let (operation, tokens, value) = jqesque.into_parts();
+    /// assert_eq!(operation, Operation::Insert);
+    /// assert_eq!(tokens, vec![PathToken::Key("foo".to_string())]);
+    /// assert_eq!(value, Some(json!(1)));
+    /// ```
+    pub fn from_parts(
+        operation: Operation,
+        tokens: Vec<PathToken>,
+        value: Option<Value>,
+    ) -> Result<Self, JqesqueError> {
+        match operation {
+            Operation::Copy | Operation::Move => {
+                return Err(JqesqueError::MissingFromError(operation))
+            }
+            Operation::TestReplace => return Err(JqesqueError::MissingExpectedError(operation)),
+            Operation::Remove => {}
+            _ if value.is_none() => return Err(JqesqueError::MissingValueError(operation)),
+            _ => {}
+        }
+
+        Ok(Jqesque {
+            tokens,
+            value,
+            operation,
+            required_type: None,
+            from: None,
+            expected: None,
+            separator: Separator::Dot,
+            pointer_cache: Default::default(),
+        })
+    }
+
+    /// Splits the `Jqesque` into its operation, path tokens and value.
+    ///
+    /// This is the counterpart of `from_parts`. Any type annotation, the path taken from (for
+    /// `Copy` and `Move`) and the expected value (for `TestReplace`) are dropped.
+    pub fn into_parts(self) -> (Operation, Vec<PathToken>, Option<Value>) {
+        (self.operation, self.tokens, self.value)
     }
 
     /// Returns the path tokens of the parsed structure.
@@ -97,6 +431,46 @@ impl Jqesque {
         &self.tokens
     }
 
+    /// Sets the path tokens, replacing the path of the assignment.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, PathToken};
+    ///
+    /// let mut jqesque = "foo=1".parse::<Jqesque>().unwrap();
+    /// jqesque.set_tokens(vec![PathToken::Key("bar".to_string())]);
+    /// assert_eq!(jqesque.to_pointer().as_str(), "/bar");
+    /// ```
+    pub fn set_tokens(&mut self, tokens: Vec<PathToken>) {
+        self.tokens = tokens;
+        self.pointer_cache = PointerCache::default();
+    }
+
+    /// Returns the separator the path was parsed with, which `Display` formats it with.
+    ///
+    /// This is `Separator::Dot` for a `Jqesque` that was not parsed, e.g. one built with
+    /// `JqesqueBuilder` or `from_parts`.
+    pub fn separator(&self) -> &Separator {
+        &self.separator
+    }
+
+    /// Returns the `Jqesque` with its path formatted by `Display` using `separator` instead.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Separator};
+    ///
+    /// let jqesque = "a.b=1".parse::<Jqesque>().unwrap().with_separator(Separator::Slash);
+    /// assert_eq!(jqesque.separator(), &Separator::Slash);
+    /// assert_eq!(jqesque.to_string(), "a/b=1");
+    /// ```
+    pub fn with_separator(mut self, separator: Separator) -> Self {
+        self.separator = separator;
+        self
+    }
+
     /// Returns the value from the parsed structure.
     ///
     /// This function returns a reference to the `serde_json::Value` object that was parsed.
@@ -128,34 +502,154 @@ impl Jqesque {
         &self.value
     }
 
+    /// Returns a mutable reference to the value, to adjust it before applying.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    /// use serde_json::json;
+    ///
+    /// let mut jqesque = "greeting=hello".parse::<Jqesque>().unwrap();
+    /// if let Some(value) = jqesque.value_mut() {
+    ///     *value = json!(format!("{}, world", value.as_str().unwrap()));
+    /// }
+    /// assert_eq!(jqesque.value(), &Some(json!("hello, world")));
+    /// ```
+    pub fn value_mut(&mut self) -> Option<&mut Value> {
+        self.value.as_mut()
+    }
+
+    /// Takes the value out, leaving `None` in its place.
+    pub fn take_value(&mut self) -> Option<Value> {
+        self.value.take()
+    }
+
+    /// Sets the value, replacing any value already there.
+    pub fn set_value(&mut self, value: impl Into<Value>) {
+        self.value = Some(value.into());
+    }
+
+    /// Returns the value at the first of several candidate paths that exists in a JSON object.
+    ///
+    /// This is useful when migrating key names, where a value may live under either its new or
+    /// its legacy name. The candidates are bare paths (no operation or value) and are tried in
+    /// order.
+    ///
+    /// ## Arguments
+    ///
+    /// * `paths` - The candidate paths, e.g., `["a.b", "a.legacy_b"]`
+    /// * `separator` - The separator used in the candidate paths
+    /// * `json` - The JSON object to look the paths up in
+    ///
+    /// ## Returns
+    ///
+    /// Returns the index of the first candidate that exists along with a reference to its value,
+    /// `None` if none of the candidates exist, or a `ParseError` or `TrailingInputError` if a
+    /// candidate fails to parse.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Separator};
+    /// use serde_json::json;
+    ///
+    /// let json_obj = json!({"a": {"legacy_b": 1}});
+    /// let found = Jqesque::get_first(&["a.b", "a.legacy_b"], Separator::Dot, &json_obj).unwrap();
+    ///
+    /// assert_eq!(found, Some((1, &json!(1))));
+    /// ```
+    pub fn get_first<'a>(
+        paths: &[&str],
+        separator: Separator,
+        json: &'a Value,
+    ) -> Result<Option<(usize, &'a Value)>, JqesqueError> {
+        for (index, path) in paths.iter().enumerate() {
+            let tokens = parse_path(path, &separator)?;
+            let Ok(tokens) = resolve_relative_tokens(json, &tokens, false) else {
+                continue;
+            };
+            let pointer_buf = tokens_to_pointer(&tokens);
+            let pointer: &Pointer = &pointer_buf;
+            if let Ok(value) = pointer.resolve(json) {
+                return Ok(Some((index, value)));
+            }
+        }
+        Ok(None)
+    }
+
     /// Converts the parsed structure into a new JSON object.
     ///
-    /// This function returns a new JSON object representing the parsed structure.
+    /// This function returns a new JSON object representing the parsed structure: the document
+    /// the value would create for `Insert`, `Merge` and `Default`, and an array of JSON Patch
+    /// operations for the operations that have one. `TestLen` has no JSON Patch representation,
+    /// so it is described by an object instead: `?#items=3` gives
+    /// `{"test_len": {"path": "/items", "length": 3}}`.
+    ///
+    /// The document for `Insert`, `Merge` and `Default` (and the `Insert` part of `Auto`) cannot
+    /// always be built, e.g. for an index beyond `DEFAULT_MAX_ARRAY_GROW` or a length-relative
+    /// index below the start of the new array. `as_json` then returns `null`; use `try_as_json`
+    /// to get the error instead.
     ///
     /// ## Returns
     ///
     /// Returns a `serde_json::Value` object.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    /// use serde_json::{json, Value};
+    ///
+    /// assert_eq!(">a[1]=1".parse::<Jqesque>().unwrap().as_json(), json!({"a": [null, 1]}));
+    /// assert_eq!(">a[200000]=1".parse::<Jqesque>().unwrap().as_json(), Value::Null);
+    /// ```
     pub fn as_json(&self) -> Value {
-        match self.operation {
+        self.try_as_json().unwrap_or(Value::Null)
+    }
+
+    /// Converts the parsed structure into a new JSON object, as `as_json` does.
+    ///
+    /// ## Returns
+    ///
+    /// Returns the `serde_json::Value` object, or the error building the document for `Insert`,
+    /// `Merge`, `Default` or `Auto` failed with (e.g. an `IndexTooLargeError` or a
+    /// `RelativeIndexError`).
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, JqesqueError};
+    ///
+    /// let jqesque = ">a[200000]=1".parse::<Jqesque>().unwrap();
+    /// assert!(matches!(
+    ///     jqesque.try_as_json(),
+    ///     Err(JqesqueError::IndexTooLargeError { index: 200000, .. })
+    /// ));
+    /// ```
+    pub fn try_as_json(&self) -> Result<Value, JqesqueError> {
+        Ok(match self.operation {
             Operation::Auto => {
                 // For auto, return as an array of operations
                 let mut json_obj = Value::Array(Vec::new());
                 for op in &[Operation::Replace, Operation::Add, Operation::Insert] {
                     let mut jq = self.clone();
                     jq.operation = op.clone();
-                    let op_json = jq.as_json();
+                    let op_json = jq.try_as_json()?;
                     json_obj.as_array_mut().unwrap().push(op_json);
                 }
                 json_obj
             }
             Operation::Add | Operation::Replace | Operation::Remove | Operation::Test => {
-                let pointer_buf = self.tokens_to_pointer();
+                let pointer_buf = self.to_pointer();
                 let op_json = match self.operation {
-                    Operation::Add | Operation::Replace | Operation::Test => json!({
-                        "op": self.operation.to_string(),
-                        "path": pointer_buf.to_string(),
-                        "value": self.value.clone().unwrap_or(Value::Null)
-                    }),
+                    Operation::Add | Operation::Replace | Operation::Test => {
+                        json!({
+                            "op": self.operation.to_string(),
+                            "path": pointer_buf.to_string(),
+                            "value": self.value.clone().unwrap_or(Value::Null)
+                        })
+                    }
                     Operation::Remove => json!({
                         "op": self.operation.to_string(),
                         "path": pointer_buf.to_string()
@@ -164,177 +658,1388 @@ impl Jqesque {
                 };
                 json!([op_json]) // Return as an array of operations
             }
-            Operation::Merge | Operation::Insert => {
-                // For merge and insert, return the value to be merged or inserted
+            Operation::TestLen => json!({
+                "test_len": {
+                    "path": self.to_pointer().to_string(),
+                    "length": self.value.clone().unwrap_or(Value::Null)
+                }
+            }),
+            Operation::RemoveIf => {
+                let path = self.to_pointer().to_string();
+                json!([
+                    {
+                        "op": Operation::Test.to_string(),
+                        "path": path,
+                        "value": self.value.clone().unwrap_or(Value::Null)
+                    },
+                    {"op": Operation::Remove.to_string(), "path": path}
+                ])
+            }
+            Operation::TestReplace => {
+                let path = self.to_pointer().to_string();
+                json!([
+                    {
+                        "op": Operation::Test.to_string(),
+                        "path": path,
+                        "value": self.expected.clone().unwrap_or(Value::Null)
+                    },
+                    {
+                        "op": Operation::Replace.to_string(),
+                        "path": path,
+                        "value": self.value.clone().unwrap_or(Value::Null)
+                    }
+                ])
+            }
+            Operation::Copy | Operation::Move => json!([{
+                "op": self.operation.to_string(),
+                "from": tokens_to_pointer(self.from.as_deref().unwrap_or_default()).to_string(),
+                "path": self.to_pointer().to_string()
+            }]),
+            Operation::Merge | Operation::Insert | Operation::Default => {
+                // For merge, insert and default, return the value to be merged or inserted
                 let mut json_obj = Value::Null;
-                insert_value(&mut json_obj, &self.tokens, &self.value);
+                insert_value(
+                    &mut json_obj,
+                    &self.tokens,
+                    &self.value,
+                    DEFAULT_MAX_ARRAY_GROW,
+                )?;
                 json_obj
             }
+        })
+    }
+
+    /// Applies the parsed structure to a JSON object.
+    ///
+    /// This function applies the parsed structure to the provided JSON object, performing the operation specified
+    /// during parsing. The operation is performed in-place, modifying the provided JSON object.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object to apply the operation to
+    ///
+    /// ## Returns
+    ///
+    /// Returns the operation that was performed or a JqesqueError if an error occurred.
+    pub fn apply_to(&self, json: &mut Value) -> Result<Operation, JqesqueError> {
+        self.apply_to_detailed(json)
+            .map(|outcome| outcome.operation())
+    }
+
+    /// Applies the parsed structure to the part of a JSON object at `root`.
+    ///
+    /// The path of the assignment is taken relative to `root`, so sections of a large document
+    /// can be edited without repeating their location in every path.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object containing the part to apply the operation to
+    /// * `root` - The JSON Pointer to the part to apply the operation to
+    ///
+    /// ## Returns
+    ///
+    /// Returns the operation that was performed, an `InvalidPathError` if `root` does not exist,
+    /// or a JqesqueError if the operation fails.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    /// use jsonptr::Pointer;
+    /// use serde_json::json;
+    ///
+    /// let mut json_obj = json!({"settings": {"theme": {"color": "red"}}});
+    /// let jqesque = "color=blue".parse::<Jqesque>().unwrap();
+    /// let root = Pointer::parse("/settings/theme").unwrap();
+    ///
+    /// jqesque.apply_to_at(&mut json_obj, root).unwrap();
+    /// assert_eq!(json_obj, json!({"settings": {"theme": {"color": "blue"}}}));
+    /// ```
+    pub fn apply_to_at(&self, json: &mut Value, root: &Pointer) -> Result<Operation, JqesqueError> {
+        let subtree = root.resolve_mut(json).map_err(|e| {
+            JqesqueError::InvalidPathError(
+                format!("root {} cannot be resolved: {}", root, e),
+                Some(ErrorSource::new(e)),
+            )
+        })?;
+        self.apply_to(subtree)
+    }
+
+    /// Applies the parsed structure to a JSON object held as a `serde_json::Map`.
+    ///
+    /// This behaves like `apply_to`, without having to wrap the map in a `Value` first. As the map
+    /// must remain an object, an assignment that would replace the root with anything else fails
+    /// with a `TypeMismatchError` and leaves the map unchanged.
+    ///
+    /// ## Arguments
+    ///
+    /// * `map` - The JSON object to apply the operation to
+    ///
+    /// ## Returns
+    ///
+    /// Returns the operation that was performed or a JqesqueError if an error occurred.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    /// use serde_json::{json, Map};
+    ///
+    /// let mut map = Map::new();
+    /// let jqesque = ">settings.theme=dark".parse::<Jqesque>().unwrap();
+    ///
+    /// jqesque.apply_to_map(&mut map).unwrap();
+    /// assert_eq!(map.get("settings"), Some(&json!({"theme": "dark"})));
+    /// ```
+    pub fn apply_to_map(&self, map: &mut Map<String, Value>) -> Result<Operation, JqesqueError> {
+        // Only an assignment to the root itself can replace it, so only then is the original kept
+        let original = self.tokens.is_empty().then(|| map.clone());
+        let mut json = Value::Object(std::mem::take(map));
+        let result = self.apply_to(&mut json);
+        match json {
+            Value::Object(object) => {
+                *map = object;
+                result
+            }
+            actual => {
+                *map = original.unwrap_or_default();
+                Err(JqesqueError::TypeMismatchError {
+                    expected: ValueType::Object,
+                    actual,
+                })
+            }
+        }
+    }
+
+    /// Applies the parsed structure to a JSON object, returning a detailed outcome.
+    ///
+    /// This behaves exactly like `apply_to`, but for an `Add` into an array the outcome also tells
+    /// whether the value was appended to the end of the array or inserted before an existing
+    /// element (shifting it and the ones after it), and at which index it ended up.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object to apply the operation to
+    ///
+    /// ## Returns
+    ///
+    /// Returns an `ApplyOutcome` describing what was done, or a JqesqueError if an error occurred.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{ApplyOutcome, Jqesque, Separator};
+    /// use serde_json::json;
+    ///
+    /// let mut json_obj = json!({"array": [1, 2, 3]});
+    /// let jqesque = Jqesque::from_str_with_separator("+array/-=4", Separator::Slash).unwrap();
+    ///
+    /// let outcome = jqesque.apply_to_detailed(&mut json_obj).unwrap();
+    /// assert_eq!(outcome, ApplyOutcome::Appended { index: 3 });
+    /// ```
+    pub fn apply_to_detailed(&self, json: &mut Value) -> Result<ApplyOutcome, JqesqueError> {
+        self.apply_to_with_options(json, &ApplyOptions::default())
+    }
+
+    /// Applies the parsed structure to a JSON object with the given options.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object to apply the operation to
+    /// * `options` - The options controlling how the operation is applied
+    ///
+    /// ## Returns
+    ///
+    /// Returns an `ApplyOutcome` describing what was done, or a JqesqueError if an error occurred.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{ApplyOptions, ApplyOutcome, Jqesque, Operation};
+    /// use serde_json::json;
+    ///
+    /// let mut json_obj = json!({"foo": 1});
+    /// let jqesque = "-bar".parse::<Jqesque>().unwrap();
+    /// let options = ApplyOptions::new().remove_if_exists(true);
+    ///
+    /// let outcome = jqesque.apply_to_with_options(&mut json_obj, &options).unwrap();
+    /// assert_eq!(outcome, ApplyOutcome::Unchanged(Operation::Remove));
+    /// ```
+    pub fn apply_to_with_options(
+        &self,
+        json: &mut Value,
+        options: &ApplyOptions,
+    ) -> Result<ApplyOutcome, JqesqueError> {
+        self.apply_observed(json, options, &mut |_, _| {})
+    }
+
+    /// Applies the parsed structure to a JSON object with the given options, telling `observer`
+    /// about the nodes an `Insert`, `Merge` or `Default` walks through.
+    pub(crate) fn apply_observed(
+        &self,
+        json: &mut Value,
+        options: &ApplyOptions,
+        observer: &mut Observer,
+    ) -> Result<ApplyOutcome, JqesqueError> {
+        let outcome = self.apply_unordered(json, options, observer)?;
+        if options.key_order == KeyOrder::Sorted {
+            sort_keys(json);
+        }
+        Ok(outcome)
+    }
+
+    /// Applies the parsed structure to a JSON object, leaving the order of keys to `serde_json`.
+    fn apply_unordered(
+        &self,
+        json: &mut Value,
+        options: &ApplyOptions,
+        observer: &mut Observer,
+    ) -> Result<ApplyOutcome, JqesqueError> {
+        if let (Some(required_type), Some(value)) = (&self.required_type, &self.value) {
+            if !required_type.matches(value) {
+                return Err(JqesqueError::TypeMismatchError {
+                    expected: *required_type,
+                    actual: value.clone(),
+                });
+            }
+        }
+
+        if options.case_insensitive_keys
+            && matches!(
+                self.operation,
+                Operation::Test
+                    | Operation::TestLen
+                    | Operation::Remove
+                    | Operation::Replace
+                    | Operation::RemoveIf
+                    | Operation::TestReplace
+            )
+        {
+            if let Cow::Owned(tokens) = match_key_case(json, &self.tokens)? {
+                let mut matched = self.clone();
+                matched.tokens = tokens;
+                return matched.apply_unordered(json, options, observer);
+            }
+        }
+
+        if self.has_wildcard() {
+            return self.apply_wildcard_to(json, options, observer);
+        }
+
+        if options.on_missing == MissingPolicy::Skip
+            && matches!(
+                self.operation,
+                Operation::Remove
+                    | Operation::Test
+                    | Operation::TestLen
+                    | Operation::RemoveIf
+                    | Operation::TestReplace
+            )
+            && !self.path_exists(json)
+        {
+            return Ok(ApplyOutcome::Skipped(self.operation.clone()));
+        }
+
+        if options.coerce_to_existing_type
+            && self.required_type.is_none()
+            && matches!(
+                self.operation,
+                Operation::Insert
+                    | Operation::Merge
+                    | Operation::Replace
+                    | Operation::Add
+                    | Operation::Auto
+            )
+        {
+            if let (Some(value), Ok(existing)) = (&self.value, self.resolve(json)) {
+                let mut coerced = value.clone();
+                coerce_to_existing(existing, &mut coerced);
+                if coerced != *value {
+                    let mut matched = self.clone();
+                    matched.value = Some(coerced);
+                    return matched.apply_unordered(json, options, observer);
+                }
+            }
+        }
+
+        match self.operation {
+            Operation::Auto => {
+                validate_auto_order(&options.auto_order)?;
+
+                // Try each operation in turn, returning the error of the last one if all fail
+                let mut result = None;
+                for operation in &options.auto_order {
+                    let mut attempt = self.clone();
+                    attempt.operation = operation.clone();
+                    let outcome = attempt.apply_unordered(json, options, observer);
+                    if outcome.is_ok() {
+                        return outcome;
+                    }
+                    result = Some(outcome);
+                }
+                result.expect("auto order should not be empty after validation")
+            }
+            Operation::Add | Operation::Replace => {
+                if let Some(ref value) = self.value {
+                    let pointer_buf = self.resolved_pointer(json, false)?;
+
+                    let outcome = match self.operation {
+                        Operation::Add => add_outcome(json, &pointer_buf),
+                        _ => ApplyOutcome::Applied(self.operation.clone()),
+                    };
+
+                    let patch_op = match self.operation {
+                        Operation::Add => PatchOperation::Add(AddOperation {
+                            path: pointer_buf,
+                            value: value.clone(),
+                        }),
+                        Operation::Replace => PatchOperation::Replace(ReplaceOperation {
+                            path: pointer_buf,
+                            value: value.clone(),
+                        }),
+                        _ => unreachable!(),
+                    };
+
+                    let patch = Patch(vec![patch_op]);
+                    json_patch::patch(json, &patch).map_err(|e| patch_error(&self.operation, e))?;
+                    Ok(outcome)
+                } else {
+                    Err(JqesqueError::MissingValueError(self.operation.clone()))
+                }
+            }
+            Operation::Remove => {
+                let pointer_buf = match self.resolved_pointer(json, false) {
+                    Ok(pointer_buf) => pointer_buf,
+                    Err(_) if options.remove_if_exists => {
+                        return Ok(ApplyOutcome::Unchanged(Operation::Remove))
+                    }
+                    Err(e) => return Err(e),
+                };
+                let pointer: &Pointer = &pointer_buf;
+                if options.remove_if_exists && pointer.resolve(json).is_err() {
+                    return Ok(ApplyOutcome::Unchanged(Operation::Remove));
+                }
+
+                let patch_op = PatchOperation::Remove(RemoveOperation { path: pointer_buf });
+                let patch = Patch(vec![patch_op]);
+                json_patch::patch(json, &patch).map_err(|e| patch_error(&self.operation, e))?;
+                Ok(ApplyOutcome::Applied(Operation::Remove))
+            }
+            Operation::Test => {
+                if let Some(ref expected_value) = self.value {
+                    let tokens = resolve_relative_tokens(json, &self.tokens, false)?;
+                    let pointer_buf = self.pointer_to(&tokens);
+                    let pointer: &Pointer = &pointer_buf;
+
+                    match pointer.resolve(json) {
+                        Ok(actual_value) => {
+                            if actual_value == expected_value {
+                                Ok(ApplyOutcome::Applied(Operation::Test))
+                            } else {
+                                Err(JqesqueError::TestFailedError {
+                                    expected: expected_value.clone(),
+                                    actual: actual_value.clone(),
+                                })
+                            }
+                        }
+                        Err(e) => Err(invalid_path_error(json, &tokens, e)),
+                    }
+                } else {
+                    Err(JqesqueError::MissingValueError(self.operation.clone()))
+                }
+            }
+            Operation::Merge => {
+                let tokens = resolve_relative_tokens(json, &self.tokens, true)?;
+                let mut value = self.value.clone().unwrap_or(Value::Null);
+                match options.merge.strategy {
+                    MergeStrategy::SkipNull if value.is_null() => {}
+                    MergeStrategy::DeleteNull if value.is_null() => remove_key(json, &tokens),
+                    // Merge at the target itself, so that the elements before an array index are
+                    // left alone rather than merged with padding
+                    _ => {
+                        let mut pointer = PointerBuf::new();
+                        let (entry, created) = entry_mut(
+                            json,
+                            &tokens,
+                            options.max_array_grow,
+                            &mut pointer,
+                            false,
+                            observer,
+                        )?;
+                        merge_json_observed(
+                            entry,
+                            &mut value,
+                            &options.merge,
+                            &mut pointer,
+                            created,
+                            observer,
+                        )
+                    }
+                }
+                Ok(ApplyOutcome::Applied(Operation::Merge))
+            }
+            Operation::Insert => {
+                let tokens = resolve_relative_tokens(json, &self.tokens, true)?;
+                insert_value_observed(
+                    json,
+                    &tokens,
+                    &self.value,
+                    options.max_array_grow,
+                    observer,
+                )?;
+                Ok(ApplyOutcome::Applied(Operation::Insert))
+            }
+            Operation::TestLen => {
+                let expected = self
+                    .value
+                    .as_ref()
+                    .ok_or_else(|| JqesqueError::MissingValueError(self.operation.clone()))?;
+                let tokens = resolve_relative_tokens(json, &self.tokens, false)?;
+                let pointer_buf = self.pointer_to(&tokens);
+                let pointer: &Pointer = &pointer_buf;
+
+                let len = match pointer.resolve(json) {
+                    Ok(Value::Array(array)) => array.len(),
+                    Ok(Value::Object(object)) => object.len(),
+                    Ok(_) => {
+                        return Err(JqesqueError::InvalidPathError(
+                            format!("value at {} is not an array or object", pointer_buf),
+                            None,
+                        ))
+                    }
+                    Err(e) => return Err(invalid_path_error(json, &tokens, e)),
+                };
+                if expected.as_u64() != Some(len as u64) {
+                    return Err(JqesqueError::TestFailedError {
+                        expected: expected.clone(),
+                        actual: Value::from(len),
+                    });
+                }
+                Ok(ApplyOutcome::Applied(Operation::TestLen))
+            }
+            Operation::RemoveIf => {
+                if options.remove_if_exists && !self.path_exists(json) {
+                    return Ok(ApplyOutcome::Unchanged(Operation::RemoveIf));
+                }
+                let mut test = self.clone();
+                test.operation = Operation::Test;
+                test.apply_unordered(json, options, observer)?;
+
+                let mut remove = self.clone();
+                remove.operation = Operation::Remove;
+                remove.apply_unordered(json, options, observer)?;
+                Ok(ApplyOutcome::Applied(Operation::RemoveIf))
+            }
+            Operation::TestReplace => {
+                let expected = self
+                    .expected
+                    .clone()
+                    .ok_or_else(|| JqesqueError::MissingExpectedError(self.operation.clone()))?;
+                let mut test = self.clone();
+                test.operation = Operation::Test;
+                test.value = Some(expected);
+                test.required_type = None;
+                test.expected = None;
+                test.apply_unordered(json, options, observer)?;
+
+                let mut replace = self.clone();
+                replace.operation = Operation::Replace;
+                replace.expected = None;
+                replace.apply_unordered(json, options, observer)?;
+                Ok(ApplyOutcome::Applied(Operation::TestReplace))
+            }
+            Operation::Default => {
+                if self.path_exists(json) {
+                    return Ok(ApplyOutcome::Unchanged(Operation::Default));
+                }
+                let tokens = resolve_relative_tokens(json, &self.tokens, true)?;
+                insert_value_observed(
+                    json,
+                    &tokens,
+                    &self.value,
+                    options.max_array_grow,
+                    observer,
+                )?;
+                Ok(ApplyOutcome::Applied(Operation::Default))
+            }
+            Operation::Copy | Operation::Move => {
+                let from = self
+                    .from
+                    .as_ref()
+                    .ok_or_else(|| JqesqueError::MissingFromError(self.operation.clone()))?;
+                let from = tokens_to_pointer(&resolve_relative_tokens(json, from, false)?);
+
+                if self.operation == Operation::Copy {
+                    let path = self.resolved_pointer(json, false)?;
+                    let patch = Patch(vec![PatchOperation::Copy(CopyOperation { from, path })]);
+                    json_patch::patch(json, &patch).map_err(|e| patch_error(&self.operation, e))?;
+                    return Ok(ApplyOutcome::Applied(Operation::Copy));
+                }
+
+                // json_patch undoes whole operations only, so a move that fails to add the value
+                // once it has taken it out loses it. Keep a copy of the value (rather than of the
+                // document) to put back in that case
+                let moved = from.resolve(json).ok().cloned();
+                let relative = self
+                    .tokens
+                    .iter()
+                    .any(|token| matches!(token, PathToken::LengthRelative(_) | PathToken::Append));
+
+                // The error, if any, and whether the value had been taken out by then
+                let result = if relative {
+                    // As per RFC6902, the destination is resolved once the source has been
+                    // removed, so that e.g. `@arr.-<arr[0]` moves the first element to the end
+                    let remove = Patch(vec![PatchOperation::Remove(RemoveOperation {
+                        path: from.clone(),
+                    })]);
+                    json_patch::patch(json, &remove)
+                        .map_err(|e| patch_error(&self.operation, e))?;
+                    resolve_relative_tokens(json, &self.tokens, false)
+                        .and_then(|tokens| {
+                            let add = Patch(vec![PatchOperation::Add(AddOperation {
+                                path: tokens_to_pointer(&tokens),
+                                value: moved.clone().unwrap_or_default(),
+                            })]);
+                            json_patch::patch(json, &add)
+                                .map_err(|e| patch_error(&self.operation, e))
+                        })
+                        .map_err(|e| (e, true))
+                } else {
+                    let patch = Patch(vec![PatchOperation::Move(MoveOperation {
+                        from: from.clone(),
+                        path: self.to_pointer(),
+                    })]);
+                    // The source and the move into itself are checked before taking the value out
+                    json_patch::patch(json, &patch).map_err(|e| {
+                        let taken = matches!(e.kind, PatchErrorKind::InvalidPointer);
+                        (patch_error(&self.operation, e), taken)
+                    })
+                };
+
+                if let Err((error, taken)) = result {
+                    if let (true, Some(value)) = (taken, moved) {
+                        let restore = Patch(vec![PatchOperation::Add(AddOperation {
+                            path: from,
+                            value,
+                        })]);
+                        json_patch::patch(json, &restore)
+                            .expect("the value should go back where it was taken from");
+                    }
+                    return Err(error);
+                }
+                Ok(ApplyOutcome::Applied(Operation::Move))
+            }
+        }
+    }
+
+    /// Returns whether the path (or the path taken from) contains a `PathToken::Wildcard`.
+    pub(crate) fn has_wildcard(&self) -> bool {
+        self.tokens
+            .iter()
+            .chain(self.from.iter().flatten())
+            .any(|token| *token == PathToken::Wildcard)
+    }
+
+    /// Applies a `Remove` or `Test` with wildcards to every path in `json` the wildcards match.
+    fn apply_wildcard_to(
+        &self,
+        json: &mut Value,
+        options: &ApplyOptions,
+        observer: &mut Observer,
+    ) -> Result<ApplyOutcome, JqesqueError> {
+        let matches = match self.operation {
+            Operation::Remove | Operation::Test if self.from.is_none() => {
+                expand_wildcards(json, &self.tokens)
+            }
+            _ => return Err(JqesqueError::WildcardError(self.operation.clone())),
+        };
+
+        if matches.is_empty() {
+            return match self.operation {
+                Operation::Remove => Ok(ApplyOutcome::Unchanged(Operation::Remove)),
+                _ if options.on_missing == MissingPolicy::Skip => {
+                    Ok(ApplyOutcome::Skipped(self.operation.clone()))
+                }
+                _ => Err(JqesqueError::InvalidPathError(
+                    format!(
+                        "no values match the wildcard path {}",
+                        format_path(&self.tokens, &Separator::Dot, false)
+                    ),
+                    None,
+                )),
+            };
+        }
+
+        // Remove from the back, so that removing an array element does not shift the indices of
+        // the matches still to be removed
+        for tokens in matches.into_iter().rev() {
+            let mut concrete = self.clone();
+            concrete.tokens = tokens;
+            concrete.apply_unordered(json, options, observer)?;
+        }
+        Ok(ApplyOutcome::Applied(self.operation.clone()))
+    }
+
+    /// Previews the result of applying the parsed structure, without mutating the JSON object.
+    ///
+    /// The JSON object is cloned and the operation is applied to the clone as by `apply_to`, so
+    /// the result can be compared with the original before committing to the change. Errors,
+    /// including a failed `Test`, are returned as they would be by `apply_to`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object to preview the operation on
+    ///
+    /// ## Returns
+    ///
+    /// Returns the resulting JSON object, or a JqesqueError if an error occurred.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    /// use serde_json::json;
+    ///
+    /// let json_obj = json!({"foo": 1});
+    /// let jqesque = "foo=2".parse::<Jqesque>().unwrap();
+    ///
+    /// assert_eq!(jqesque.preview(&json_obj).unwrap(), json!({"foo": 2}));
+    /// assert_eq!(json_obj, json!({"foo": 1}));
+    /// ```
+    pub fn preview(&self, json: &Value) -> Result<Value, JqesqueError> {
+        let mut preview = json.clone();
+        self.apply_to(&mut preview)?;
+        Ok(preview)
+    }
+
+    /// Applies the parsed structure to a JSON object, returning a JSON Patch of what changed.
+    ///
+    /// The patch is computed by comparing the document before and after applying, so it reflects
+    /// the actual change rather than the operation: a `Default` for an existing key, or a
+    /// `Replace` with the value already there, gives an empty patch. Unlike `affected_pointers`,
+    /// this also covers changes such as the `null` padding added by an `Insert`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object to apply the operation to
+    ///
+    /// ## Returns
+    ///
+    /// Returns the operation that was performed along with the patch, or a JqesqueError if an
+    /// error occurred.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Operation};
+    /// use serde_json::json;
+    ///
+    /// let mut json_obj = json!({"foo": 1});
+    /// let jqesque = "foo=2".parse::<Jqesque>().unwrap();
+    ///
+    /// let (operation, patch) = jqesque.apply_and_diff(&mut json_obj).unwrap();
+    /// assert_eq!(operation, Operation::Replace);
+    /// assert_eq!(
+    ///     serde_json::to_value(patch).unwrap(),
+    ///     json!([{"op": "replace", "path": "/foo", "value": 2}])
+    /// );
+    /// ```
+    pub fn apply_and_diff(&self, json: &mut Value) -> Result<(Operation, Patch), JqesqueError> {
+        let original = json.clone();
+        let operation = self.apply_to(json)?;
+        Ok((operation, json_patch::diff(&original, json)))
+    }
+
+    /// Applies the parsed structure to a JSON object, inserting a default for a missing `Replace`.
+    ///
+    /// For `Operation::Replace`, if the path does not exist in the JSON object, `default` is
+    /// inserted at the path (creating any missing structure, as `Insert` does) instead of failing.
+    /// This is a gentler upsert than `Auto`, with a caller-specified value for the missing case.
+    /// All other operations behave exactly as in `apply_to`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object to apply the operation to
+    /// * `default` - The value to insert if the path to replace does not exist
+    ///
+    /// ## Returns
+    ///
+    /// Returns the operation that was performed (`Replace` or `Insert` for a replace) or a
+    /// JqesqueError if an error occurred.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Operation};
+    /// use serde_json::json;
+    ///
+    /// let mut json_obj = json!({});
+    /// let jqesque = "=settings.theme=dark".parse::<Jqesque>().unwrap();
+    ///
+    /// let operation = jqesque.apply_to_or(&mut json_obj, json!("light")).unwrap();
+    /// assert_eq!(operation, Operation::Insert);
+    /// assert_eq!(json_obj, json!({"settings": {"theme": "light"}}));
+    /// ```
+    pub fn apply_to_or(&self, json: &mut Value, default: Value) -> Result<Operation, JqesqueError> {
+        if self.operation == Operation::Replace {
+            let pointer_buf = self.resolved_pointer(json, true)?;
+            let pointer: &Pointer = &pointer_buf;
+            if pointer.resolve(json).is_err() {
+                insert_value(json, &self.tokens, &Some(default), DEFAULT_MAX_ARRAY_GROW)?;
+                return Ok(Operation::Insert);
+            }
+        }
+        self.apply_to(json)
+    }
+
+    /// Converts the path tokens to a JSON Pointer.
+    ///
+    /// This function converts the path tokens to a JSON Pointer, which is a string representation of the path.
+    /// See `tokens_to_pointer` for how the tokens are represented.
+    ///
+    /// ## Returns
+    ///
+    /// Returns a `PointerBuf` object representing the path tokens.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    ///
+    /// let jqesque = ">foo.\"a/b\"[0]=1".parse::<Jqesque>().unwrap();
+    /// assert_eq!(jqesque.to_pointer().as_str(), "/foo/a~1b/0");
+    /// ```
+    pub fn to_pointer(&self) -> PointerBuf {
+        self.pointer_cache.pointer(&self.tokens)
+    }
+
+    /// Formats the path tokens as a path string, as they would be written in an assignment.
+    ///
+    /// Indices are attached to the preceding segment (`foo[0]`), and keys that are not plain
+    /// identifiers or that contain the separator are quoted, so the result parses back to the
+    /// same tokens. Unlike `to_pointer`, this is meant for humans, e.g. in error messages.
+    ///
+    /// ## Arguments
+    ///
+    /// * `separator` - The separator to put between keys
+    ///
+    /// ## Returns
+    ///
+    /// Returns the path as a string, without the operation or value.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::{Jqesque, Separator};
+    ///
+    /// let jqesque = ">foo.bar[0].baz=true".parse::<Jqesque>().unwrap();
+    /// assert_eq!(jqesque.path_string(Separator::Dot), "foo.bar[0].baz");
+    /// assert_eq!(jqesque.path_string(Separator::Slash), "foo/bar[0]/baz");
+    /// ```
+    pub fn path_string(&self, separator: Separator) -> String {
+        format_path(&self.tokens, &separator, false)
+    }
+
+    /// Checks whether the path exists in a JSON object, ignoring the value entirely.
+    ///
+    /// Length-relative indices are resolved against `json` first, so `arr[-1]` exists if `arr` is
+    /// a non-empty array, while `arr[#]` (one past the end) never does.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object to look the path up in
+    ///
+    /// ## Returns
+    ///
+    /// Returns `true` if the path resolves to a value (including `null`), `false` otherwise.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    /// use serde_json::json;
+    ///
+    /// let json_obj = json!({"foo": {"bar": [null]}});
+    /// assert!("?foo.bar[0]=1".parse::<Jqesque>().unwrap().path_exists(&json_obj));
+    /// assert!(!"-foo.baz".parse::<Jqesque>().unwrap().path_exists(&json_obj));
+    /// ```
+    pub fn path_exists(&self, json: &Value) -> bool {
+        self.resolve(json).is_ok()
+    }
+
+    /// Returns the value the path points to in a JSON object, ignoring the operation and value.
+    ///
+    /// Length-relative indices are resolved against `json` first, so `arr[-1]` is the last
+    /// element of `arr`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object to look the path up in
+    ///
+    /// ## Returns
+    ///
+    /// Returns a reference to the value at the path, or an `InvalidPathError` (or, for an
+    /// unresolvable length-relative index, a `RelativeIndexError`) if it does not exist.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    /// use serde_json::json;
+    ///
+    /// let json_obj = json!({"foo": {"bar": [1, 2]}});
+    /// let jqesque = "-foo.bar[-1]".parse::<Jqesque>().unwrap();
+    /// assert_eq!(jqesque.resolve(&json_obj).unwrap(), &json!(2));
+    /// ```
+    pub fn resolve<'a>(&self, json: &'a Value) -> Result<&'a Value, JqesqueError> {
+        let tokens = resolve_relative_tokens(json, &self.tokens, false)?;
+        let pointer_buf = self.pointer_to(&tokens);
+        let pointer: &Pointer = &pointer_buf;
+        pointer
+            .resolve(json)
+            .map_err(|e| invalid_path_error(json, &tokens, e))
+    }
+
+    /// Returns the JSON Pointers of every value that applying the assignment to `json` touches.
+    ///
+    /// This is normally the pointer of the path, with length-relative indices resolved against
+    /// `json` where possible. A path with wildcards gives the pointer of each existing match, and
+    /// `Copy` and `Move` add the pointer they take the value from, after the one they write to. A
+    /// `Merge` gives the pointer of every leaf of its value, below the path, as it would be
+    /// written with the default `MergeOptions`; empty objects and arrays count as leaves. This
+    /// makes it possible to check an assignment against a list of editable paths before applying
+    /// it.
+    ///
+    /// ## Arguments
+    ///
+    /// * `json` - The JSON object the assignment is to be applied to
+    ///
+    /// ## Returns
+    ///
+    /// Returns the pointers, as strings, in document order.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    /// use serde_json::json;
+    ///
+    /// let json_obj = json!({"users": [{"temp": 1}, {"temp": 2}]});
+    ///
+    /// let jqesque = "-users[*].temp".parse::<Jqesque>().unwrap();
+    /// assert_eq!(jqesque.affected_pointers(&json_obj), vec!["/users/0/temp", "/users/1/temp"]);
+    ///
+    /// let jqesque = "~settings={\"theme\":{\"color\":\"red\"},\"size\":12}"
+    ///     .parse::<Jqesque>()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     jqesque.affected_pointers(&json_obj),
+    ///     vec!["/settings/size", "/settings/theme/color"]
+    /// );
+    /// ```
+    pub fn affected_pointers(&self, json: &Value) -> Vec<String> {
+        let create_missing = matches!(
+            self.operation,
+            Operation::Insert | Operation::Merge | Operation::Default | Operation::Auto
+        );
+        // Paths that cannot be resolved are reported as they are, so that they are still checked
+        let resolve = |tokens: &[PathToken], create_missing: bool| {
+            resolve_relative_tokens(json, tokens, create_missing)
+                .map(Cow::into_owned)
+                .unwrap_or_else(|_| tokens.to_vec())
+        };
+
+        let targets = if self.tokens.contains(&PathToken::Wildcard) {
+            expand_wildcards(json, &self.tokens)
+        } else {
+            vec![resolve(&self.tokens, create_missing)]
+        };
+
+        let mut pointers: Vec<String> = match (&self.operation, &self.value) {
+            (Operation::Merge, Some(value)) => {
+                let mut leaves: Vec<Vec<PathToken>> = Jqesque::flatten(value, Separator::Dot)
+                    .into_iter()
+                    .map(|leaf| leaf.tokens)
+                    .collect();
+                // An empty object has no leaves, but is still merged into the target
+                if leaves.is_empty() {
+                    leaves.push(vec![]);
+                }
+                targets
+                    .iter()
+                    .flat_map(|target| {
+                        leaves.iter().map(move |leaf| {
+                            tokens_to_pointer(&[target.as_slice(), leaf].concat()).to_string()
+                        })
+                    })
+                    .collect()
+            }
+            _ => targets
+                .iter()
+                .map(|target| tokens_to_pointer(target).to_string())
+                .collect(),
+        };
+        if let Some(from) = &self.from {
+            pointers.push(tokens_to_pointer(&resolve(from, false)).to_string());
         }
+        pointers
     }
 
-    /// Applies the parsed structure to a JSON object.
-    ///
-    /// This function applies the parsed structure to the provided JSON object, performing the operation specified
-    /// during parsing. The operation is performed in-place, modifying the provided JSON object.
+    /// Converts the path tokens to a JSON Pointer, resolving relative indices against `json`.
     ///
     /// ## Arguments
     ///
-    /// * `json` - The JSON object to apply the operation to
-    ///
-    /// ## Returns
-    ///
-    /// Returns the operation that was performed or a JqesqueError if an error occurred.
-    pub fn apply_to(&self, json: &mut Value) -> Result<Operation, JqesqueError> {
-        match self.operation {
-            Operation::Auto => {
-                // Try Replace
-                let mut jq_replace = self.clone();
-                jq_replace.operation = Operation::Replace;
-                if jq_replace.apply_to(json).is_ok() {
-                    return Ok(Operation::Replace);
-                }
+    /// * `json` - The JSON object to resolve length-relative indices against
+    /// * `create_missing` - Whether a missing array counts as empty rather than being an error
+    pub(crate) fn resolved_pointer(
+        &self,
+        json: &Value,
+        create_missing: bool,
+    ) -> Result<PointerBuf, JqesqueError> {
+        let tokens = resolve_relative_tokens(json, &self.tokens, create_missing)?;
+        Ok(self.pointer_to(&tokens))
+    }
 
-                // Try Add
-                let mut jq_add = self.clone();
-                jq_add.operation = Operation::Add;
-                if jq_add.apply_to(json).is_ok() {
-                    return Ok(Operation::Add);
-                }
+    /// Converts path tokens resolved against a document to a JSON Pointer, using the cached
+    /// pointer of the path when there was nothing to resolve (and the tokens are the path itself).
+    fn pointer_to(&self, tokens: &[PathToken]) -> PointerBuf {
+        if std::ptr::eq(tokens, self.tokens.as_slice()) {
+            self.pointer_cache.pointer(&self.tokens)
+        } else {
+            tokens_to_pointer(tokens)
+        }
+    }
+}
 
-                // Fallback to Insert
-                let mut jq_insert = self.clone();
-                jq_insert.operation = Operation::Insert;
-                jq_insert.apply_to(json)
-            }
-            Operation::Add | Operation::Replace => {
-                if let Some(ref value) = self.value {
-                    let pointer_buf = self.tokens_to_pointer();
+/// Converts a json_patch error into a `JqesqueError::PatchError` for `operation`.
+fn patch_error(operation: &Operation, error: json_patch::PatchError) -> JqesqueError {
+    JqesqueError::PatchError {
+        op: operation.clone(),
+        path: error.path.to_string(),
+        message: error.kind.to_string(),
+        source: ErrorSource::new(error.kind),
+    }
+}
 
-                    let patch_op = match self.operation {
-                        Operation::Add => PatchOperation::Add(AddOperation {
-                            path: pointer_buf,
-                            value: value.clone(),
-                        }),
-                        Operation::Replace => PatchOperation::Replace(ReplaceOperation {
-                            path: pointer_buf,
-                            value: value.clone(),
-                        }),
-                        _ => unreachable!(),
-                    };
+/// Checks that the operations `Auto` tries are non-empty and can all create or update a value.
+fn validate_auto_order(order: &[Operation]) -> Result<(), JqesqueError> {
+    if order.is_empty() {
+        return Err(JqesqueError::InvalidAutoOrderError(
+            "at least one operation is required".to_string(),
+        ));
+    }
+    match order.iter().find(|operation| {
+        !matches!(
+            operation,
+            Operation::Replace | Operation::Add | Operation::Insert | Operation::Merge
+        )
+    }) {
+        Some(operation) => Err(JqesqueError::InvalidAutoOrderError(format!(
+            "operation {} cannot be tried, only replace, add, insert and merge can",
+            operation
+        ))),
+        None => Ok(()),
+    }
+}
 
-                    let patch = Patch(vec![patch_op]);
-                    json_patch::patch(json, &patch)
-                        .map_err(|e| JqesqueError::PatchError(e.to_string()))?;
-                    Ok(self.operation.clone())
-                } else {
-                    Err(JqesqueError::MissingValueError(self.operation.clone()))
-                }
-            }
-            Operation::Remove => {
-                let pointer_buf = self.tokens_to_pointer();
+/// Builds a `JqesqueError::InvalidPathError` for a path that failed to resolve.
+///
+/// The message names the zero-based index of the first token that could not be resolved against
+/// the JSON object, along with the token itself, followed by the underlying error.
+fn invalid_path_error(json: &Value, tokens: &[PathToken], error: ResolveError) -> JqesqueError {
+    let message = match first_unresolvable_token(json, tokens) {
+        Some(index) => format!(
+            "failed at token {} (`{}`): {}",
+            index,
+            token_label(&tokens[index]),
+            error
+        ),
+        None => error.to_string(),
+    };
+    JqesqueError::InvalidPathError(message, Some(ErrorSource::new(error)))
+}
 
-                let patch_op = PatchOperation::Remove(RemoveOperation { path: pointer_buf });
-                let patch = Patch(vec![patch_op]);
-                json_patch::patch(json, &patch)
-                    .map_err(|e| JqesqueError::PatchError(e.to_string()))?;
-                Ok(Operation::Remove)
-            }
-            Operation::Test => {
-                if let Some(ref expected_value) = self.value {
-                    let pointer_buf = self.tokens_to_pointer();
-                    let pointer: &Pointer = &pointer_buf;
+/// Renders a path token for use in error messages.
+fn token_label(token: &PathToken) -> String {
+    match token {
+        PathToken::Key(key) => key.clone(),
+        PathToken::Index(index) => index.to_string(),
+        PathToken::LengthRelative(offset) => format!("#{:+}", offset),
+        PathToken::Append => "-".to_string(),
+        PathToken::Wildcard => "*".to_string(),
+    }
+}
 
-                    match pointer.resolve(json) {
-                        Ok(actual_value) => {
-                            if actual_value == expected_value {
-                                Ok(Operation::Test)
-                            } else {
-                                Err(JqesqueError::TestFailedError {
-                                    expected: expected_value.clone(),
-                                    actual: actual_value.clone(),
-                                })
-                            }
-                        }
-                        Err(e) => Err(JqesqueError::InvalidPathError(e.to_string())),
-                    }
+/// Formats path tokens in assignment syntax, quoting keys that are not plain identifiers.
+///
+/// If `unprefixed` is set, a leading key starting with `-` is quoted too, so that it is not read
+/// back as the remove operator.
+///
+/// The empty path to the document root is formatted as the separator on its own.
+fn format_path(tokens: &[PathToken], separator: &Separator, unprefixed: bool) -> String {
+    if tokens.is_empty() {
+        return separator.as_str().into_owned();
+    }
+    let mut path = String::new();
+    for (position, token) in tokens.iter().enumerate() {
+        match token {
+            PathToken::Key(key) => {
+                if position > 0 {
+                    path.push_str(&separator.as_str());
+                }
+                if needs_quotes(key, separator)
+                    || (unprefixed && position == 0 && key.starts_with('-'))
+                {
+                    path.push_str(&quoted(key));
                 } else {
-                    Err(JqesqueError::MissingValueError(self.operation.clone()))
+                    path.push_str(key);
                 }
             }
-            Operation::Merge => {
-                // Assuming no errors occur during merge
-                let mut temp_value = Value::Null;
-                insert_value(&mut temp_value, &self.tokens, &self.value);
-                merge_json(json, &mut temp_value);
-                Ok(Operation::Merge)
-            }
-            Operation::Insert => {
-                // Assuming no errors occur during insert
-                insert_value(json, &self.tokens, &self.value);
-                Ok(Operation::Insert)
+            PathToken::Index(index) => path.push_str(&format!("[{}]", index)),
+            PathToken::LengthRelative(0) => path.push_str("[#]"),
+            PathToken::LengthRelative(offset) => path.push_str(&format!("[#{:+}]", offset)),
+            PathToken::Append => path.push_str("[-]"),
+            PathToken::Wildcard => {
+                if position > 0 {
+                    path.push_str(&separator.as_str());
+                }
+                path.push('*');
             }
         }
     }
-
-    /// Converts the path tokens to a JSON Pointer.
-    ///
-    /// This function converts the path tokens to a JSON Pointer, which is a string representation of the path.
-    ///
-    /// ## Returns
-    ///
-    /// Returns a `PointerBuf` object representing the path tokens.
-    fn tokens_to_pointer(&self) -> PointerBuf {
-        let tokens = self.tokens.iter().map(|token| match token {
-            PathToken::Key(ref key) => Token::new(escape_json_pointer_segment(key)),
-            PathToken::Index(idx) => Token::new(idx.to_string()),
-        });
-
-        PointerBuf::from_tokens(tokens)
-    }
+    path
 }
 
-/// Helper function to escape JSON Pointer segments.
+/// Quotes a key for use in an assignment, unless it can be written as it is.
 ///
-/// This is necessary to escape the characters '~' and '/' in JSON Pointer segments, as per
-/// the JSON Pointer specification evalution:
-/// https://datatracker.ietf.org/doc/html/rfc6901#section-4
+/// A key made of letters, digits, `_` and `-` is returned as-is, unless it contains the
+/// separator or starts with `-` (which could be read as the remove operator or the append token).
+/// Any other key is put in double quotes, with `"` and `\` escaped by a backslash and control
+/// characters written as `\uXXXX` escapes. Either way, the result parses back to the same key, so
+/// keys from untrusted sources can be embedded in an assignment safely.
 ///
 /// ## Arguments
 ///
-/// * `segment` - The segment to escape
+/// * `key` - The key to quote
+/// * `separator` - The separator the assignment will be parsed with
+///
+/// ## Example
 ///
-/// ## Returns
+/// ```rust
+/// use jqesque::{quote_key, Jqesque, PathToken, Separator};
 ///
-/// Returns the escaped segment as a `String`.
-fn escape_json_pointer_segment(segment: &str) -> String {
-    segment.replace('~', "~0").replace('/', "~1")
+/// assert_eq!(quote_key("name", Separator::Dot), "name");
+/// assert_eq!(quote_key("a.b", Separator::Dot), "\"a.b\"");
+/// assert_eq!(quote_key("a/b", Separator::Slash), "\"a/b\"");
+/// assert_eq!(quote_key("say \"hi\"", Separator::Dot), "\"say \\\"hi\\\"\"");
+///
+/// let input = format!(
+///     "{}.{}=1",
+///     quote_key("x=1", Separator::Dot),
+///     quote_key("y", Separator::Dot)
+/// );
+/// let jqesque = input.parse::<Jqesque>().unwrap();
+/// assert_eq!(jqesque.tokens()[0], PathToken::Key("x=1".to_string()));
+/// ```
+pub fn quote_key(key: &str, separator: Separator) -> String {
+    if needs_quotes(key, &separator) || key.starts_with('-') {
+        quoted(key)
+    } else {
+        key.to_string()
+    }
+}
+
+// Whether a key must be quoted wherever it is in the path
+fn needs_quotes(key: &str, separator: &Separator) -> bool {
+    key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        || separator
+            .alternatives()
+            .iter()
+            .any(|alternative| key.contains(alternative.as_str()))
+}
+
+// Quote a key, escaping backslashes and double quotes, and control characters as `\uXXXX`
+// (since `quoted_string` reads e.g. `\n` back as a plain `n`)
+fn quoted(key: &str) -> String {
+    let mut quoted = String::with_capacity(key.len() + 2);
+    quoted.push('"');
+    for c in key.chars() {
+        if c == '\\' || c == '"' {
+            quoted.push('\\');
+            quoted.push(c);
+        } else if c.is_control() {
+            quoted.push_str(&format!("\\u{:04x}", c as u32));
+        } else {
+            quoted.push(c);
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Works out where an `Add` at `pointer` will put its value, before the patch is applied.
+fn add_outcome(json: &Value, pointer: &Pointer) -> ApplyOutcome {
+    let Some((parent, last)) = pointer.split_back() else {
+        return ApplyOutcome::Applied(Operation::Add);
+    };
+    let Ok(Value::Array(array)) = parent.resolve(json) else {
+        return ApplyOutcome::Applied(Operation::Add);
+    };
+
+    if last.encoded() == "-" {
+        return ApplyOutcome::Appended { index: array.len() };
+    }
+    match last.encoded().parse::<usize>() {
+        Ok(index) if index == array.len() => ApplyOutcome::Appended { index },
+        Ok(index) => ApplyOutcome::Inserted { index },
+        Err(_) => ApplyOutcome::Applied(Operation::Add),
+    }
+}
+
+/// The detailed outcome of applying a `Jqesque`, as returned by `Jqesque::apply_to_detailed`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApplyOutcome {
+    /// The operation was applied. For `Auto`, this is the operation it resolved to.
+    Applied(Operation),
+    /// An `Add` appended the value to the end of an array, where it ended up at `index`.
+    Appended { index: usize },
+    /// An `Add` inserted the value into an array at `index`, shifting the existing element at
+    /// that index and all elements after it up by one.
+    Inserted { index: usize },
+    /// The operation succeeded without changing anything, e.g. a `Remove` of a missing path
+    /// with `ApplyOptions::remove_if_exists` set, or a `Default` for a path that already exists.
+    Unchanged(Operation),
+    /// A `Remove`, `Test`, `TestLen` or `RemoveIf` was skipped, as its path does not exist and
+    /// `ApplyOptions::on_missing` is `MissingPolicy::Skip`.
+    Skipped(Operation),
+}
+
+impl ApplyOutcome {
+    /// Returns the operation that was performed.
+    pub fn operation(&self) -> Operation {
+        match self {
+            ApplyOutcome::Applied(operation)
+            | ApplyOutcome::Unchanged(operation)
+            | ApplyOutcome::Skipped(operation) => operation.clone(),
+            ApplyOutcome::Appended { .. } | ApplyOutcome::Inserted { .. } => Operation::Add,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// A single segment of a path.
+///
+/// Tokens are ordered by variant first, in declaration order (`Key` < `Index` < `LengthRelative`
+/// < `Append` < `Wildcard`), and then by their contents: keys compare lexicographically by byte
+/// and indices numerically. Comparing two token slices therefore orders paths lexicographically,
+/// with object keys sorting before array positions at the same depth.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum PathToken {
     Key(String),
     Index(usize),
+    /// An index relative to the length of the array at apply time, written as `[#]` (the length
+    /// itself, i.e. one past the end) or `[#-N]`/`[#+N]`. `[#-1]` is the last element. `[-N]` is
+    /// shorthand for `[#-N]`.
+    LengthRelative(isize),
+    /// The position one past the end of the array, written as a bare `-` segment (the RFC6901
+    /// append token, e.g. `log/-` or `log.-`) or as `[-]` or `[]`. It behaves like `[#]`.
+    Append,
+    /// Every key of an object or element of an array, written as a bare `*` segment (e.g.
+    /// `users.*.temp`) or as `[*]`. Only `Remove` and `Test` support wildcards, and apply to each
+    /// existing path they match. Quote it (`"*"`) to use it as an object key instead.
+    Wildcard,
+}
+
+impl PathToken {
+    /// Returns the key if this is a `PathToken::Key`.
+    pub fn as_key(&self) -> Option<&str> {
+        match self {
+            PathToken::Key(key) => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Returns the index if this is a `PathToken::Index`.
+    pub fn as_index(&self) -> Option<usize> {
+        match self {
+            PathToken::Index(index) => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Returns true if this is a `PathToken::Key`.
+    pub fn is_key(&self) -> bool {
+        matches!(self, PathToken::Key(_))
+    }
+
+    /// Returns true if this is a `PathToken::Index`.
+    pub fn is_index(&self) -> bool {
+        matches!(self, PathToken::Index(_))
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Renders a single token: a key as-is (without quoting), and array positions in brackets, e.g.
+/// `[0]`, `[#-1]` or `[-]`. A wildcard is rendered as `*`.
+///
+/// Use `Jqesque::path_string` to render a whole path with separators and quoting.
+impl Display for PathToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathToken::Key(key) => write!(f, "{}", key),
+            PathToken::Index(index) => write!(f, "[{}]", index),
+            PathToken::LengthRelative(0) => write!(f, "[#]"),
+            PathToken::LengthRelative(offset) => write!(f, "[#{:+}]", offset),
+            PathToken::Append => write!(f, "[-]"),
+            PathToken::Wildcard => write!(f, "*"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Separator {
+    #[default]
     Dot,
     Slash,
     Custom(char),
+    /// A multi-character separator, e.g. `::` or `->`.
+    CustomStr(String),
+    /// Any of several characters, e.g. `AnyOf(vec!['/', '.'])` to accept both `a/b` and `a.b`
+    /// (or a mix, as in `a/b.c`). Paths are formatted with the first of them.
+    AnyOf(Vec<char>),
 }
 
 impl Separator {
+    // Whether this is the default `Separator::Dot`, which is left out when serializing
+    fn is_dot(&self) -> bool {
+        *self == Separator::Dot
+    }
+
+    /// Returns the separator as a character.
+    ///
+    /// For `Separator::CustomStr`, this is the first character of the separator (or `'\0'` if it
+    /// is empty); use `as_str` to get all of it. For `Separator::AnyOf`, it is the first of the
+    /// characters (or `'\0'` if there are none).
     pub fn as_char(&self) -> char {
         match self {
             Separator::Dot => '.',
             Separator::Slash => '/',
             Separator::Custom(c) => *c,
+            Separator::CustomStr(s) => s.chars().next().unwrap_or('\0'),
+            Separator::AnyOf(chars) => chars.first().copied().unwrap_or('\0'),
+        }
+    }
+
+    /// Returns the separator as a string, as used to format paths.
+    ///
+    /// For `Separator::AnyOf`, this is the first of the characters; use `alternatives` to get all
+    /// of them.
+    pub fn as_str(&self) -> Cow<'_, str> {
+        match self {
+            Separator::CustomStr(s) => Cow::Borrowed(s),
+            separator => Cow::Owned(separator.as_char().to_string()),
+        }
+    }
+
+    /// Returns every string accepted as the separator: each of the characters for
+    /// `Separator::AnyOf`, and the separator itself otherwise.
+    pub fn alternatives(&self) -> Vec<String> {
+        match self {
+            Separator::AnyOf(chars) => chars.iter().map(char::to_string).collect(),
+            separator => vec![separator.as_str().into_owned()],
+        }
+    }
+}
+
+impl FromStr for Separator {
+    type Err = JqesqueError;
+
+    /// Parses a separator from its textual form: `.` is `Separator::Dot`, `/` is
+    /// `Separator::Slash`, any other single character is `Separator::Custom`, and a longer string
+    /// is `Separator::CustomStr`. An empty string is an error.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut chars = input.chars();
+        match (chars.next(), chars.next()) {
+            (None, _) => Err(JqesqueError::InvalidSeparatorError(
+                "a separator cannot be empty".to_string(),
+            )),
+            (Some('.'), None) => Ok(Separator::Dot),
+            (Some('/'), None) => Ok(Separator::Slash),
+            (Some(c), None) => Ok(Separator::Custom(c)),
+            _ => Ok(Separator::CustomStr(input.to_string())),
+        }
+    }
+}
+
+/// A JSON value type, as used in type annotations such as `count:int=5`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    /// A string, written `str`
+    Str,
+    /// An integer, written `int`
+    Int,
+    /// Any number, written `float`. Integers are accepted as well, since they are valid floats.
+    Float,
+    /// A boolean, written `bool`
+    Bool,
+    /// `null`, written `null`
+    Null,
+    /// An array, written `array`
+    Array,
+    /// An object, written `object`
+    Object,
+}
+
+impl ValueType {
+    /// Returns whether the value is of this type.
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            ValueType::Str => value.is_string(),
+            ValueType::Int => value.is_i64() || value.is_u64(),
+            ValueType::Float => value.is_number(),
+            ValueType::Bool => value.is_boolean(),
+            ValueType::Null => value.is_null(),
+            ValueType::Array => value.is_array(),
+            ValueType::Object => value.is_object(),
+        }
+    }
+}
+
+impl Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let type_str = match self {
+            ValueType::Str => "str",
+            ValueType::Int => "int",
+            ValueType::Float => "float",
+            ValueType::Bool => "bool",
+            ValueType::Null => "null",
+            ValueType::Array => "array",
+            ValueType::Object => "object",
+        };
+        write!(f, "{}", type_str)
+    }
+}
+
+impl FromStr for ValueType {
+    type Err = JqesqueError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "str" => Ok(ValueType::Str),
+            "int" => Ok(ValueType::Int),
+            "float" => Ok(ValueType::Float),
+            "bool" => Ok(ValueType::Bool),
+            "null" => Ok(ValueType::Null),
+            "array" => Ok(ValueType::Array),
+            "object" => Ok(ValueType::Object),
+            _ => Err(JqesqueError::UnknownTypeError(input.to_string())),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum Operation {
     /// **Inserts** the parsed structure into the provided JSON object.
     ///
@@ -371,12 +2076,11 @@ pub enum Operation {
     /// });
     ///
     /// // Input string to parse
-    /// let input = "settings.theme={\"color\":\"blue\",\"font\":\"Helvetica\"}";
+    /// let input = ">settings.theme={\"color\":\"blue\",\"font\":\"Helvetica\"}";
     /// let separator = Separator::Dot;
     ///
     /// let jqesque = Jqesque::from_str_with_separator(input, separator).unwrap();
-    /// // Using apply_to with no explicit operator will use the operator "Insert" and this will
-    /// // overwrite the existing "theme" object
+    /// // The `>` operator inserts, which overwrites the existing "theme" object
     /// jqesque.apply_to(&mut json_obj);
     ///
     /// // The "theme" object is replaced entirely
@@ -394,8 +2098,8 @@ pub enum Operation {
     /// // Note that the "size" key in the original "theme" object is removed
     /// ```
     ///
-    /// In this example, `parse_and_insert` replaces the entire `"theme"` object with the new value,
-    /// removing any existing keys not specified in the new value.    
+    /// In this example, the insert replaces the entire `"theme"` object with the new value,
+    /// removing any existing keys not specified in the new value.
     Insert,
 
     /// **Merges** the parsed structure into the JSON object.
@@ -466,6 +2170,55 @@ pub enum Operation {
     Replace,
     Test,
 
+    /// **Copies** the value at another path in the JSON object to the path, as RFC6902 `copy`.
+    ///
+    /// Written as `&dest<source`, e.g. `&backup.settings<settings`, with both paths using the
+    /// same separator. There is no value. As with `Add`, the parent of the destination must
+    /// exist, and a value already at the destination is overwritten. The source must exist.
+    Copy,
+
+    /// **Moves** the value at another path in the JSON object to the path, as RFC6902 `move`.
+    ///
+    /// Written as `@dest<source`, e.g. `@settings.theme<theme`, with both paths using the same
+    /// separator. There is no value. The value is removed from the source and added at the
+    /// destination, with the same rules as `Copy`. Per RFC6902, moving a value into one of its own
+    /// descendants (e.g. `@a.b<a`) is an error.
+    Move,
+
+    /// **Sets a default** for the path, inserting the value only if the path does not exist.
+    ///
+    /// Written as `:path=value`, e.g. `:settings.theme=light`. If the path already resolves to a
+    /// value (including `null`), the document is left as it is and the outcome is
+    /// `ApplyOutcome::Unchanged(Operation::Default)`. Otherwise the value is inserted as by
+    /// `Insert`, creating any missing structure. Unlike `Add`, an existing value is not an error.
+    Default,
+
+    /// **Removes** the path only if its value equals the given value, as a compare-and-delete.
+    ///
+    /// Written as `-?path=value`, e.g. `-?lock.owner="me"`. The value is tested as by `Test`, and
+    /// only if the test passes is the path removed. If it fails, the document is left unchanged
+    /// and the error is a `TestFailedError` (or an `InvalidPathError` if the path does not exist,
+    /// unless `ApplyOptions::remove_if_exists` is set). As a JSON Patch, it is a `test` followed
+    /// by a `remove`.
+    RemoveIf,
+
+    /// **Tests the length** of the array or object at the path.
+    ///
+    /// Written as `?#path=length`, e.g. `?#items=3`, where the length is a non-negative integer.
+    /// If the number of elements of the array (or keys of the object) differs, the error is a
+    /// `TestFailedError` with the expected and actual lengths. A path that does not exist, or that
+    /// holds a scalar, is an `InvalidPathError`. It has no JSON Patch representation.
+    TestLen,
+
+    /// **Tests and replaces** the value at the path, as a compare-and-swap.
+    ///
+    /// Written as `?=path=expected=>new`, e.g. `?=config.version=3=>4`. The expected value is
+    /// tested as by `Test`, and only if the test passes is the value replaced by the new one. If
+    /// it fails, the document is left unchanged and the error is a `TestFailedError` (or an
+    /// `InvalidPathError` if the path does not exist). As a JSON Patch, it is a `test` followed by
+    /// a `replace`.
+    TestReplace,
+
     /// **Auto** operation.
     ///
     /// The `Auto` operation will attempt the following operations in order:
@@ -487,12 +2240,28 @@ impl Display for Operation {
             Operation::Remove => "remove",
             Operation::Replace => "replace",
             Operation::Test => "test",
+            Operation::Copy => "copy",
+            Operation::Move => "move",
+            Operation::Default => "default",
+            Operation::RemoveIf => "remove_if",
+            Operation::TestLen => "test_len",
+            Operation::TestReplace => "test_replace",
             Operation::Auto => "auto",
         };
         write!(f, "{}", op_str)
     }
 }
 
+/// Parses an operation from its name, as rendered by `Display` (e.g. `"merge"` or `"remove_if"`).
+impl FromStr for Operation {
+    type Err = JqesqueError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Operation::from_name(input)
+            .ok_or_else(|| JqesqueError::UnknownOperationError(input.to_string()))
+    }
+}
+
 impl Operation {
     // Define the operator characters as associated constants
     const INSERT_OP: char = '>';
@@ -501,6 +2270,12 @@ impl Operation {
     const REMOVE_OP: char = '-';
     const REPLACE_OP: char = '=';
     const TEST_OP: char = '?';
+    const COPY_OP: char = '&';
+    const MOVE_OP: char = '@';
+    const DEFAULT_OP: char = ':';
+    const REMOVE_IF_PREFIX: &'static str = "-?";
+    const TEST_LEN_PREFIX: &'static str = "?#";
+    const TEST_REPLACE_PREFIX: &'static str = "?=";
 
     // Get all valid operators
     pub fn operators() -> &'static [char] {
@@ -511,6 +2286,9 @@ impl Operation {
             Self::REMOVE_OP,
             Self::REPLACE_OP,
             Self::TEST_OP,
+            Self::COPY_OP,
+            Self::MOVE_OP,
+            Self::DEFAULT_OP,
         ]
     }
 
@@ -523,6 +2301,9 @@ impl Operation {
             Self::REMOVE_OP => Some(Self::Remove),
             Self::REPLACE_OP => Some(Self::Replace),
             Self::TEST_OP => Some(Self::Test),
+            Self::COPY_OP => Some(Self::Copy),
+            Self::MOVE_OP => Some(Self::Move),
+            Self::DEFAULT_OP => Some(Self::Default),
             _ => None,
         }
     }
@@ -536,25 +2317,282 @@ impl Operation {
             Self::Remove => Some(Self::REMOVE_OP),
             Self::Replace => Some(Self::REPLACE_OP),
             Self::Test => Some(Self::TEST_OP),
+            Self::Copy => Some(Self::COPY_OP),
+            Self::Move => Some(Self::MOVE_OP),
+            Self::Default => Some(Self::DEFAULT_OP),
+            // Written with a two-character prefix such as `-?`, see `prefix`
+            Self::RemoveIf | Self::TestLen | Self::TestReplace => None,
             Self::Auto => None,
         }
     }
+
+    /// Returns the prefix an assignment with this operation is written with, e.g. `>` for
+    /// `Insert`, `-?` for `RemoveIf`, `?#` for `TestLen` or `?=` for `TestReplace`, or `None` for
+    /// `Auto`, which has none.
+    pub fn prefix(&self) -> Option<Cow<'static, str>> {
+        match self {
+            Self::RemoveIf => Some(Cow::Borrowed(Self::REMOVE_IF_PREFIX)),
+            Self::TestLen => Some(Cow::Borrowed(Self::TEST_LEN_PREFIX)),
+            Self::TestReplace => Some(Cow::Borrowed(Self::TEST_REPLACE_PREFIX)),
+            operation => operation
+                .to_operator()
+                .map(|operator| Cow::Owned(operator.to_string())),
+        }
+    }
+
+    // Convert from an operation name, as rendered by Display, to Operation
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "insert" => Some(Self::Insert),
+            "merge" => Some(Self::Merge),
+            "add" => Some(Self::Add),
+            "remove" => Some(Self::Remove),
+            "replace" => Some(Self::Replace),
+            "test" => Some(Self::Test),
+            "copy" => Some(Self::Copy),
+            "move" => Some(Self::Move),
+            "default" => Some(Self::Default),
+            "remove_if" => Some(Self::RemoveIf),
+            "test_len" => Some(Self::TestLen),
+            "test_replace" => Some(Self::TestReplace),
+            "auto" => Some(Self::Auto),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the operation requires the target path to already exist.
+    ///
+    /// The JSON Patch operations (`Add`, `Replace`, `Remove`, `Test`, `Copy` and `Move`, as well
+    /// as `RemoveIf`, `TestLen` and `TestReplace`) fail if the path (or for `Add`, `Copy` and
+    /// `Move`, its parent) does not exist in the target document. `Insert`, `Merge`, `Default`
+    /// and `Auto` create any missing structure as needed.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Operation;
+    ///
+    /// assert!(Operation::Replace.requires_existing_path());
+    /// assert!(!Operation::Insert.requires_existing_path());
+    /// ```
+    pub fn requires_existing_path(&self) -> bool {
+        match self {
+            Self::Add
+            | Self::Replace
+            | Self::Remove
+            | Self::RemoveIf
+            | Self::Test
+            | Self::TestLen
+            | Self::TestReplace
+            | Self::Copy
+            | Self::Move => true,
+            Self::Insert | Self::Merge | Self::Default | Self::Auto => false,
+        }
+    }
 }
 
+/// The errors returned by jqesque.
+///
+/// Errors caused by another error keep it as their `source()`: an `ErrorSource` for errors from
+/// other crates (e.g. of `PatchError` and of an `InvalidPathError` for a path that failed to
+/// resolve), and the error of the assignment for `BatchError` and `LineError`. A `ParseError`
+/// has no source, as the parser's errors borrow the input.
 #[derive(Error, Debug, PartialEq)]
 pub enum JqesqueError {
-    #[error("Parsing error: {0}")]
-    NomError(String),
+    #[error("Parsing error: {message}")]
+    ParseError {
+        // The byte offset in the input where parsing failed
+        offset: usize,
+        // The one-based character column where parsing failed
+        column: usize,
+        message: String,
+    },
+
+    #[error("Unexpected input {remaining:?} after {consumed:?}")]
+    TrailingInputError { consumed: String, remaining: String },
 
     #[error("Operation {0} requires a value")]
     MissingValueError(Operation),
 
-    #[error("JSON Patch error: {0}")]
-    PatchError(String), // Store the error message as a string, json_patch::PatchError does not implement PartialEq
+    #[error("Operation {0} requires a path to take the value from")]
+    MissingFromError(Operation),
+
+    // The message is also stored as a string, so that it can be matched on without the source.
+    #[error("Operation {op} failed at {path}: {message}")]
+    PatchError {
+        op: Operation,
+        path: String,
+        message: String,
+        source: ErrorSource,
+    },
 
     #[error("Test failed: expected {expected} but found {actual}")]
     TestFailedError { expected: Value, actual: Value },
 
+    // The source is the JSON Pointer error, if the path failed to resolve
     #[error("Failed to access path: {0}")]
-    InvalidPathError(String),
+    InvalidPathError(String, #[source] Option<ErrorSource>),
+
+    #[error("Value {actual} is not of the required type {expected}")]
+    TypeMismatchError { expected: ValueType, actual: Value },
+
+    #[error("Invalid operation spec: {0}")]
+    InvalidSpecError(String),
+
+    #[error("Invalid JSON value {input:?}: {message}")]
+    InvalidJsonValueError {
+        input: String,
+        message: String,
+        source: ErrorSource,
+    },
+
+    #[error("Index {index} would extend an array by more than the limit of {limit} elements")]
+    IndexTooLargeError { index: usize, limit: usize },
+
+    // Parsing stops once the limit is exceeded, so `depth` is the number of tokens read by then
+    #[error("Path with {depth} or more tokens exceeds the limit of {limit}")]
+    InputTooComplexError { depth: usize, limit: usize },
+
+    #[error("Input of {length} bytes exceeds the limit of {limit}")]
+    InputTooLongError { length: usize, limit: usize },
+
+    #[error("Invalid separator: {0}")]
+    InvalidSeparatorError(String),
+
+    #[error("Unknown type: {0}")]
+    UnknownTypeError(String),
+
+    #[error("Unknown operation: {0}")]
+    UnknownOperationError(String),
+
+    #[error("{}", relative_index_message(*offset, *len))]
+    RelativeIndexError { offset: isize, len: usize },
+
+    #[error("{}", unsupported_operation_message(.0))]
+    UnsupportedOperationError(Operation),
+
+    #[error("Operation {0} requires an expected value")]
+    MissingExpectedError(Operation),
+
+    #[error("Operation {0} can only be applied to a serde_json::Value")]
+    UnsupportedDocumentOperationError(Operation),
+
+    #[error("Invalid auto order: {0}")]
+    InvalidAutoOrderError(String),
+
+    #[error("Failed to read value from {path}: {message}")]
+    ValueSourceError {
+        path: String,
+        message: String,
+        source: ErrorSource,
+    },
+
+    #[error("YAML error: {0}")]
+    YamlError(String, #[source] ErrorSource),
+
+    #[error("Operation {0} does not support wildcards, as it needs a concrete target")]
+    WildcardError(Operation),
+
+    #[error("Key {key:?} is ambiguous, as it matches {matches:?} ignoring case")]
+    AmbiguousKeyError { key: String, matches: Vec<String> },
+
+    #[error("Assignment {index} failed: {source}")]
+    BatchError {
+        index: usize,
+        source: Box<JqesqueError>,
+    },
+
+    #[error("Line {line} failed: {source}")]
+    LineError {
+        // The one-based line number of the assignment
+        line: usize,
+        source: Box<JqesqueError>,
+    },
+}
+
+/// The underlying error of a `JqesqueError`, as returned by its `source()`.
+///
+/// This wraps errors from the crates jqesque builds on, such as a `json_patch::PatchErrorKind`,
+/// a `jsonptr` resolution error or a `serde_json::Error`. Two sources are equal if their messages
+/// are, so that `JqesqueError` can be compared in tests. It displays as the wrapped error, and
+/// its own `source()` is that of the wrapped error, so chains are reported without repeats.
+///
+/// ## Example
+///
+/// ```rust
+/// use std::error::Error;
+/// use jqesque::{ErrorSource, Jqesque};
+/// use serde_json::json;
+///
+/// let jqesque = "?missing.key=1".parse::<Jqesque>().unwrap();
+/// let error = jqesque.apply_to(&mut json!({})).unwrap_err();
+/// let source = error.source().unwrap().downcast_ref::<ErrorSource>().unwrap();
+/// assert!(source.downcast_ref::<jsonptr::resolve::ResolveError>().is_some());
+/// ```
+pub struct ErrorSource(Box<dyn std::error::Error + Send + Sync>);
+
+impl ErrorSource {
+    /// Wraps `error` as the source of a `JqesqueError`.
+    pub fn new(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        ErrorSource(Box::new(error))
+    }
+
+    /// Returns the wrapped error.
+    pub fn get_ref(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        &*self.0
+    }
+
+    /// Returns the wrapped error as a `T`, if it is one.
+    pub fn downcast_ref<T: std::error::Error + 'static>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+}
+
+impl std::fmt::Debug for ErrorSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for ErrorSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ErrorSource {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+// Compared by message, as the wrapped errors do not implement PartialEq
+impl PartialEq for ErrorSource {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+/// Formats the message for a `JqesqueError::UnsupportedOperationError`.
+fn unsupported_operation_message(operation: &Operation) -> String {
+    match operation {
+        Operation::Auto => {
+            "Operation auto has no JSON Patch representation, as it is only resolved \
+                            when applied; use an explicit operation instead"
+                .to_string()
+        }
+        operation => format!("Operation {} has no JSON Patch representation", operation),
+    }
+}
+
+/// Formats the message for a `JqesqueError::RelativeIndexError`.
+fn relative_index_message(offset: isize, len: usize) -> String {
+    if len == 0 {
+        format!("Cannot resolve index #{:+} against an empty array", offset)
+    } else {
+        format!(
+            "Index #{:+} is out of range for an array of length {}",
+            offset, len
+        )
+    }
 }
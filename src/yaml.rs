@@ -0,0 +1,92 @@
+use serde_json::Value;
+
+use crate::types::{ErrorSource, Jqesque, JqesqueError, Operation};
+
+impl Jqesque {
+    /// Applies the parsed structure to a YAML document.
+    ///
+    /// The document is converted to JSON, the operation is applied as by `apply_to`, and the
+    /// result is converted back, so paths and operations behave exactly as they do for JSON. The
+    /// document is left untouched if the operation fails.
+    ///
+    /// Only YAML that can be represented as JSON is supported: mapping keys must be strings (or
+    /// scalars, which are converted to strings), and tags are dropped. As with JSON, mapping keys
+    /// come back sorted unless serde_json's `preserve_order` feature is enabled.
+    ///
+    /// ## Arguments
+    ///
+    /// * `yaml` - The YAML document to apply the operation to
+    ///
+    /// ## Returns
+    ///
+    /// Returns the operation that was performed or a JqesqueError if an error occurred, including
+    /// a `YamlError` if the document cannot be converted.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    ///
+    /// let mut yaml: serde_yaml::Value = serde_yaml::from_str("server:\n  port: 80\n").unwrap();
+    /// let jqesque = "server.port=8080".parse::<Jqesque>().unwrap();
+    /// jqesque.apply_to_yaml(&mut yaml).unwrap();
+    ///
+    /// assert_eq!(serde_yaml::to_string(&yaml).unwrap(), "server:\n  port: 8080\n");
+    /// ```
+    pub fn apply_to_yaml(&self, yaml: &mut serde_yaml::Value) -> Result<Operation, JqesqueError> {
+        let mut json: Value = serde_json::to_value(&*yaml).map_err(yaml_error)?;
+        let operation = self.apply_to(&mut json)?;
+        *yaml = to_yaml(&json);
+        Ok(operation)
+    }
+
+    /// Converts the parsed structure into a new document, as by `try_as_json`, serialized as YAML.
+    ///
+    /// ## Returns
+    ///
+    /// Returns the YAML string, the error from `try_as_json` if the document cannot be built, or a
+    /// `YamlError` if serialization fails.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use jqesque::Jqesque;
+    ///
+    /// let jqesque = ">foo.bar[0]=true".parse::<Jqesque>().unwrap();
+    /// assert_eq!(jqesque.to_yaml_string().unwrap(), "foo:\n  bar:\n  - true\n");
+    /// ```
+    pub fn to_yaml_string(&self) -> Result<String, JqesqueError> {
+        serde_yaml::to_string(&to_yaml(&self.try_as_json()?)).map_err(yaml_error)
+    }
+}
+
+// Convert a JSON value to YAML. Numbers are converted explicitly rather than serialized, as
+// with serde_json's `arbitrary_precision` they serialize as a map holding the number's text.
+fn to_yaml(json: &Value) -> serde_yaml::Value {
+    match json {
+        Value::Null => serde_yaml::Value::Null,
+        Value::Bool(b) => serde_yaml::Value::Bool(*b),
+        Value::Number(number) => {
+            if let Some(n) = number.as_u64() {
+                serde_yaml::Value::Number(n.into())
+            } else if let Some(n) = number.as_i64() {
+                serde_yaml::Value::Number(n.into())
+            } else if let Some(n) = number.as_f64() {
+                serde_yaml::Value::Number(n.into())
+            } else {
+                serde_yaml::Value::String(number.to_string())
+            }
+        }
+        Value::String(s) => serde_yaml::Value::String(s.clone()),
+        Value::Array(array) => serde_yaml::Value::Sequence(array.iter().map(to_yaml).collect()),
+        Value::Object(map) => serde_yaml::Value::Mapping(
+            map.iter()
+                .map(|(key, value)| (serde_yaml::Value::String(key.clone()), to_yaml(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn yaml_error(error: impl std::error::Error + Send + Sync + 'static) -> JqesqueError {
+    JqesqueError::YamlError(error.to_string(), ErrorSource::new(error))
+}
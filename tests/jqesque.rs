@@ -1,4 +1,8 @@
-use jqesque::{Jqesque, JqesqueError, Operation, Separator};
+use jqesque::{
+    ApplyOptions, ApplyOutcome, ArrayMergeStrategy, ErrorSource, Jqesque, JqesqueBuilder,
+    JqesqueError, JsonLike, KeyOrder, MergeOptions, MergeStrategy, MissingPolicy, NodeEvent,
+    Operation, ParseOptions, PathToken, Separator, ValueType,
+};
 use serde_json::json;
 use yare::parameterized;
 
@@ -435,23 +439,34 @@ fn test_auto_operation(
 
 /// Tests for patch errors.
 #[parameterized(
-        remove_nonexistent_key = { "-nonexistent", Separator::Dot },
-        replace_nonexistent_key = { "=nonexistent=value", Separator::Dot },
-        add_invalid_index = { "+array[10]=value", Separator::Dot },
+        remove_nonexistent_key = { "-nonexistent", Separator::Dot, Operation::Remove, "/nonexistent" },
+        replace_nonexistent_key = { "=nonexistent=value", Separator::Dot, Operation::Replace, "/nonexistent" },
+        replace_nested = { "=a/b/c=value", Separator::Slash, Operation::Replace, "/a/b/c" },
+        add_invalid_index = { "+array[10]=value", Separator::Dot, Operation::Add, "/array/10" },
     )]
-fn test_patch_errors(input: &str, separator: Separator) {
+fn test_patch_errors(input: &str, separator: Separator, op: Operation, path: &str) {
     let parsed = Jqesque::from_str_with_separator(input, separator).unwrap();
     let mut json_obj = json!({ "array": [1, 2, 3] });
     let result = parsed.apply_to(&mut json_obj);
-    assert!(
-        result.is_err(),
-        "Expected PatchError but operation succeeded"
+    assert_eq!(
+        result,
+        Err(JqesqueError::PatchError {
+            op,
+            path: path.to_string(),
+            message: "path is invalid".to_string(),
+            source: ErrorSource::new(json_patch::PatchErrorKind::InvalidPointer),
+        })
+    );
+}
+
+#[test]
+fn test_patch_error_message() {
+    let parsed = "=a.b.c=value".parse::<Jqesque>().unwrap();
+    let err = parsed.apply_to(&mut json!({})).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Operation replace failed at /a/b/c: path is invalid"
     );
-    match result {
-        Err(JqesqueError::PatchError(_)) => (),
-        Err(e) => panic!("Expected PatchError, got {:?}", e),
-        _ => panic!("Expected error but operation succeeded"),
-    }
 }
 
 /// Tests for the `Test` operation that should **succeed**.
@@ -489,7 +504,7 @@ fn test_test_failed_errors(input: &str, separator: Separator, initial_json: serd
                 expected, actual
             );
         }
-        Err(JqesqueError::InvalidPathError(_)) => {
+        Err(JqesqueError::InvalidPathError(..)) => {
             // This can happen if the path doesn't exist
         }
         Err(e) => panic!("Expected TestFailedError, got {:?}", e),
@@ -500,7 +515,7 @@ fn test_test_failed_errors(input: &str, separator: Separator, initial_json: serd
 /// Tests for invalid path errors.
 #[parameterized(
         invalid_path_syntax = { "+key..subkey=value", Separator::Dot },
-        invalid_array_index = { "+array[-1]=value", Separator::Dot },
+        invalid_array_index = { "+array[-0]=value", Separator::Dot },
         invalid_escape_sequence = { "+key\\subkey=value", Separator::Dot },
     )]
 fn test_invalid_path_errors(input: &str, separator: Separator) {
@@ -510,8 +525,8 @@ fn test_invalid_path_errors(input: &str, separator: Separator) {
         "Expected parsing error due to invalid path but parsing succeeded"
     );
     match result {
-        Err(JqesqueError::NomError(_)) => (),
-        Err(e) => panic!("Expected NomError, got {:?}", e),
+        Err(JqesqueError::ParseError { .. }) => (),
+        Err(e) => panic!("Expected ParseError, got {:?}", e),
         _ => panic!("Expected error but parsing succeeded"),
     }
 }
@@ -529,3 +544,3470 @@ fn test_as_json(input: &str, expected: serde_json::Value) {
 
     assert_eq!(json_obj, expected);
 }
+
+/// Tests for try_as_json that should **fail**, where `as_json` gives `null`.
+#[parameterized(
+    index_too_large = { ">a[200000]=1", "IndexTooLargeError" },
+    relative_before_start = { ">a[#-1]=1", "RelativeIndexError" },
+    auto = { "a[200000]=1", "IndexTooLargeError" },
+)]
+fn test_try_as_json_err(input: &str, expected: &str) {
+    let jqesque = input.parse::<Jqesque>().expect("Failed to parse input");
+    let error = jqesque.try_as_json().expect_err("Expected an error");
+
+    assert!(
+        format!("{:?}", error).starts_with(expected),
+        "Got {:?}",
+        error
+    );
+    assert_eq!(jqesque.as_json(), serde_json::Value::Null);
+}
+
+/// Tests for Operation::requires_existing_path.
+#[parameterized(
+    add = { Operation::Add, true },
+    replace = { Operation::Replace, true },
+    remove = { Operation::Remove, true },
+    test = { Operation::Test, true },
+    insert = { Operation::Insert, false },
+    merge = { Operation::Merge, false },
+    auto = { Operation::Auto, false },
+)]
+fn test_requires_existing_path(operation: Operation, expected: bool) {
+    assert_eq!(operation.requires_existing_path(), expected);
+}
+
+/// Tests that parsed structures hold no borrows from the input.
+#[parameterized(
+    simple_key = { "key=value", json!({"key": "value"}) },
+    nested_keys = { ">parent.child=value", json!({"parent": {"child": "value"}}) },
+)]
+fn test_parse_owned(input: &str, expected: serde_json::Value) {
+    fn assert_static<T: 'static>(_: &T) {}
+
+    let owned = input.to_string();
+    let parsed = Jqesque::parse_owned(owned).expect("Failed to parse input");
+    assert_static(&parsed);
+
+    let mut json_obj = serde_json::Value::Null;
+    parsed.apply_to(&mut json_obj).unwrap();
+
+    assert_eq!(json_obj, expected);
+}
+
+#[test]
+fn test_parse_owned_error_is_owned() {
+    fn assert_static<T: 'static>(_: &T) {}
+
+    let err = Jqesque::parse_owned("key".to_string()).unwrap_err();
+    assert_static(&err);
+}
+
+/// Tests for apply_to_logging.
+#[parameterized(
+    replace_existing = { "existing_key=new_value", Operation::Replace, "/existing_key", Some(json!("old_value")), Some(json!("new_value")) },
+    insert_new = { "parent.child=value", Operation::Insert, "/parent/child", None, Some(json!("value")) },
+    remove_existing = { "-array[1]", Operation::Remove, "/array/1", Some(json!(2)), Some(json!(3)) },
+    test_existing = { "?existing_key=old_value", Operation::Test, "/existing_key", Some(json!("old_value")), Some(json!("old_value")) },
+)]
+fn test_apply_to_logging(
+    input: &str,
+    expected_operation: Operation,
+    expected_pointer: &str,
+    expected_before: Option<serde_json::Value>,
+    expected_after: Option<serde_json::Value>,
+) {
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+
+    let mut json_obj = json!({
+        "existing_key": "old_value",
+        "array": [1, 2, 3]
+    });
+    let mut log = Vec::new();
+
+    let operation = parsed.apply_to_logging(&mut json_obj, &mut log).unwrap();
+    assert_eq!(operation, expected_operation);
+
+    assert_eq!(log.len(), 1);
+    let entry = &log[0];
+    assert_eq!(entry.operation, expected_operation);
+    assert_eq!(entry.pointer, expected_pointer);
+    assert!(entry.timestamp.is_some());
+    assert_eq!(entry.before, expected_before);
+    assert_eq!(entry.after, expected_after);
+}
+
+#[test]
+fn test_apply_to_logging_failure_is_not_logged() {
+    let parsed = "=nonexistent=value".parse::<Jqesque>().unwrap();
+
+    let mut json_obj = json!({ "key": "value" });
+    let mut log = Vec::new();
+
+    assert!(parsed.apply_to_logging(&mut json_obj, &mut log).is_err());
+    assert!(log.is_empty());
+}
+
+/// Tests for apply_to_logging with wildcards, which log an entry per match.
+#[parameterized(
+    remove = { "-items.*.id", Operation::Remove, vec![("/items/0/id", Some(json!(1)), None), ("/items/1/id", Some(json!(2)), None)] },
+    test = { "?items.*.tag=x", Operation::Test, vec![("/items/0/tag", Some(json!("x")), Some(json!("x"))), ("/items/1/tag", Some(json!("x")), Some(json!("x")))] },
+    no_matches = { "-missing.*", Operation::Remove, vec![] },
+)]
+fn test_apply_to_logging_wildcard(
+    input: &str,
+    expected_operation: Operation,
+    expected: Vec<(&str, Option<serde_json::Value>, Option<serde_json::Value>)>,
+) {
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+    let mut json_obj = json!({"items": [{"id": 1, "tag": "x"}, {"id": 2, "tag": "x"}]});
+    let mut log = Vec::new();
+
+    let operation = parsed.apply_to_logging(&mut json_obj, &mut log).unwrap();
+    assert_eq!(operation, expected_operation);
+
+    let entries: Vec<_> = log
+        .into_iter()
+        .map(|entry| (entry.pointer, entry.before, entry.after))
+        .collect();
+    let expected: Vec<_> = expected
+        .into_iter()
+        .map(|(pointer, before, after)| (pointer.to_string(), before, after))
+        .collect();
+    assert_eq!(entries, expected);
+}
+
+/// Tests for get_first.
+#[parameterized(
+    first_exists = { vec!["a.b", "a.legacy_b"], Some((0, json!(1))) },
+    second_exists = { vec!["a.missing", "a.legacy_b"], Some((1, json!(2))) },
+    array_index = { vec!["a.missing", "list[1]"], Some((1, json!("y"))) },
+    none_exist = { vec!["a.missing", "b"], None },
+)]
+fn test_get_first(paths: Vec<&str>, expected: Option<(usize, serde_json::Value)>) {
+    let json_obj = json!({
+        "a": { "b": 1, "legacy_b": 2 },
+        "list": ["x", "y"]
+    });
+
+    let found = Jqesque::get_first(&paths, Separator::Dot, &json_obj).unwrap();
+    assert_eq!(found.map(|(i, v)| (i, v.clone())), expected);
+}
+
+#[test]
+fn test_get_first_invalid_path() {
+    let json_obj = json!({ "a": 1 });
+    let result = Jqesque::get_first(&["a..b"], Separator::Dot, &json_obj);
+    assert!(result.is_err());
+}
+
+/// Tests for lint_separators and normalize_separators.
+#[parameterized(
+    clean_dot = { "a.b.c=1", Separator::Dot, vec![], "a.b.c=1" },
+    clean_slash = { "a/b/c=1", Separator::Slash, vec![], "a/b/c=1" },
+    slash_in_dot_path = { "a.b/c=1", Separator::Dot, vec![(1, 3, '/')], "a.b.c=1" },
+    dot_in_slash_path = { "a.b/c=1", Separator::Slash, vec![(0, 1, '.')], "a/b/c=1" },
+    with_operator = { "+a/b.c=1", Separator::Slash, vec![(1, 4, '.')], "+a/b/c=1" },
+    after_index = { "a[0]/b=1", Separator::Dot, vec![(1, 4, '/')], "a[0].b=1" },
+    quoted_key_ignored = { "\"a/b\".c=1", Separator::Dot, vec![], "\"a/b\".c=1" },
+    single_quoted_key_ignored = { "'a/b'.c=1", Separator::Dot, vec![], "'a/b'.c=1" },
+    escaped_separator_ignored = { "a\\.b/c=1", Separator::Dot, vec![(0, 4, '/')], "a\\.b.c=1" },
+    value_ignored = { "url=/api/v1", Separator::Dot, vec![], "url=/api/v1" },
+    remove_without_value = { "-a/b", Separator::Dot, vec![(0, 2, '/')], "-a.b" },
+    multi_char = { "a::b/c.d=1", Separator::CustomStr("::".to_string()), vec![(1, 4, '/'), (1, 6, '.')], "a::b::c::d=1" },
+)]
+fn test_lint_separators(
+    input: &str,
+    separator: Separator,
+    expected: Vec<(usize, usize, char)>,
+    expected_normalized: &str,
+) {
+    let warnings: Vec<_> = jqesque::lint_separators(input, separator.clone())
+        .into_iter()
+        .map(|w| (w.token_index, w.offset, w.found))
+        .collect();
+    assert_eq!(warnings, expected);
+
+    let normalized = jqesque::normalize_separators(input, separator.clone());
+    assert_eq!(normalized, expected_normalized);
+    assert!(Jqesque::from_str_with_separator(&normalized, separator).is_ok());
+}
+
+/// Tests for apply_to_or.
+#[parameterized(
+    replace_existing = { "=existing_key=new_value", Operation::Replace, json!({"existing_key": "new_value", "array": [1, 2, 3]}) },
+    replace_missing = { "=missing.key=new_value", Operation::Insert, json!({"existing_key": "old_value", "array": [1, 2, 3], "missing": {"key": "default"}}) },
+    replace_missing_index = { "=array[5]=new_value", Operation::Insert, json!({"existing_key": "old_value", "array": [1, 2, 3, null, null, "default"]}) },
+    add_unaffected = { "+new_key=new_value", Operation::Add, json!({"existing_key": "old_value", "array": [1, 2, 3], "new_key": "new_value"}) },
+)]
+fn test_apply_to_or(input: &str, expected_operation: Operation, expected: serde_json::Value) {
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+
+    let mut json_obj = json!({
+        "existing_key": "old_value",
+        "array": [1, 2, 3]
+    });
+
+    let operation = parsed.apply_to_or(&mut json_obj, json!("default")).unwrap();
+    assert_eq!(operation, expected_operation);
+    assert_eq!(json_obj, expected);
+}
+
+/// Tests for the exposed parser combinators.
+#[parameterized(
+    simple = { "foo", '.', "", vec![PathToken::Key("foo".to_string())] },
+    nested = { "foo.bar=1", '.', "=1", vec![PathToken::Key("foo".to_string()), PathToken::Key("bar".to_string())] },
+    with_index = { "foo/bar[1] rest", '/', " rest", vec![PathToken::Key("foo".to_string()), PathToken::Key("bar".to_string()), PathToken::Index(1)] },
+    quoted = { "\"a.b\".c", '.', "", vec![PathToken::Key("a.b".to_string()), PathToken::Key("c".to_string())] },
+)]
+fn test_parser_path(input: &str, separator: char, rest: &str, expected: Vec<PathToken>) {
+    let (remaining, tokens) = jqesque::parser::path(input, separator).unwrap();
+    assert_eq!(remaining, rest);
+    assert_eq!(tokens, expected);
+}
+
+#[parameterized(
+    number = { "42", json!(42) },
+    object = { "{\"a\":1}", json!({"a": 1}) },
+    string = { "hello", json!("hello") },
+)]
+fn test_parser_json_value(input: &str, expected: serde_json::Value) {
+    let (remaining, value) = jqesque::parser::json_value(input).unwrap();
+    assert_eq!(remaining, "");
+    assert_eq!(value, expected);
+}
+
+#[parameterized(
+    bare = { "foo.bar", '.', ".bar", vec![PathToken::Key("foo".to_string())] },
+    quoted = { "\"a.b\"/c", '/', "/c", vec![PathToken::Key("a.b".to_string())] },
+    wildcard = { "*.x", '.', ".x", vec![PathToken::Wildcard] },
+    append = { "-=1", '.', "=1", vec![PathToken::Append] },
+)]
+fn test_parser_key_segment(input: &str, separator: char, rest: &str, expected: Vec<PathToken>) {
+    let (remaining, tokens) = jqesque::parser::key_segment(input, separator).unwrap();
+    assert_eq!(remaining, rest);
+    assert_eq!(tokens, expected);
+}
+
+/// Tests for length-relative array indices that should **succeed**.
+#[parameterized(
+    insert_append = { ">array[#]=4", json!({"array": [1, 2, 3, 4]}) },
+    insert_last = { ">array[#-1]=4", json!({"array": [1, 2, 4]}) },
+    insert_past_end = { ">array[#+1]=4", json!({"array": [1, 2, 3, null, 4]}) },
+    insert_new_array = { ">new[#]=1", json!({"array": [1, 2, 3], "new": [1]}) },
+    insert_nested = { ">objects[#].name=x", json!({"array": [1, 2, 3], "objects": [{"name": "x"}]}) },
+    add_append = { "+array[#]=4", json!({"array": [1, 2, 3, 4]}) },
+    add_before_last = { "+array[#-1]=4", json!({"array": [1, 2, 4, 3]}) },
+    replace_last = { "=array[#-1]=4", json!({"array": [1, 2, 4]}) },
+    remove_last = { "-array[#-1]", json!({"array": [1, 2]}) },
+    remove_first = { "-array[#-3]", json!({"array": [2, 3]}) },
+    test_last = { "?array[#-1]=3", json!({"array": [1, 2, 3]}) },
+    negative_remove_last = { "-array[-1]", json!({"array": [1, 2]}) },
+    negative_replace_first = { "=array[-3]=0", json!({"array": [0, 2, 3]}) },
+    negative_insert_last = { ">array[-1]=4", json!({"array": [1, 2, 4]}) },
+    negative_nested = { ">array[-1][0]=4", json!({"array": [1, 2, [4]]}) },
+    merge_append = { "~array[#]=4", json!({"array": [1, 2, 3, 4]}) },
+    merge_last = { "~array[#-1]=4", json!({"array": [1, 2, 4]}) },
+)]
+fn test_length_relative_index_ok(input: &str, expected: serde_json::Value) {
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+
+    let mut json_obj = json!({ "array": [1, 2, 3] });
+    parsed.apply_to(&mut json_obj).unwrap();
+
+    assert_eq!(json_obj, expected);
+}
+
+/// Tests for length-relative array indices that should **fail**.
+#[parameterized(
+    insert_last_of_new_array = { ">new[#-1]=1", JqesqueError::RelativeIndexError { offset: -1, len: 0 } },
+    remove_out_of_range = { "-array[#-4]", JqesqueError::RelativeIndexError { offset: -4, len: 3 } },
+    replace_last_of_missing_array = { "=missing[#-1]=1", JqesqueError::InvalidPathError("no array to resolve length-relative index at token 1".to_string(), None) },
+    negative_insert_new_array = { ">new[-1]=1", JqesqueError::RelativeIndexError { offset: -1, len: 0 } },
+    negative_remove_out_of_range = { "-array[-4]", JqesqueError::RelativeIndexError { offset: -4, len: 3 } },
+    overflow = { ">array[#+9223372036854775807]=1", JqesqueError::RelativeIndexError { offset: isize::MAX, len: 3 } },
+)]
+fn test_length_relative_index_err(input: &str, expected: JqesqueError) {
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+
+    let mut json_obj = json!({ "array": [1, 2, 3] });
+    let result = parsed.apply_to(&mut json_obj);
+
+    assert_eq!(result, Err(expected));
+    assert_eq!(json_obj, json!({ "array": [1, 2, 3] }));
+}
+
+#[test]
+fn test_length_relative_index_repeated_append() {
+    let parsed = ">log[#]=entry".parse::<Jqesque>().unwrap();
+
+    let mut json_obj = serde_json::Value::Null;
+    for _ in 0..3 {
+        parsed.apply_to(&mut json_obj).unwrap();
+    }
+
+    assert_eq!(json_obj, json!({"log": ["entry", "entry", "entry"]}));
+}
+
+/// Tests for apply_to_detailed.
+#[parameterized(
+    add_append_token = { "+array/-=4", ApplyOutcome::Appended { index: 3 } },
+    add_append_index = { "+array/3=4", ApplyOutcome::Appended { index: 3 } },
+    add_insert_index = { "+array/1=4", ApplyOutcome::Inserted { index: 1 } },
+    add_object_key = { "+new_key=4", ApplyOutcome::Applied(Operation::Add) },
+    auto_append = { "array/3=4", ApplyOutcome::Appended { index: 3 } },
+    auto_replace = { "array/1=4", ApplyOutcome::Applied(Operation::Replace) },
+    insert = { ">array/1=4", ApplyOutcome::Applied(Operation::Insert) },
+)]
+fn test_apply_to_detailed(input: &str, expected: ApplyOutcome) {
+    let parsed =
+        Jqesque::from_str_with_separator(input, Separator::Slash).expect("Failed to parse input");
+
+    let mut json_obj = json!({ "array": [1, 2, 3] });
+    let outcome = parsed.apply_to_detailed(&mut json_obj).unwrap();
+
+    assert_eq!(outcome, expected);
+    assert_eq!(
+        outcome.operation(),
+        parsed.apply_to(&mut json!({ "array": [1, 2, 3] })).unwrap()
+    );
+}
+
+/// Tests for number formatting with and without the `arbitrary_precision` feature.
+#[parameterized(
+    trailing_zero = { "key=1.50", "1.50", "1.5" },
+    exponent = { "key=1e3", "1e3", "1000.0" },
+    integer = { "key=42", "42", "42" },
+)]
+fn test_number_formatting(input: &str, preserved: &str, normalized: &str) {
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+    let value = parsed.value().clone().unwrap();
+
+    let expected = if cfg!(feature = "arbitrary_precision") {
+        preserved
+    } else {
+        normalized
+    };
+    assert_eq!(serde_json::to_string(&value).unwrap(), expected);
+}
+
+/// Tests for split_assignments.
+#[parameterized(
+    single = { "foo=1", vec!["foo=1"] },
+    semicolons = { "foo.bar=1;baz[0]=true;-old.key", vec!["foo.bar=1", "baz[0]=true", "-old.key"] },
+    newlines = { "foo=1\nbar=2\r\nbaz=3", vec!["foo=1", "bar=2", "baz=3"] },
+    whitespace_and_empty = { "  foo=1 ;; \n\n bar=2 ;", vec!["foo=1", "bar=2"] },
+    delimiter_in_json_object = { "x={\"a\":\";\"};y=1", vec!["x={\"a\":\";\"}", "y=1"] },
+    delimiter_in_json_array = { "x=[1,\n2];y=1", vec!["x=[1,\n2]", "y=1"] },
+    delimiter_in_quoted_key = { "\"a;b\"=1;c=2", vec!["\"a;b\"=1", "c=2"] },
+    escaped_quote = { "x=\"a\\\";b\";y=1", vec!["x=\"a\\\";b\"", "y=1"] },
+    delimiter_in_single_quoted_key = { "'a;b'=1;c=2", vec!["'a;b'=1", "c=2"] },
+    escaped_single_quote = { "'it\\';s'=1;c=2", vec!["'it\\';s'=1", "c=2"] },
+    single_quote_in_nested_key = { "a.'b;c'=1;d=2", vec!["a.'b;c'=1", "d=2"] },
+    apostrophe_in_value = { "name=O'Brien;x=1", vec!["name=O'Brien", "x=1"] },
+    apostrophe_after_space_in_value = { "msg=rock 'n roll;b=2", vec!["msg=rock 'n roll", "b=2"] },
+    single_quote_opening_value = { "x='a;b'", vec!["x='a", "b'"] },
+    single_quoted_key_after_operator = { "='a;b'=1;?='c;d'=1=>2", vec!["='a;b'=1", "?='c;d'=1=>2"] },
+    single_quoted_copy_source = { "&a<'b;c';d=1", vec!["&a<'b;c'", "d=1"] },
+    empty = { "", vec![] },
+    trailing_comment = { "foo=bar # set foo", vec!["foo=bar"] },
+    comment_line = { "# header\nfoo=1\n  # indented\nbar=2", vec!["foo=1", "bar=2"] },
+    comment_after_delimiter = { "foo=1;# rest;bar=2\nbaz=3", vec!["foo=1", "baz=3"] },
+    comment_only = { "# nothing here", vec![] },
+    hash_in_value = { "color=#fff;x=a#b", vec!["color=#fff", "x=a#b"] },
+    hash_in_quotes = { "x=\"a # b\" # c", vec!["x=\"a # b\""] },
+    hash_in_single_quoted_key = { "'a #b'=1 # c", vec!["'a #b'=1"] },
+    hash_after_apostrophe_in_value = { "msg=rock 'n roll # note\nb=2", vec!["msg=rock 'n roll", "b=2"] },
+    hash_in_json = { "x={\"a\": \"#\", \"b\": [ #1]}", vec!["x={\"a\": \"#\", \"b\": [ #1]}"] },
+    hash_in_index = { ">arr[#]=1 #append", vec![">arr[#]=1"] },
+)]
+fn test_split_assignments(input: &str, expected: Vec<&str>) {
+    assert_eq!(jqesque::split_assignments(input), expected);
+}
+
+/// Tests for type annotations on the path that should **succeed**.
+#[parameterized(
+    int = { "count:int=5", json!({"count": 5}) },
+    str = { "name:str=bob", json!({"name": "bob"}) },
+    str_quoted_number = { "zip:str=\"01234\"", json!({"zip": "01234"}) },
+    float = { "ratio:float=0.5", json!({"ratio": 0.5}) },
+    float_from_int = { "ratio:float=1", json!({"ratio": 1}) },
+    bool = { "flag:bool=true", json!({"flag": true}) },
+    null = { "nothing:null=null", json!({"nothing": null}) },
+    array = { "list:array=[1,2]", json!({"list": [1, 2]}) },
+    object = { "obj:object={\"a\":1}", json!({"obj": {"a": 1}}) },
+    nested_path = { ">a.b[0]:int=1", json!({"a": {"b": [1]}}) },
+)]
+fn test_type_annotation_ok(input: &str, expected: serde_json::Value) {
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+    assert!(parsed.required_type.is_some());
+
+    let mut json_obj = serde_json::Value::Null;
+    parsed.apply_to(&mut json_obj).unwrap();
+
+    assert_eq!(json_obj, expected);
+}
+
+/// Tests for type annotations on the path that should **fail** when applied.
+#[parameterized(
+    int_given_string = { "count:int=five", ValueType::Int, json!("five") },
+    int_given_float = { "count:int=5.5", ValueType::Int, json!(5.5) },
+    str_given_number = { "name:str=1234", ValueType::Str, json!(1234) },
+    bool_given_string = { "flag:bool=yes", ValueType::Bool, json!("yes") },
+    object_given_array = { "obj:object=[1]", ValueType::Object, json!([1]) },
+)]
+fn test_type_annotation_mismatch(input: &str, expected: ValueType, actual: serde_json::Value) {
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+
+    let mut json_obj = json!({});
+    let result = parsed.apply_to(&mut json_obj);
+
+    assert_eq!(
+        result,
+        Err(JqesqueError::TypeMismatchError { expected, actual })
+    );
+    assert_eq!(json_obj, json!({}));
+}
+
+#[test]
+fn test_type_annotation_unknown_type() {
+    assert!("count:integer=5".parse::<Jqesque>().is_err());
+}
+
+/// Tests for removing with `ApplyOptions::remove_if_exists`.
+#[parameterized(
+    existing_key = { "-existing_key", ApplyOutcome::Applied(Operation::Remove), json!({"array": [1, 2, 3]}) },
+    existing_index = { "-array[1]", ApplyOutcome::Applied(Operation::Remove), json!({"existing_key": "value", "array": [1, 3]}) },
+    missing_key = { "-missing", ApplyOutcome::Unchanged(Operation::Remove), json!({"existing_key": "value", "array": [1, 2, 3]}) },
+    missing_nested_key = { "-missing.nested", ApplyOutcome::Unchanged(Operation::Remove), json!({"existing_key": "value", "array": [1, 2, 3]}) },
+    missing_index = { "-array[10]", ApplyOutcome::Unchanged(Operation::Remove), json!({"existing_key": "value", "array": [1, 2, 3]}) },
+    missing_relative_index = { "-missing[#-1]", ApplyOutcome::Unchanged(Operation::Remove), json!({"existing_key": "value", "array": [1, 2, 3]}) },
+)]
+fn test_remove_if_exists(input: &str, expected_outcome: ApplyOutcome, expected: serde_json::Value) {
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+    let options = ApplyOptions::new().remove_if_exists(true);
+
+    let mut json_obj = json!({
+        "existing_key": "value",
+        "array": [1, 2, 3]
+    });
+
+    let outcome = parsed
+        .apply_to_with_options(&mut json_obj, &options)
+        .unwrap();
+
+    assert_eq!(outcome, expected_outcome);
+    assert_eq!(json_obj, expected);
+}
+
+#[test]
+fn test_remove_if_exists_is_rerunnable() {
+    let parsed = "-key".parse::<Jqesque>().unwrap();
+    let options = ApplyOptions::new().remove_if_exists(true);
+
+    let mut json_obj = json!({ "key": "value" });
+    let first = parsed.apply_to_with_options(&mut json_obj, &options);
+    let second = parsed.apply_to_with_options(&mut json_obj, &options);
+
+    assert_eq!(first, Ok(ApplyOutcome::Applied(Operation::Remove)));
+    assert_eq!(second, Ok(ApplyOutcome::Unchanged(Operation::Remove)));
+    assert_eq!(json_obj, json!({}));
+
+    // Without the option, the second removal fails
+    assert!(parsed.apply_to(&mut json_obj).is_err());
+}
+
+/// Tests for from_segments.
+#[parameterized(
+    simple = { vec!["key"], json!({"key": "value"}) },
+    nested = { vec!["parent", "child"], json!({"parent": {"child": "value"}}) },
+    separator_in_segment = { vec!["a.b", "c/d"], json!({"a.b": {"c/d": "value"}}) },
+    numeric_segment_stays_key = { vec!["list", "0"], json!({"list": {"0": "value"}}) },
+    quotes_in_segment = { vec!["\"quoted\""], json!({"\"quoted\"": "value"}) },
+)]
+fn test_from_segments(segments: Vec<&str>, expected: serde_json::Value) {
+    let jqesque = Jqesque::from_segments(segments, Some(json!("value")), Operation::Insert);
+
+    let mut json_obj = serde_json::Value::Null;
+    jqesque.apply_to(&mut json_obj).unwrap();
+
+    assert_eq!(json_obj, expected);
+}
+
+#[test]
+fn test_from_segments_owned_strings() {
+    let segments: Vec<String> = vec!["key".to_string()];
+    let jqesque = Jqesque::from_segments(segments, None, Operation::Remove);
+
+    let mut json_obj = json!({ "key": "value", "other": 1 });
+    jqesque.apply_to(&mut json_obj).unwrap();
+
+    assert_eq!(json_obj, json!({ "other": 1 }));
+}
+
+/// Tests for the failing token reported in path resolution errors.
+#[parameterized(
+    missing_root_key = { "?missing=1", 0, "missing" },
+    missing_nested_key = { "?foo.bar.baz=1", 1, "bar" },
+    missing_leaf = { "?foo.qux=1", 1, "qux" },
+    index_out_of_range = { "?list[5]=1", 1, "5" },
+    key_into_scalar = { "?foo.leaf.deeper=1", 2, "deeper" },
+)]
+fn test_invalid_path_error_token(input: &str, index: usize, token: &str) {
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+    let json_obj = json!({
+        "foo": { "leaf": true },
+        "list": [1, 2]
+    });
+
+    match parsed.apply_to(&mut json_obj.clone()) {
+        Err(JqesqueError::InvalidPathError(message, _)) => {
+            let expected = format!("failed at token {} (`{}`)", index, token);
+            assert!(
+                message.starts_with(&expected),
+                "Expected message to start with {:?}, got {:?}",
+                expected,
+                message
+            );
+        }
+        other => panic!("Expected InvalidPathError, got {:?}", other),
+    }
+}
+
+/// Tests for from_json_spec and to_json_spec that should **succeed**.
+#[parameterized(
+    insert = {
+        json!({"op": "insert", "path": ["foo", "bar", 0, "baz"], "value": "hi"}),
+        ">foo.bar[0].baz=hi"
+    },
+    remove = { json!({"op": "remove", "path": ["array", 1]}), "-array[1]" },
+    auto = { json!({"op": "auto", "path": ["key"], "value": 1}), "key=1" },
+    merge_object = { json!({"op": "merge", "path": ["a"], "value": {"b": true}}), "~a={\"b\": true}" },
+    length_relative = { json!({"op": "replace", "path": ["array", {"length_relative": -1}], "value": 4}), "=array[#-1]=4" },
+    typed = { json!({"op": "insert", "path": ["count"], "value": 5, "type": "int"}), ">count:int=5" },
+)]
+fn test_json_spec_ok(spec: serde_json::Value, equivalent: &str) {
+    let from_spec = Jqesque::from_json_spec(&spec).expect("Failed to build from spec");
+    let parsed = equivalent
+        .parse::<Jqesque>()
+        .expect("Failed to parse input");
+
+    assert_eq!(from_spec, parsed);
+    assert_eq!(from_spec.to_json_spec(), spec);
+}
+
+#[test]
+fn test_json_spec_defaults_to_auto() {
+    let jqesque = Jqesque::from_json_spec(&json!({"path": ["key"], "value": 1})).unwrap();
+    assert_eq!(jqesque.operation, Operation::Auto);
+}
+
+/// Tests for from_json_spec that should **fail**.
+#[parameterized(
+    not_an_object = { json!(["insert"]) },
+    unknown_op = { json!({"op": "frobnicate", "path": ["a"], "value": 1}) },
+    missing_path = { json!({"op": "insert", "value": 1}) },
+    path_not_array = { json!({"op": "insert", "path": "a.b", "value": 1}) },
+    negative_index = { json!({"op": "insert", "path": ["a", -1], "value": 1}) },
+    float_index = { json!({"op": "insert", "path": ["a", 1.5], "value": 1}) },
+    bool_segment = { json!({"op": "insert", "path": [true], "value": 1}) },
+    unknown_type = { json!({"op": "insert", "path": ["a"], "value": 1, "type": "integer"}) },
+    copy_without_from = { json!({"op": "copy", "path": ["a"]}) },
+    move_without_from = { json!({"op": "move", "path": ["a"]}) },
+)]
+fn test_json_spec_err(spec: serde_json::Value) {
+    assert!(Jqesque::from_json_spec(&spec).is_err());
+}
+
+#[test]
+fn test_json_spec_from_ignored() {
+    let spec = json!({"op": "insert", "path": ["a"], "value": 1, "from": ["b"]});
+    let jqesque = Jqesque::from_json_spec(&spec).unwrap();
+
+    assert_eq!(jqesque.from, None);
+    assert_eq!(
+        jqesque.to_json_spec(),
+        json!({"op": "insert", "path": ["a"], "value": 1})
+    );
+}
+
+/// Tests for merging with the different null strategies.
+#[parameterized(
+    overwrite_null = { "~user={\"name\": null, \"age\": 31}", MergeStrategy::Overwrite, json!({"user": {"name": null, "age": 31}}) },
+    skip_null = { "~user={\"name\": null, \"age\": 31}", MergeStrategy::SkipNull, json!({"user": {"name": "bob", "age": 31}}) },
+    skip_null_new_key = { "~user={\"email\": null}", MergeStrategy::SkipNull, json!({"user": {"name": "bob", "age": 30}}) },
+    skip_null_value = { "~user.name=null", MergeStrategy::SkipNull, json!({"user": {"name": "bob", "age": 30}}) },
+    overwrite_null_value = { "~user.name=null", MergeStrategy::Overwrite, json!({"user": {"name": null, "age": 30}}) },
+    delete_null_value = { "~user.name=null", MergeStrategy::DeleteNull, json!({"user": {"age": 30}}) },
+    delete_null_in_object = { "~user={\"name\": null, \"age\": 31}", MergeStrategy::DeleteNull, json!({"user": {"age": 31}}) },
+    delete_null_missing_key = { "~user.email=null", MergeStrategy::DeleteNull, json!({"user": {"name": "bob", "age": 30}}) },
+    delete_null_missing_parent = { "~other.key=null", MergeStrategy::DeleteNull, json!({"user": {"name": "bob", "age": 30}}) },
+    string_concat = { "~user.name=\" jr\"", MergeStrategy::StringConcat, json!({"user": {"name": "bob jr", "age": 30}}) },
+    string_concat_in_object = { "~user={\"name\": \"by\", \"age\": 31}", MergeStrategy::StringConcat, json!({"user": {"name": "bobby", "age": 31}}) },
+    string_concat_new_key = { "~user.email=b@example.com", MergeStrategy::StringConcat, json!({"user": {"name": "bob", "age": 30, "email": "b@example.com"}}) },
+    string_concat_number = { "~user.age=\"31\"", MergeStrategy::StringConcat, json!({"user": {"name": "bob", "age": "31"}}) },
+    overwrite_string = { "~user.name=\" jr\"", MergeStrategy::Overwrite, json!({"user": {"name": " jr", "age": 30}}) },
+)]
+fn test_merge_strategy(input: &str, strategy: MergeStrategy, expected: serde_json::Value) {
+    let mut json_obj = json!({"user": {"name": "bob", "age": 30}});
+    let jqesque = input.parse::<Jqesque>().expect("Failed to parse input");
+    let options = ApplyOptions::new().merge(MergeOptions::new().strategy(strategy));
+
+    jqesque
+        .apply_to_with_options(&mut json_obj, &options)
+        .expect("Failed to apply");
+
+    assert_eq!(json_obj, expected);
+}
+
+/// Tests for parse_many and apply_all that should **succeed**.
+#[parameterized(
+    semicolons = { "foo.bar=1;baz[0]=true;-old.key", ';', json!({"foo": {"bar": 1}, "baz": [true], "old": {}}) },
+    newlines = { "foo.bar=1\nbaz[0]=true\n-old.key\n", ';', json!({"foo": {"bar": 1}, "baz": [true], "old": {}}) },
+    delimiter_in_value = { "x={\"a\":\";\"};y=\"a;b\"", ';', json!({"old": {"key": 1}, "x": {"a": ";"}, "y": "a;b"}) },
+    custom_delimiter = { "a=1,b=2", ',', json!({"old": {"key": 1}, "a": 1, "b": 2}) },
+    in_order = { ">a=1;~a=2;=a=3", ';', json!({"old": {"key": 1}, "a": 3}) },
+)]
+fn test_parse_many_ok(input: &str, delimiter: char, expected: serde_json::Value) {
+    let jqesques = Jqesque::parse_many_with_delimiter(input, Separator::Dot, delimiter)
+        .expect("Failed to parse input");
+    let mut json_obj = json!({"old": {"key": 1}});
+
+    Jqesque::apply_all(&jqesques, &mut json_obj).expect("Failed to apply");
+
+    assert_eq!(json_obj, expected);
+}
+
+/// Tests for parse_many and apply_all that should **fail**, with the index of the failing assignment.
+#[parameterized(
+    parse_error = { "a=1;[x]=2;c=3", 1 },
+    apply_error = { "a=1;b=2;=missing.key=3", 2 },
+    remove_missing = { "-missing", 0 },
+)]
+fn test_parse_many_err(input: &str, failing_index: usize) {
+    let mut json_obj = json!({"old": {"key": 1}});
+    let result = Jqesque::parse_many(input, Separator::Dot)
+        .and_then(|jqesques| Jqesque::apply_all(&jqesques, &mut json_obj));
+
+    match result {
+        Err(JqesqueError::BatchError { index, .. }) => assert_eq!(index, failing_index),
+        other => panic!("Expected a batch error, got {:?}", other),
+    }
+}
+
+/// Tests for formatting a Jqesque back into assignment syntax.
+#[parameterized(
+    insert = { ">foo.bar[0].baz=true", ">foo.bar[0].baz=true" },
+    auto = { "foo=1", "foo=1" },
+    string_value = { "=foo=hello", "=foo=\"hello\"" },
+    object_value = { "~a={\"b\": [1, null]}", "~a={\"b\":[1,null]}" },
+    remove = { "-foo[1]", "-foo[1]" },
+    quoted_key = { ">\"a.b\".\"say \\\"hi\\\"\"=1", ">\"a.b\".\"say \\\"hi\\\"\"=1" },
+    leading_dash_key = { "\"-foo\"=1", "\"-foo\"=1" },
+    nested_indices = { "+[0][1].x=null", "+[0][1].x=null" },
+    length_relative = { ">log[#]=1", ">log[#]=1" },
+    length_relative_offset = { "=log[#-1]=1", "=log[#-1]=1" },
+    typed = { ">count:int=5", ">count:int=5" },
+)]
+fn test_display_round_trip(input: &str, expected: &str) {
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+    let formatted = parsed.to_string();
+
+    assert_eq!(formatted, expected);
+    assert_eq!(
+        formatted.parse::<Jqesque>().expect("Failed to re-parse"),
+        parsed
+    );
+}
+
+#[test]
+fn test_display_negative_index() {
+    let parsed = "-array[-1]".parse::<Jqesque>().unwrap();
+
+    assert_eq!(parsed, "-array[#-1]".parse::<Jqesque>().unwrap());
+    assert_eq!(parsed.to_string(), "-array[#-1]");
+}
+
+#[test]
+fn test_display_from_segments() {
+    let jqesque = Jqesque::from_segments(["a b", "x\ty"], Some(json!(1)), Operation::Insert);
+    let formatted = jqesque.to_string();
+
+    assert_eq!(formatted, ">\"a b\".\"x\\u0009y\"=1");
+    assert_eq!(formatted.parse::<Jqesque>().unwrap(), jqesque);
+}
+
+/// Tests for to_json_patch that should **succeed**.
+#[parameterized(
+    add = { "+foo.bar=1", json!([{"op": "add", "path": "/foo/bar", "value": 1}]) },
+    append = { "+array[#]=4", json!([{"op": "add", "path": "/array/-", "value": 4}]) },
+    replace = { "=array[0]=true", json!([{"op": "replace", "path": "/array/0", "value": true}]) },
+    remove = { "-foo.bar", json!([{"op": "remove", "path": "/foo/bar"}]) },
+    test = { "?foo={\"a\": null}", json!([{"op": "test", "path": "/foo", "value": {"a": null}}]) },
+)]
+fn test_to_json_patch_ok(input: &str, expected: serde_json::Value) {
+    let jqesque = input.parse::<Jqesque>().expect("Failed to parse input");
+    let patch = jqesque.to_json_patch().expect("Failed to convert to patch");
+
+    assert_eq!(serde_json::to_value(patch).unwrap(), expected);
+}
+
+/// Tests for to_json_patch that should **fail**.
+#[parameterized(
+    insert = { ">foo=1", JqesqueError::UnsupportedOperationError(Operation::Insert) },
+    merge = { "~foo=1", JqesqueError::UnsupportedOperationError(Operation::Merge) },
+    auto = { "foo=1", JqesqueError::UnsupportedOperationError(Operation::Auto) },
+    length_relative = { "=array[#-1]=1", JqesqueError::InvalidPathError("length-relative index #-1 cannot be represented in a JSON Patch".to_string(), None) },
+)]
+fn test_to_json_patch_err(input: &str, expected: JqesqueError) {
+    let jqesque = input.parse::<Jqesque>().expect("Failed to parse input");
+
+    assert_eq!(jqesque.to_json_patch().unwrap_err(), expected);
+}
+
+#[test]
+fn test_to_json_patch_collected() {
+    let patch = json_patch::Patch(
+        Jqesque::parse_many("+a=1;?a=1;=b=2;-c", Separator::Dot)
+            .unwrap()
+            .iter()
+            .flat_map(|jqesque| jqesque.to_json_patch().unwrap().0)
+            .collect(),
+    );
+    let mut json_obj = json!({"b": 1, "c": 1});
+
+    json_patch::patch(&mut json_obj, &patch).expect("Failed to apply patch");
+
+    assert_eq!(json_obj, json!({"a": 1, "b": 2}));
+}
+
+/// Tests for from_patch_operation, round-tripping through to_json_patch.
+#[parameterized(
+    add = { json!({"op": "add", "path": "/foo/bar", "value": 1}), "+foo.bar=1" },
+    append = { json!({"op": "add", "path": "/array/-", "value": 4}), "+array.-=4" },
+    replace = { json!({"op": "replace", "path": "/array/0/x", "value": true}), "=array[0].x=true" },
+    remove = { json!({"op": "remove", "path": "/foo/1"}), "-foo[1]" },
+    test = { json!({"op": "test", "path": "/foo", "value": {"a": null}}), "?foo={\"a\": null}" },
+    leading_zero_is_key = { json!({"op": "remove", "path": "/foo/01"}), "-foo.01" },
+    copy = { json!({"op": "copy", "from": "/a/0", "path": "/b/-"}), "&b.-<a[0]" },
+    move_op = { json!({"op": "move", "from": "/a", "path": "/b/c"}), "@b.c<a" },
+)]
+fn test_from_patch_operation_ok(op: serde_json::Value, equivalent: &str) {
+    let op: json_patch::PatchOperation = serde_json::from_value(op).unwrap();
+    let jqesque = Jqesque::from_patch_operation(&op);
+
+    assert_eq!(
+        jqesque,
+        equivalent
+            .parse::<Jqesque>()
+            .expect("Failed to parse input")
+    );
+    assert_eq!(jqesque.to_json_patch().unwrap().0, vec![op]);
+}
+
+/// Tests for the append token `-` with the different operations.
+#[parameterized(
+    insert_slash = { ">log/-=c", Separator::Slash, json!({"log": ["a", "b", "c"]}) },
+    insert_dot = { ">log.-=c", Separator::Dot, json!({"log": ["a", "b", "c"]}) },
+    insert_bracket = { ">log[-]=c", Separator::Dot, json!({"log": ["a", "b", "c"]}) },
+    insert_new_array = { ">new/-=c", Separator::Slash, json!({"log": ["a", "b"], "new": ["c"]}) },
+    insert_nested = { ">log/-/x=c", Separator::Slash, json!({"log": ["a", "b", {"x": "c"}]}) },
+    merge = { "~log/-={\"x\": 1}", Separator::Slash, json!({"log": ["a", "b", {"x": 1}]}) },
+    add = { "+log/-=c", Separator::Slash, json!({"log": ["a", "b", "c"]}) },
+    auto = { "log/-=c", Separator::Slash, json!({"log": ["a", "b", "c"]}) },
+    quoted_key = { ">obj/\"-\"=c", Separator::Slash, json!({"log": ["a", "b"], "obj": {"-": "c"}}) },
+)]
+fn test_append_token(input: &str, separator: Separator, expected: serde_json::Value) {
+    let parsed = Jqesque::from_str_with_separator(input, separator).expect("Failed to parse input");
+    let mut json_obj = json!({"log": ["a", "b"]});
+
+    parsed.apply_to(&mut json_obj).expect("Failed to apply");
+
+    assert_eq!(json_obj, expected);
+}
+
+#[test]
+fn test_append_token_repeated() {
+    let parsed = Jqesque::from_str_with_separator(">log/-=entry", Separator::Slash).unwrap();
+    let mut json_obj = json!({});
+
+    for _ in 0..3 {
+        parsed.apply_to(&mut json_obj).unwrap();
+    }
+
+    assert_eq!(json_obj, json!({"log": ["entry", "entry", "entry"]}));
+}
+
+/// Tests for parsing values with and without type inference.
+#[parameterized(
+    // The number as serde_json parses it: `1.1`, or `1.10` verbatim with `arbitrary_precision`
+    inferred_number = { "version=1.10", true, serde_json::from_str("1.10").unwrap() },
+    literal_number = { "version=1.10", false, json!("1.10") },
+    literal_leading_zero = { "zip=01234", false, json!("01234") },
+    literal_bool = { "flag=true", false, json!("true") },
+    literal_quotes = { "name=\"bob\"", false, json!("\"bob\"") },
+    literal_object = { "obj={\"a\": 1}", false, json!("{\"a\": 1}") },
+    literal_space = { "phone= +47 555 01234", false, json!("+47 555 01234") },
+)]
+fn test_infer_types(input: &str, infer_types: bool, expected: serde_json::Value) {
+    let options = ParseOptions::new().infer_types(infer_types);
+    let parsed = Jqesque::from_str_with_options(input, Separator::Dot, options)
+        .expect("Failed to parse input");
+
+    assert_eq!(parsed.value, Some(expected));
+}
+
+/// Tests for parse_with_options.
+#[parameterized(
+    default = { ">foo.bar=1", ParseOptions::new(), json!({"foo": {"bar": 1}}) },
+    slash = { ">foo/bar=1", ParseOptions::new().separator(Separator::Slash), json!({"foo": {"bar": 1}}) },
+    slash_literal = { ">foo/bar=1", ParseOptions::new().separator(Separator::Slash).infer_types(false), json!({"foo": {"bar": "1"}}) },
+    custom = { ">foo|bar=1", ParseOptions::new().separator(Separator::Custom('|')), json!({"foo": {"bar": 1}}) },
+)]
+fn test_parse_with_options(input: &str, options: ParseOptions, expected: serde_json::Value) {
+    let parsed = Jqesque::parse_with_options(input, &options).expect("Failed to parse input");
+
+    assert_eq!(parsed.as_json(), expected);
+    if options == ParseOptions::new() {
+        assert_eq!(parsed, input.parse::<Jqesque>().unwrap());
+    }
+}
+
+/// Tests for multi-character separators.
+#[parameterized(
+    double_colon = { "a::b::c=1", "::", json!({"a": {"b": {"c": 1}}}) },
+    arrow = { "a->b->c=1", "->", json!({"a": {"b": {"c": 1}}}) },
+    arrow_with_dashes = { "my-key->other-key=1", "->", json!({"my-key": {"other-key": 1}}) },
+    arrow_with_index = { "a[0]->b=1", "->", json!({"a": [{"b": 1}]}) },
+    quoted = { "\"a::b\"::c=1", "::", json!({"a::b": {"c": 1}}) },
+    single_char = { "a|b=1", "|", json!({"a": {"b": 1}}) },
+    type_annotation = { "a::b:int=1", "::", json!({"a": {"b": 1}}) },
+)]
+fn test_multi_char_separator(input: &str, separator: &str, expected: serde_json::Value) {
+    let separator = Separator::CustomStr(separator.to_string());
+    let parsed = Jqesque::from_str_with_separator(input, separator).expect("Failed to parse input");
+    let mut json_obj = json!({});
+
+    parsed.apply_to(&mut json_obj).expect("Failed to apply");
+
+    assert_eq!(json_obj, expected);
+}
+
+#[test]
+fn test_path_with_separator() {
+    let (rest, tokens) = jqesque::parser::path_with_separator("a->b-c->[1]=x", "->").unwrap();
+
+    assert_eq!(rest, "=x");
+    assert_eq!(
+        tokens,
+        vec![
+            PathToken::Key("a".to_string()),
+            PathToken::Key("b-c".to_string()),
+            PathToken::Index(1),
+        ]
+    );
+}
+
+/// Tests for tokens_to_pointer and tokens_from_pointer.
+#[parameterized(
+    keys = { "a.b", "/a/b" },
+    index = { "a[0].b", "/a/0/b" },
+    escaped = { "\"a/b\".\"c~d\"", "/a~1b/c~0d" },
+    append = { "log.-", "/log/-" },
+    leading_index = { "[1][2]", "/1/2" },
+)]
+fn test_pointer_round_trip(path: &str, expected: &str) {
+    let jqesque = Jqesque::from_str_with_separator(&format!("-{}", path), Separator::Dot)
+        .expect("Failed to parse input");
+    let pointer = jqesque::tokens_to_pointer(&jqesque.tokens);
+
+    assert_eq!(pointer.as_str(), expected);
+    assert_eq!(jqesque.to_pointer(), pointer);
+    assert_eq!(jqesque::tokens_from_pointer(&pointer), jqesque.tokens);
+}
+
+/// Tests for JSON Patch operations on keys that need escaping in a JSON Pointer.
+#[parameterized(
+    replace = { "=\"a/b\"=2", json!({"a/b": 2, "c~d": 1}) },
+    remove = { "-\"c~d\"", json!({"a/b": 1}) },
+    test = { "?\"a/b\"=1", json!({"a/b": 1, "c~d": 1}) },
+)]
+fn test_escaped_keys(input: &str, expected: serde_json::Value) {
+    let mut json_obj = json!({"a/b": 1, "c~d": 1});
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+
+    parsed.apply_to(&mut json_obj).expect("Failed to apply");
+
+    assert_eq!(json_obj, expected);
+}
+
+/// Tests for the location reported by parse errors.
+#[parameterized(
+    double_separator = { "key..subkey=value", 4, ".subkey=value" },
+    trailing_separator = { "key.=value", 4, "=value" },
+    missing_equals = { "foo.bar", 7, "" },
+    unicode_column = { "nøkkel..x=1", 8, ".x=1" },
+)]
+fn test_parse_error_location(input: &str, expected_offset: usize, rest: &str) {
+    match input.parse::<Jqesque>() {
+        Err(JqesqueError::ParseError { offset, column, .. }) => {
+            assert_eq!(offset, expected_offset);
+            assert_eq!(&input[offset..], rest);
+            assert_eq!(column, input[..offset].chars().count() + 1);
+        }
+        other => panic!("Expected a parse error, got {:?}", other),
+    }
+}
+
+/// Tests for merging arrays with the different array strategies.
+#[parameterized(
+    by_index = { "~tags=[\"c\"]", ArrayMergeStrategy::ByIndex, json!({"tags": ["c", "b"], "items": [{"a": 1}, {"b": 2}]}) },
+    concat = { "~tags=[\"c\"]", ArrayMergeStrategy::Concat, json!({"tags": ["a", "b", "c"], "items": [{"a": 1}, {"b": 2}]}) },
+    replace = { "~tags=[\"c\"]", ArrayMergeStrategy::Replace, json!({"tags": ["c"], "items": [{"a": 1}, {"b": 2}]}) },
+    union = { "~tags=[\"b\", \"c\", \"c\"]", ArrayMergeStrategy::Union, json!({"tags": ["a", "b", "c"], "items": [{"a": 1}, {"b": 2}]}) },
+    by_index_objects = { "~items=[{\"c\": 3}]", ArrayMergeStrategy::ByIndex, json!({"tags": ["a", "b"], "items": [{"a": 1, "c": 3}, {"b": 2}]}) },
+    union_objects = { "~items=[{\"b\": 2}, {\"c\": 3}]", ArrayMergeStrategy::Union, json!({"tags": ["a", "b"], "items": [{"a": 1}, {"b": 2}, {"c": 3}]}) },
+)]
+fn test_array_merge_strategy(input: &str, arrays: ArrayMergeStrategy, expected: serde_json::Value) {
+    let mut json_obj = json!({"tags": ["a", "b"], "items": [{"a": 1}, {"b": 2}]});
+    let jqesque = input.parse::<Jqesque>().expect("Failed to parse input");
+    let options = ApplyOptions::new().merge(MergeOptions::new().arrays(arrays));
+
+    jqesque
+        .apply_to_with_options(&mut json_obj, &options)
+        .expect("Failed to apply");
+
+    assert_eq!(json_obj, expected);
+}
+
+/// Tests for apply_to_with_report.
+#[parameterized(
+    auto_replace = { "key=new", Operation::Replace, "/key", Some(json!("old")) },
+    auto_add = { "other=1", Operation::Add, "/other", None },
+    auto_insert = { "a.b.c=1", Operation::Insert, "/a/b/c", None },
+    remove = { "-array[1]", Operation::Remove, "/array/1", Some(json!(2)) },
+    remove_last = { "-array[-1]", Operation::Remove, "/array/2", Some(json!(3)) },
+    append = { ">array.-=4", Operation::Insert, "/array/3", None },
+    merge = { "~key=new", Operation::Merge, "/key", Some(json!("old")) },
+    test = { "?key=old", Operation::Test, "/key", Some(json!("old")) },
+)]
+fn test_apply_to_with_report(
+    input: &str,
+    operation: Operation,
+    pointer: &str,
+    previous: Option<serde_json::Value>,
+) {
+    let mut json_obj = json!({"key": "old", "array": [1, 2, 3]});
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+
+    let report = parsed
+        .apply_to_with_report(&mut json_obj)
+        .expect("Failed to apply");
+
+    assert_eq!(report.operation, operation);
+    assert_eq!(report.pointer, pointer);
+    assert_eq!(report.previous, previous);
+}
+
+#[test]
+fn test_apply_to_with_report_failure_leaves_document() {
+    let mut json_obj = json!({"key": "old"});
+    let parsed = "?key=new".parse::<Jqesque>().unwrap();
+
+    assert!(parsed.apply_to_with_report(&mut json_obj).is_err());
+    assert_eq!(json_obj, json!({"key": "old"}));
+}
+
+#[test]
+fn test_apply_to_with_report_wildcard() {
+    let mut json_obj = json!({"a": [{"b": 1}]});
+    let parsed = "-a.*.b".parse::<Jqesque>().unwrap();
+
+    assert!(matches!(
+        parsed.apply_to_with_report(&mut json_obj),
+        Err(JqesqueError::WildcardError(Operation::Remove))
+    ));
+    assert_eq!(json_obj, json!({"a": [{"b": 1}]}));
+}
+
+/// Tests for preview, which must leave the original document untouched.
+#[parameterized(
+    replace = { "key=new", Ok(json!({"key": "new", "array": [1, 2]})) },
+    remove = { "-array[0]", Ok(json!({"key": "old", "array": [2]})) },
+    test_passed = { "?key=old", Ok(json!({"key": "old", "array": [1, 2]})) },
+    test_failed = { "?key=new", Err(JqesqueError::TestFailedError { expected: json!("new"), actual: json!("old") }) },
+)]
+fn test_preview(input: &str, expected: Result<serde_json::Value, JqesqueError>) {
+    let json_obj = json!({"key": "old", "array": [1, 2]});
+    let parsed = input.parse::<Jqesque>().expect("Failed to parse input");
+
+    assert_eq!(parsed.preview(&json_obj), expected);
+    assert_eq!(json_obj, json!({"key": "old", "array": [1, 2]}));
+}
+
+/// Tests for keys quoted with single quotes.
+#[parameterized(
+    quoted_key = { ">'complex.key'=123", Separator::Dot, json!({"complex.key": 123}) },
+    key_with_spaces = { ">'key with spaces'=value", Separator::Dot, json!({"key with spaces": "value"}) },
+    special_chars_in_key = { ">'key!@#$%^&*()'=value", Separator::Dot, json!({"key!@#$%^&*()": "value"}) },
+    escaped_single_quote = { ">'it\\'s'=1", Separator::Dot, json!({"it's": 1}) },
+    unescaped_double_quote = { ">'say \"hi\"'=1", Separator::Dot, json!({"say \"hi\"": 1}) },
+    nested = { ">'a.b'.c=1", Separator::Dot, json!({"a.b": {"c": 1}}) },
+    slash_separator = { ">'a/b'/c=1", Separator::Slash, json!({"a/b": {"c": 1}}) },
+    with_index = { ">'a.b'[0]=1", Separator::Dot, json!({"a.b": [1]}) },
+)]
+fn test_single_quoted_key(input: &str, separator: Separator, expected: serde_json::Value) {
+    let jqesque = Jqesque::from_str_with_separator(input, separator).unwrap();
+    assert_eq!(jqesque.as_json(), expected);
+}
+
+#[parameterized(
+    unterminated = { ">'key=1" },
+    mismatched_quotes = { ">'key\"=1" },
+)]
+fn test_single_quoted_key_err(input: &str) {
+    assert!(Jqesque::from_str_with_separator(input, Separator::Dot).is_err());
+}
+
+/// Tests for separators escaped with a backslash inside unquoted keys.
+#[parameterized(
+    escaped_dot = { "a\\.b.c=1", Separator::Dot, vec![PathToken::Key("a.b".to_string()), PathToken::Key("c".to_string())] },
+    escaped_slash = { "a\\/b/c=1", Separator::Slash, vec![PathToken::Key("a/b".to_string()), PathToken::Key("c".to_string())] },
+    escaped_backslash = { "a\\\\b.c=1", Separator::Dot, vec![PathToken::Key("a\\b".to_string()), PathToken::Key("c".to_string())] },
+    trailing_escape = { "a.b\\.=1", Separator::Dot, vec![PathToken::Key("a".to_string()), PathToken::Key("b.".to_string())] },
+    leading_escape = { "\\.a=1", Separator::Dot, vec![PathToken::Key(".a".to_string())] },
+    before_index = { "a\\.b[0]=1", Separator::Dot, vec![PathToken::Key("a.b".to_string()), PathToken::Index(0)] },
+    multi_char = { "a\\::b::c=1", Separator::CustomStr("::".to_string()), vec![PathToken::Key("a::b".to_string()), PathToken::Key("c".to_string())] },
+)]
+fn test_escaped_separator(input: &str, separator: Separator, expected: Vec<PathToken>) {
+    let jqesque = Jqesque::from_str_with_separator(input, separator).unwrap();
+    assert_eq!(jqesque.tokens, expected);
+}
+
+#[test]
+fn test_escaped_separator_apply() {
+    let mut json_obj = json!({});
+    let jqesque = Jqesque::from_str_with_separator("a\\.b.c=1", Separator::Dot).unwrap();
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, json!({"a.b": {"c": 1}}));
+}
+
+/// Tests for quoted keys inside brackets.
+#[parameterized(
+    dotted_key = { ">a[\"x.y\"][0]=true", Separator::Dot, json!({"a": {"x.y": [true]}}) },
+    nested = { ">foo[\"bar.baz\"].qux=1", Separator::Dot, json!({"foo": {"bar.baz": {"qux": 1}}}) },
+    single_quoted = { ">foo['bar.baz']=1", Separator::Dot, json!({"foo": {"bar.baz": 1}}) },
+    leading = { ">[\"a.b\"].c=1", Separator::Dot, json!({"a.b": {"c": 1}}) },
+    chained = { ">a[\"b\"][\"c\"]=1", Separator::Dot, json!({"a": {"b": {"c": 1}}}) },
+    index_then_key = { ">a[0][\"b\"]=1", Separator::Dot, json!({"a": [{"b": 1}]}) },
+    numeric_key = { ">a[\"0\"]=1", Separator::Dot, json!({"a": {"0": 1}}) },
+    slash_separator = { ">a[\"b/c\"]/d=1", Separator::Slash, json!({"a": {"b/c": {"d": 1}}}) },
+)]
+fn test_bracketed_key(input: &str, separator: Separator, expected: serde_json::Value) {
+    let jqesque = Jqesque::from_str_with_separator(input, separator).unwrap();
+    assert_eq!(jqesque.as_json(), expected);
+}
+
+#[parameterized(
+    unterminated_quote = { ">a[\"b]=1" },
+    unquoted_key = { ">a[b]=1" },
+    unclosed_bracket = { ">a[\"b\"=1" },
+)]
+fn test_bracketed_key_err(input: &str) {
+    assert!(Jqesque::from_str_with_separator(input, Separator::Dot).is_err());
+}
+
+/// Tests for flatten, checking the emitted assignments and that they rebuild the document.
+#[parameterized(
+    flat = { json!({"a": 1, "b": "x"}), vec![">a=1", ">b=\"x\""] },
+    nested = { json!({"foo": {"bar": [1, {"baz": true}]}}), vec![">foo.bar[0]=1", ">foo.bar[1].baz=true"] },
+    empty_containers = { json!({"obj": {}, "arr": [], "n": null}), vec![">arr=[]", ">n=null", ">obj={}"] },
+    nested_arrays = { json!({"m": [[1], []]}), vec![">m[0][0]=1", ">m[1]=[]"] },
+    quoted_key = { json!({"a.b": {"c": 1}}), vec![">\"a.b\".c=1"] },
+    empty = { json!({}), vec![] },
+)]
+fn test_flatten(document: serde_json::Value, expected: Vec<&str>) {
+    let jqesques = Jqesque::flatten(&document, Separator::Dot);
+    let rendered: Vec<String> = jqesques.iter().map(|j| j.to_string()).collect();
+    assert_eq!(rendered, expected);
+
+    let mut json_obj = json!({});
+    Jqesque::apply_all(&jqesques, &mut json_obj).expect("Failed to apply");
+    assert_eq!(json_obj, document);
+}
+
+#[test]
+fn test_flatten_separator() {
+    let document = json!({"a": {"b.c": [1]}, "d/e": 2});
+    let jqesques = Jqesque::flatten(&document, Separator::Slash);
+    let rendered: Vec<String> = jqesques.iter().map(|j| j.to_string()).collect();
+    assert_eq!(rendered, vec![">a/\"b.c\"[0]=1", ">\"d/e\"=2"]);
+    assert!(jqesques.iter().all(|j| j.separator() == &Separator::Slash));
+
+    let input = rendered.join("\n");
+    let jqesques = Jqesque::parse_many(&input, Separator::Slash).expect("Failed to parse");
+    let mut json_obj = json!({});
+    Jqesque::apply_all(&jqesques, &mut json_obj).expect("Failed to apply");
+    assert_eq!(json_obj, document);
+}
+
+#[test]
+fn test_flatten_round_trip_through_parse_many() {
+    let document = json!({"server": {"host": "example.com", "ports": [80, 443]}, "debug": false});
+    let input = Jqesque::flatten(&document, Separator::Dot)
+        .iter()
+        .map(|j| j.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let jqesques = Jqesque::parse_many(&input, Separator::Dot).expect("Failed to parse");
+    let mut json_obj = json!({});
+    Jqesque::apply_all(&jqesques, &mut json_obj).expect("Failed to apply");
+    assert_eq!(json_obj, document);
+}
+
+/// Tests for path_string, rendering the path back with a given separator.
+#[parameterized(
+    round_trip = { ">foo.bar[0].baz=1", Separator::Dot, Separator::Dot, "foo.bar[0].baz" },
+    to_slash = { ">foo.bar[0].baz=1", Separator::Dot, Separator::Slash, "foo/bar[0]/baz" },
+    from_slash = { ">foo/bar[0]/baz=1", Separator::Slash, Separator::Dot, "foo.bar[0].baz" },
+    quoted_separator = { ">\"a.b\".c=1", Separator::Dot, Separator::Dot, "\"a.b\".c" },
+    unquoted_for_slash = { ">\"a.b\".c=1", Separator::Dot, Separator::Slash, "\"a.b\"/c" },
+    custom_separator_in_key = { ">a_b.c=1", Separator::Dot, Separator::Custom('_'), "\"a_b\"_c" },
+    multi_char = { ">a.b-c=1", Separator::Dot, Separator::CustomStr("::".to_string()), "a::b-c" },
+    leading_index = { ">[0][1].x=1", Separator::Dot, Separator::Dot, "[0][1].x" },
+    relative = { ">log[#-1]=1", Separator::Dot, Separator::Dot, "log[#-1]" },
+    leading_dash = { "\"-foo\".bar=1", Separator::Dot, Separator::Dot, "-foo.bar" },
+)]
+fn test_path_string(input: &str, parse_separator: Separator, separator: Separator, expected: &str) {
+    let jqesque = Jqesque::from_str_with_separator(input, parse_separator).unwrap();
+    let path = jqesque.path_string(separator.clone());
+    assert_eq!(path, expected);
+
+    let reparsed = Jqesque::from_str_with_separator(&format!(">{}=1", path), separator).unwrap();
+    assert_eq!(reparsed.tokens, jqesque.tokens);
+}
+
+/// Tests for JqesqueBuilder that should **succeed**, comparing against the parsed equivalent.
+#[parameterized(
+    insert = { JqesqueBuilder::new().key("foo").index(0).key("bar").value(1).operation(Operation::Insert), ">foo[0].bar=1" },
+    default_operation = { JqesqueBuilder::new().key("foo").value("bar"), "foo=bar" },
+    dotted_key = { JqesqueBuilder::new().key("a.b").key("c").value(true).operation(Operation::Add), "+\"a.b\".c=true" },
+    numeric_key = { JqesqueBuilder::new().key("0").value(json!({"x": null})).operation(Operation::Merge), "~\"0\"={\"x\":null}" },
+    remove_without_value = { JqesqueBuilder::new().key("foo").key("bar").operation(Operation::Remove), "-foo.bar" },
+    copy = { JqesqueBuilder::new().key("b").from(vec![PathToken::Key("a".to_string()), PathToken::Index(0)]).operation(Operation::Copy), "&b<a[0]" },
+    move_ignores_value = { JqesqueBuilder::new().key("b").value(1).from(vec![PathToken::Key("a".to_string())]).operation(Operation::Move), "@b<a" },
+)]
+fn test_builder_ok(builder: JqesqueBuilder, expected: &str) {
+    let jqesque = builder.build().expect("Failed to build");
+    assert_eq!(
+        jqesque,
+        Jqesque::from_str_with_separator(expected, Separator::Dot).unwrap()
+    );
+}
+
+/// Tests for JqesqueBuilder that should **fail**, for operations that need a value.
+#[parameterized(
+    auto = { None, Operation::Auto },
+    insert = { Some(Operation::Insert), Operation::Insert },
+    test = { Some(Operation::Test), Operation::Test },
+)]
+fn test_builder_missing_value(operation: Option<Operation>, expected: Operation) {
+    let mut builder = JqesqueBuilder::new().key("foo");
+    if let Some(operation) = operation {
+        builder = builder.operation(operation);
+    }
+    match builder.build() {
+        Err(JqesqueError::MissingValueError(op)) => assert_eq!(op, expected),
+        other => panic!("Expected MissingValueError, got {:?}", other),
+    }
+}
+
+/// Tests for JqesqueBuilder that should **fail**, for operations that need a path to take the
+/// value from.
+#[parameterized(
+    copy = { Operation::Copy, None },
+    move_op = { Operation::Move, None },
+    copy_with_value = { Operation::Copy, Some(json!(1)) },
+)]
+fn test_builder_missing_from(operation: Operation, value: Option<serde_json::Value>) {
+    let mut builder = JqesqueBuilder::new()
+        .key("foo")
+        .operation(operation.clone());
+    if let Some(value) = value {
+        builder = builder.value(value);
+    }
+    match builder.build() {
+        Err(JqesqueError::MissingFromError(op)) => assert_eq!(op, operation),
+        other => panic!("Expected MissingFromError, got {:?}", other),
+    }
+}
+
+/// Tests for whitespace around the input, the separator and the `=`.
+#[parameterized(
+    padded = { " a . b = c ", Separator::Dot, json!({"a": {"b": "c"}}) },
+    leading_and_trailing = { "\t>a.b=c\n", Separator::Dot, json!({"a": {"b": "c"}}) },
+    around_equals = { ">a  =  1", Separator::Dot, json!({"a": 1}) },
+    slash = { ">a / b / c=1", Separator::Slash, json!({"a": {"b": {"c": 1}}}) },
+    multi_char = { ">a :: b=1", Separator::CustomStr("::".to_string()), json!({"a": {"b": 1}}) },
+    after_index = { ">a[0] . b=1", Separator::Dot, json!({"a": [{"b": 1}]}) },
+    type_annotation = { ">a:int = 1", Separator::Dot, json!({"a": 1}) },
+    quoted_key_kept = { ">\" a \" . b = 1", Separator::Dot, json!({" a ": {"b": 1}}) },
+    inner_value_spaces_kept = { ">a = hello  world ", Separator::Dot, json!({"a": "hello  world"}) },
+    quoted_value_kept = { ">a = \" padded \" ", Separator::Dot, json!({"a": " padded "}) },
+)]
+fn test_whitespace(input: &str, separator: Separator, expected: serde_json::Value) {
+    let mut json_obj = json!({});
+    let jqesque = Jqesque::from_str_with_separator(input, separator).unwrap();
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, expected);
+}
+
+#[test]
+fn test_whitespace_remove() {
+    let mut json_obj = json!({"a": {"b": 1, "c": 2}});
+    let jqesque = Jqesque::from_str_with_separator(" -a . b ", Separator::Dot).unwrap();
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, json!({"a": {"c": 2}}));
+}
+
+/// Tests for heredoc values, which are kept verbatim as strings.
+#[parameterized(
+    multiline = { ">cert=<<EOF\n-----BEGIN-----\nabc=\n-----END-----\nEOF", "-----BEGIN-----\nabc=\n-----END-----" },
+    single_line = { ">a=<<END\n42\nEND", "42" },
+    empty = { ">a=<<EOF\nEOF", "" },
+    blank_lines = { ">a=<<EOF\n\nx\n\nEOF", "\nx\n" },
+    crlf = { ">a=<<EOF\r\nx\r\ny\r\nEOF", "x\r\ny" },
+    json_not_inferred = { ">a=<<EOF\n{\"b\": 1}\nEOF", "{\"b\": 1}" },
+    terminator_inside = { ">a=<<EOF\nEOF is not alone\nEOF", "EOF is not alone" },
+    trailing_newline = { ">a = <<EOF\nx\nEOF\n", "x" },
+    not_a_heredoc = { ">a=<<EOF", "<<EOF" },
+)]
+fn test_heredoc(input: &str, expected: &str) {
+    let jqesque = Jqesque::from_str_with_separator(input, Separator::Dot).unwrap();
+    assert_eq!(jqesque.value, Some(json!(expected)));
+}
+
+#[test]
+fn test_heredoc_apply() {
+    let mut json_obj = json!({"tls": {}});
+    let input = ">tls.cert=<<PEM\nline one\nline=two\nPEM";
+    let jqesque = Jqesque::from_str_with_separator(input, Separator::Dot).unwrap();
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, json!({"tls": {"cert": "line one\nline=two"}}));
+}
+
+/// Tests for apply_all_atomic that should **succeed**, with the operation each assignment performed.
+#[parameterized(
+    single = { ">a=1", vec![Operation::Insert], json!({"a": 1}) },
+    auto_resolved = { "a=1;a=2;-a;b.c=3", vec![Operation::Add, Operation::Replace, Operation::Remove, Operation::Insert], json!({"b": {"c": 3}}) },
+    empty = { "", vec![], json!({}) },
+)]
+fn test_apply_all_atomic_ok(
+    input: &str,
+    expected_operations: Vec<Operation>,
+    expected: serde_json::Value,
+) {
+    let jqesques = Jqesque::parse_many(input, Separator::Dot).expect("Failed to parse");
+    let mut json_obj = json!({});
+    let operations = Jqesque::apply_all_atomic(&jqesques, &mut json_obj).expect("Failed to apply");
+    assert_eq!(operations, expected_operations);
+    assert_eq!(json_obj, expected);
+}
+
+/// Tests for apply_all_atomic that should **fail**, leaving the document untouched.
+#[parameterized(
+    first = { "=missing=1;>a=2", 0 },
+    midway = { ">a=2;+b=3;?a=3;>c=4", 2 },
+    last = { ">a=2;-missing", 1 },
+)]
+fn test_apply_all_atomic_err(input: &str, failing_index: usize) {
+    let jqesques = Jqesque::parse_many(input, Separator::Dot).expect("Failed to parse");
+    let original = json!({"a": 1});
+    let mut json_obj = original.clone();
+    match Jqesque::apply_all_atomic(&jqesques, &mut json_obj) {
+        Err(JqesqueError::BatchError { index, .. }) => assert_eq!(index, failing_index),
+        other => panic!("Expected BatchError, got {:?}", other),
+    }
+    assert_eq!(json_obj, original);
+}
+
+/// Tests for path_exists, which ignores the value.
+#[parameterized(
+    existing_key = { "?a.b=ignored", true },
+    null_value = { "?a.n=1", true },
+    missing_key = { "?a.missing=1", false },
+    missing_parent = { "-x.y", false },
+    existing_index = { "?arr[1]=1", true },
+    index_out_of_bounds = { "?arr[2]=1", false },
+    last_element = { "?arr[-1]=1", true },
+    past_the_end = { "?arr[#]=1", false },
+    append = { "?arr.-=1", false },
+    relative_on_object = { "?a[-1]=1", false },
+    dotted_key = { "?\"c.d\"=1", true },
+)]
+fn test_path_exists(input: &str, expected: bool) {
+    let json_obj = json!({"a": {"b": 1, "n": null}, "arr": [1, 2], "c.d": 0});
+    let jqesque = Jqesque::from_str_with_separator(input, Separator::Dot).unwrap();
+    assert_eq!(jqesque.path_exists(&json_obj), expected);
+}
+
+/// Tests for a custom order of operations tried by `Auto`.
+#[parameterized(
+    default_replaces = { "a.b=2", None, ApplyOutcome::Applied(Operation::Replace), json!({"a": {"b": 2}, "arr": [1]}) },
+    insert_only = { "x.y=2", Some(vec![Operation::Insert]), ApplyOutcome::Applied(Operation::Insert), json!({"a": {"b": 1}, "arr": [1], "x": {"y": 2}}) },
+    add_before_replace = { "arr[0]=0", Some(vec![Operation::Add, Operation::Replace]), ApplyOutcome::Inserted { index: 0 }, json!({"a": {"b": 1}, "arr": [0, 1]}) },
+    merge_first = { "a={\"c\":3}", Some(vec![Operation::Merge, Operation::Insert]), ApplyOutcome::Applied(Operation::Merge), json!({"a": {"b": 1, "c": 3}, "arr": [1]}) },
+    falls_through = { "x.y=2", Some(vec![Operation::Replace, Operation::Add, Operation::Merge]), ApplyOutcome::Applied(Operation::Merge), json!({"a": {"b": 1}, "arr": [1], "x": {"y": 2}}) },
+)]
+fn test_auto_order_ok(
+    input: &str,
+    order: Option<Vec<Operation>>,
+    expected_outcome: ApplyOutcome,
+    expected: serde_json::Value,
+) {
+    let mut options = ApplyOptions::new();
+    if let Some(order) = order {
+        options = options.auto_order(order);
+    }
+    let mut json_obj = json!({"a": {"b": 1}, "arr": [1]});
+    let jqesque = Jqesque::from_str_with_separator(input, Separator::Dot).unwrap();
+    let outcome = jqesque
+        .apply_to_with_options(&mut json_obj, &options)
+        .unwrap();
+    assert_eq!(outcome, expected_outcome);
+    assert_eq!(json_obj, expected);
+}
+
+/// Tests for `Auto` orders that should **fail**.
+#[parameterized(
+    empty = { vec![] },
+    test = { vec![Operation::Replace, Operation::Test] },
+    remove = { vec![Operation::Remove] },
+    nested_auto = { vec![Operation::Auto] },
+)]
+fn test_auto_order_invalid(order: Vec<Operation>) {
+    let options = ApplyOptions::new().auto_order(order);
+    let mut json_obj = json!({"a": 1});
+    let jqesque = Jqesque::from_str_with_separator("a=2", Separator::Dot).unwrap();
+    let result = jqesque.apply_to_with_options(&mut json_obj, &options);
+    assert!(matches!(
+        result,
+        Err(JqesqueError::InvalidAutoOrderError(_))
+    ));
+    assert_eq!(json_obj, json!({"a": 1}));
+}
+
+#[test]
+fn test_auto_order_last_error() {
+    let options = ApplyOptions::new().auto_order([Operation::Replace]);
+    let mut json_obj = json!({});
+    let jqesque = Jqesque::from_str_with_separator("a=2", Separator::Dot).unwrap();
+    let result = jqesque.apply_to_with_options(&mut json_obj, &options);
+    assert!(matches!(result, Err(JqesqueError::PatchError { .. })));
+}
+
+/// Tests for the `Copy` operation that should **succeed**.
+#[parameterized(
+    key = { "&b<a", Separator::Dot, json!({"a": {"x": 1}, "b": {"x": 1}, "arr": [1, 2]}) },
+    nested = { "&a.y<a.x", Separator::Dot, json!({"a": {"x": 1, "y": 1}, "arr": [1, 2]}) },
+    overwrite = { "&a<arr", Separator::Dot, json!({"a": [1, 2], "arr": [1, 2]}) },
+    into_array = { "&arr[0]<a.x", Separator::Dot, json!({"a": {"x": 1}, "arr": [1, 1, 2]}) },
+    append = { "&arr.-<a.x", Separator::Dot, json!({"a": {"x": 1}, "arr": [1, 2, 1]}) },
+    from_last = { "&last<arr[-1]", Separator::Dot, json!({"a": {"x": 1}, "arr": [1, 2], "last": 2}) },
+    slash = { "&a/y<a/x", Separator::Slash, json!({"a": {"x": 1, "y": 1}, "arr": [1, 2]}) },
+    padded = { "&b < a", Separator::Dot, json!({"a": {"x": 1}, "b": {"x": 1}, "arr": [1, 2]}) },
+)]
+fn test_copy_ok(input: &str, separator: Separator, expected: serde_json::Value) {
+    let mut json_obj = json!({"a": {"x": 1}, "arr": [1, 2]});
+    let jqesque = Jqesque::from_str_with_separator(input, separator).unwrap();
+    assert_eq!(jqesque.operation, Operation::Copy);
+    assert_eq!(jqesque.value, None);
+
+    let operation = jqesque.apply_to(&mut json_obj).expect("Failed to apply");
+    assert_eq!(operation, Operation::Copy);
+    assert_eq!(json_obj, expected);
+}
+
+/// Tests for the `Copy` operation that should **fail** when applied.
+#[parameterized(
+    missing_source = { "&b<missing" },
+    missing_destination_parent = { "&x.y<a" },
+    source_out_of_bounds = { "&b<arr[5]" },
+)]
+fn test_copy_err(input: &str) {
+    let original = json!({"a": {"x": 1}, "arr": [1, 2]});
+    let mut json_obj = original.clone();
+    let jqesque = Jqesque::from_str_with_separator(input, Separator::Dot).unwrap();
+    assert!(matches!(
+        jqesque.apply_to(&mut json_obj),
+        Err(JqesqueError::PatchError { .. })
+    ));
+    assert_eq!(json_obj, original);
+}
+
+/// Tests for `Copy` syntax that should **fail** to parse.
+#[parameterized(
+    missing_source = { "&b" },
+    empty_source = { "&b<" },
+    with_value = { "&b<a=1" },
+)]
+fn test_copy_parse_err(input: &str) {
+    assert!(Jqesque::from_str_with_separator(input, Separator::Dot).is_err());
+}
+
+#[test]
+fn test_copy_round_trip() {
+    let jqesque = Jqesque::from_str_with_separator("&\"b.c\"[0]<a.x", Separator::Dot).unwrap();
+    assert_eq!(jqesque.to_string(), "&\"b.c\"[0]<a.x");
+    assert_eq!(
+        Jqesque::from_json_spec(&jqesque.to_json_spec()).unwrap(),
+        jqesque
+    );
+    assert_eq!(
+        jqesque.to_json_spec(),
+        json!({"op": "copy", "path": ["b.c", 0], "from": ["a", "x"]})
+    );
+}
+
+/// Tests for the `Move` operation that should **succeed**.
+#[parameterized(
+    rename = { "@b<a", Separator::Dot, json!({"b": {"x": 1}, "arr": [1, 2]}) },
+    into_sibling = { "@arr[0]<a.x", Separator::Dot, json!({"a": {}, "arr": [1, 1, 2]}) },
+    out_of_array = { "@first<arr[0]", Separator::Dot, json!({"a": {"x": 1}, "arr": [2], "first": 1}) },
+    to_end = { "@arr.-<arr[0]", Separator::Dot, json!({"a": {"x": 1}, "arr": [2, 1]}) },
+    overwrite = { "@a<arr", Separator::Dot, json!({"a": [1, 2]}) },
+    onto_itself = { "@a<a", Separator::Dot, json!({"a": {"x": 1}, "arr": [1, 2]}) },
+    slash = { "@a/y<a/x", Separator::Slash, json!({"a": {"y": 1}, "arr": [1, 2]}) },
+)]
+fn test_move_ok(input: &str, separator: Separator, expected: serde_json::Value) {
+    let mut json_obj = json!({"a": {"x": 1}, "arr": [1, 2]});
+    let jqesque = Jqesque::from_str_with_separator(input, separator).unwrap();
+    assert_eq!(jqesque.value, None);
+
+    let operation = jqesque.apply_to(&mut json_obj).expect("Failed to apply");
+    assert_eq!(operation, Operation::Move);
+    assert_eq!(json_obj, expected);
+}
+
+/// Tests for the `Move` operation that should **fail** when applied.
+#[parameterized(
+    missing_source = { "@b<missing" },
+    missing_destination_parent = { "@x.y<a" },
+    into_descendant = { "@a.x.deeper<a" },
+    into_own_child = { "@a.y<a" },
+)]
+fn test_move_err(input: &str) {
+    let original = json!({"a": {"x": 1}, "arr": [1, 2]});
+    let mut json_obj = original.clone();
+    let jqesque = Jqesque::from_str_with_separator(input, Separator::Dot).unwrap();
+    assert!(matches!(
+        jqesque.apply_to(&mut json_obj),
+        Err(JqesqueError::PatchError { .. })
+    ));
+    assert_eq!(json_obj, original);
+}
+
+/// Tests for `Move`s that fail once the value has been taken out, which must put it back.
+#[parameterized(
+    array_element = { "@x.y<arr[0]" },
+    relative_destination = { "@x.y[#]<arr[0]" },
+    relative_into_missing_array = { "@x[#-1]<a" },
+)]
+fn test_move_err_restores_source(input: &str) {
+    let original = json!({"a": {"x": 1}, "arr": [1, 2]});
+    let mut json_obj = original.clone();
+    let jqesque = Jqesque::from_str_with_separator(input, Separator::Dot).unwrap();
+    assert!(jqesque.apply_to(&mut json_obj).is_err());
+    assert_eq!(json_obj, original);
+}
+
+#[test]
+fn test_move_round_trip() {
+    let jqesque = Jqesque::from_str_with_separator("@new.path<old.path", Separator::Dot).unwrap();
+    assert_eq!(jqesque.operation, Operation::Move);
+    assert_eq!(jqesque.to_string(), "@new.path<old.path");
+    assert!("@new.path=1".parse::<Jqesque>().is_err());
+    assert_eq!(
+        Jqesque::from_json_spec(&jqesque.to_json_spec()).unwrap(),
+        jqesque
+    );
+}
+
+#[test]
+fn test_file_value_not_allowed() {
+    let jqesque = Jqesque::from_str_with_separator(">a=@value.json", Separator::Dot).unwrap();
+    assert_eq!(jqesque.value, Some(json!("@value.json")));
+    let jqesque = "email=@handle".parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.value, Some(json!("@handle")));
+}
+
+#[cfg(not(feature = "files"))]
+#[test]
+fn test_file_value_without_feature() {
+    let options = ParseOptions::new().allow_files(true);
+    let jqesque = Jqesque::parse_with_options(">a=@value.json", &options).unwrap();
+    assert_eq!(jqesque.value, Some(json!("@value.json")));
+}
+
+/// Writes `contents` to a file unique to the test, returning its path.
+#[cfg(feature = "files")]
+fn write_value_file(name: &str, contents: &str) -> String {
+    let path = std::env::temp_dir().join(format!("jqesque-{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+/// Tests for `@path` file values that should **succeed**.
+#[cfg(feature = "files")]
+#[parameterized(
+    object = { "object.json", "{\"b\": [1, 2], \"c\": \"=x\"}", true, json!({"b": [1, 2], "c": "=x"}) },
+    number = { "number.json", "42\n", true, json!(42) },
+    multiline_string = { "string.json", "\"line one\\nline two\"", true, json!("line one\nline two") },
+    verbatim = { "verbatim.txt", "not = json\n", false, json!("not = json\n") },
+)]
+fn test_file_value_ok(name: &str, contents: &str, infer_types: bool, expected: serde_json::Value) {
+    let path = write_value_file(name, contents);
+    let options = ParseOptions::new()
+        .infer_types(infer_types)
+        .allow_files(true);
+    let jqesque = Jqesque::parse_with_options(&format!(">a = @{}", path), &options).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(jqesque.value, Some(expected));
+}
+
+#[cfg(feature = "files")]
+#[test]
+fn test_file_value_invalid_json() {
+    let path = write_value_file("invalid.json", "{not json");
+    let options = ParseOptions::new().allow_files(true);
+    let result = Jqesque::parse_with_options(&format!(">a=@{}", path), &options);
+    std::fs::remove_file(&path).unwrap();
+
+    match result {
+        Err(JqesqueError::ValueSourceError {
+            path: error_path, ..
+        }) => assert_eq!(error_path, path),
+        other => panic!("Expected ValueSourceError, got {:?}", other),
+    }
+}
+
+#[cfg(feature = "files")]
+#[test]
+fn test_file_value_missing_file() {
+    let options = ParseOptions::new().allow_files(true);
+    let result = Jqesque::parse_with_options(">a=@/nonexistent/value.json", &options);
+    match result {
+        Err(JqesqueError::ValueSourceError { path, .. }) => {
+            assert_eq!(path, "/nonexistent/value.json")
+        }
+        other => panic!("Expected ValueSourceError, got {:?}", other),
+    }
+}
+
+/// Tests for applying assignments to YAML documents.
+#[cfg(feature = "yaml")]
+#[parameterized(
+    replace = { "server.port=8080", "server:\n  port: 80\n", "server:\n  port: 8080\n" },
+    inferred_types = { ">flags.debug=true", "name: app\n", "flags:\n  debug: true\nname: app\n" },
+    append = { "+hosts.-=c", "hosts:\n- a\n- b\n", "hosts:\n- a\n- b\n- c\n" },
+    merge = { "~db={\"user\":\"admin\"}", "db:\n  host: localhost\n", "db:\n  host: localhost\n  user: admin\n" },
+    remove = { "-db.password", "db:\n  password: secret\n  user: admin\n", "db:\n  user: admin\n" },
+    numbers = { "limits={\"ratio\":0.5,\"min\":-3}", "limits: {}\n", "limits:\n  min: -3\n  ratio: 0.5\n" },
+)]
+fn test_apply_to_yaml(input: &str, yaml: &str, expected: &str) {
+    let mut yaml: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+    let jqesque = Jqesque::from_str_with_separator(input, Separator::Dot).unwrap();
+    jqesque.apply_to_yaml(&mut yaml).expect("Failed to apply");
+
+    assert_eq!(serde_yaml::to_string(&yaml).unwrap(), expected);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_to_yaml_string_index_too_large() {
+    let jqesque = Jqesque::from_str_with_separator(">a[200000]=1", Separator::Dot).unwrap();
+
+    assert!(matches!(
+        jqesque.to_yaml_string(),
+        Err(JqesqueError::IndexTooLargeError { index: 200000, .. })
+    ));
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_apply_to_yaml_failure_leaves_document() {
+    let original: serde_yaml::Value = serde_yaml::from_str("a: 1\n").unwrap();
+    let mut yaml = original.clone();
+    let jqesque = Jqesque::from_str_with_separator("?a=2", Separator::Dot).unwrap();
+
+    assert!(matches!(
+        jqesque.apply_to_yaml(&mut yaml),
+        Err(JqesqueError::TestFailedError { .. })
+    ));
+    assert_eq!(yaml, original);
+}
+
+/// Tests for resolve that should **succeed**, ignoring the operation and value.
+#[parameterized(
+    key = { "?a.b=ignored", json!(1) },
+    object = { "-a", json!({"b": 1, "n": null}) },
+    null_value = { ">a.n=1", json!(null) },
+    index = { "arr[1]=5", json!(2) },
+    last_element = { "?arr[-1]=1", json!(2) },
+    dotted_key = { "?\"c.d\"=1", json!(0) },
+)]
+fn test_resolve_ok(input: &str, expected: serde_json::Value) {
+    let json_obj = json!({"a": {"b": 1, "n": null}, "arr": [1, 2], "c.d": 0});
+    let jqesque = Jqesque::from_str_with_separator(input, Separator::Dot).unwrap();
+    assert_eq!(jqesque.resolve(&json_obj).unwrap(), &expected);
+}
+
+/// Tests for resolve that should **fail**.
+#[parameterized(
+    missing_key = { "?a.missing=1" },
+    missing_parent = { "-x.y" },
+    out_of_bounds = { "?arr[2]=1" },
+    past_the_end = { "?arr[#]=1" },
+    key_into_scalar = { "?a.b.c=1" },
+)]
+fn test_resolve_err(input: &str) {
+    let json_obj = json!({"a": {"b": 1, "n": null}, "arr": [1, 2], "c.d": 0});
+    let jqesque = Jqesque::from_str_with_separator(input, Separator::Dot).unwrap();
+    assert!(matches!(
+        jqesque.resolve(&json_obj),
+        Err(JqesqueError::InvalidPathError(..))
+    ));
+}
+
+/// Tests for wildcards in `Remove` and `Test` paths that should **succeed**.
+#[parameterized(
+    remove_from_each_element = { "-users[*].temp", json!({"users": [{"name": "a"}, {"name": "b"}, {"name": "c"}], "groups": {"x": {"temp": 1}, "y": {}}}) },
+    remove_from_each_value = { "-groups.*.temp", json!({"users": [{"name": "a", "temp": 1}, {"name": "b"}, {"name": "c", "temp": 3}], "groups": {"x": {}, "y": {}}}) },
+    remove_all_elements = { "-users[*]", json!({"users": [], "groups": {"x": {"temp": 1}, "y": {}}}) },
+    remove_nested = { "-*.*.temp", json!({"users": [{"name": "a"}, {"name": "b"}, {"name": "c"}], "groups": {"x": {}, "y": {}}}) },
+    remove_no_matches = { "-users[*].missing", json!({"users": [{"name": "a", "temp": 1}, {"name": "b"}, {"name": "c", "temp": 3}], "groups": {"x": {"temp": 1}, "y": {}}}) },
+    test_all_match = { "?groups.*.*=1", json!({"users": [{"name": "a", "temp": 1}, {"name": "b"}, {"name": "c", "temp": 3}], "groups": {"x": {"temp": 1}, "y": {}}}) },
+    test_skips_missing = { "?groups.*.temp:int=1", json!({"users": [{"name": "a", "temp": 1}, {"name": "b"}, {"name": "c", "temp": 3}], "groups": {"x": {"temp": 1}, "y": {}}}) },
+)]
+fn test_wildcard_ok(input: &str, expected: serde_json::Value) {
+    let mut json_obj = json!({
+        "users": [{"name": "a", "temp": 1}, {"name": "b"}, {"name": "c", "temp": 3}],
+        "groups": {"x": {"temp": 1}, "y": {}}
+    });
+    let jqesque = Jqesque::from_str_with_separator(input, Separator::Dot).unwrap();
+    jqesque.apply_to(&mut json_obj).expect("Failed to apply");
+    assert_eq!(json_obj, expected);
+}
+
+/// Tests for wildcards that should **fail**.
+#[parameterized(
+    test_one_differs = { "?users[*].name=a" },
+    test_no_matches = { "?users[*].missing=1" },
+    add = { "+users[*].x=1" },
+    replace = { "=users[*].name=x" },
+    insert = { ">users[*].x=1" },
+    merge = { "~users[*]={}" },
+    auto = { "users[*].x=1" },
+    copy = { "&copy<users[*]" },
+)]
+fn test_wildcard_err(input: &str) {
+    let mut json_obj = json!({"users": [{"name": "a"}, {"name": "b"}]});
+    let jqesque = Jqesque::from_str_with_separator(input, Separator::Dot).unwrap();
+    assert!(jqesque.apply_to(&mut json_obj).is_err());
+    assert_eq!(json_obj, json!({"users": [{"name": "a"}, {"name": "b"}]}));
+}
+
+#[parameterized(
+    bare = { "-a.*.b", vec![PathToken::Key("a".to_string()), PathToken::Wildcard, PathToken::Key("b".to_string())], "-a.*.b" },
+    bracketed = { "-a[*].b", vec![PathToken::Key("a".to_string()), PathToken::Wildcard, PathToken::Key("b".to_string())], "-a.*.b" },
+    quoted_is_key = { "-a.\"*\"", vec![PathToken::Key("a".to_string()), PathToken::Key("*".to_string())], "-a.\"*\"" },
+    slash = { "-a/*/b", vec![PathToken::Key("a".to_string()), PathToken::Wildcard, PathToken::Key("b".to_string())], "-a/*/b" },
+)]
+fn test_wildcard_parse(input: &str, expected: Vec<PathToken>, display: &str) {
+    let separator = if input.contains('/') {
+        Separator::Slash
+    } else {
+        Separator::Dot
+    };
+    let jqesque = Jqesque::from_str_with_separator(input, separator).unwrap();
+    assert_eq!(jqesque.tokens, expected);
+    assert_eq!(jqesque.to_string(), display);
+    assert_eq!(
+        Jqesque::from_json_spec(&jqesque.to_json_spec()).unwrap(),
+        jqesque
+    );
+}
+
+#[test]
+fn test_wildcard_error_message() {
+    let jqesque = Jqesque::from_str_with_separator("+users[*].x=1", Separator::Dot).unwrap();
+    assert_eq!(
+        jqesque.apply_to(&mut json!({"users": []})),
+        Err(JqesqueError::WildcardError(Operation::Add))
+    );
+    assert_eq!(
+        jqesque.to_json_patch(),
+        Err(JqesqueError::WildcardError(Operation::Add))
+    );
+}
+
+#[test]
+fn test_path_tokens_in_sets() {
+    let mut tokens = std::collections::HashSet::new();
+    assert!(tokens.insert(PathToken::Key("a".to_string())));
+    assert!(tokens.insert(PathToken::Index(0)));
+    assert!(!tokens.insert(PathToken::Key("a".to_string())));
+    assert!(!tokens.insert(PathToken::Index(0)));
+    assert_eq!(tokens.len(), 2);
+
+    let paths: std::collections::BTreeSet<Vec<PathToken>> = [
+        "a[1]=x", "b=x", "a[0]=x", "a.z=x", "a[1]=y", "a[#]=x", "a.-=x",
+    ]
+    .iter()
+    .map(|input| input.parse::<Jqesque>().unwrap().tokens)
+    .collect();
+    let key = |k: &str| PathToken::Key(k.to_string());
+    assert_eq!(
+        paths.into_iter().collect::<Vec<_>>(),
+        vec![
+            vec![key("a"), key("z")],
+            vec![key("a"), PathToken::Index(0)],
+            vec![key("a"), PathToken::Index(1)],
+            vec![key("a"), PathToken::LengthRelative(0)],
+            vec![key("a"), PathToken::Append],
+            vec![key("b")],
+        ]
+    );
+}
+
+#[test]
+fn test_operations_in_sets() {
+    let operations: std::collections::HashSet<Operation> = ["+a=1", "-a", "+b=2", "-c"]
+        .iter()
+        .map(|input| input.parse::<Jqesque>().unwrap().operation)
+        .collect();
+    assert_eq!(operations.len(), 2);
+    assert!(operations.contains(&Operation::Add));
+    assert!(operations.contains(&Operation::Remove));
+}
+
+#[parameterized(
+    dot = { ".", Separator::Dot },
+    slash = { "/", Separator::Slash },
+    custom = { "|", Separator::Custom('|') },
+    space = { " ", Separator::Custom(' ') },
+    multi_char = { "::", Separator::CustomStr("::".to_string()) },
+)]
+fn test_separator_from_str(input: &str, expected: Separator) {
+    assert_eq!(input.parse::<Separator>().unwrap(), expected);
+    assert_eq!(expected.as_str(), input);
+}
+
+#[test]
+fn test_separator_from_str_empty() {
+    assert!(matches!(
+        "".parse::<Separator>(),
+        Err(JqesqueError::InvalidSeparatorError(_))
+    ));
+}
+
+#[parameterized(
+    slash_in_value = { "url=/api/v1", Separator::Slash, json!({"url": "/api/v1"}) },
+    nested_slash_in_value = { "api/base=/api/v1/", Separator::Slash, json!({"api": {"base": "/api/v1/"}}) },
+    dot_in_value = { "host=example.com", Separator::Dot, json!({"host": "example.com"}) },
+    equals_in_value = { "query=a=b", Separator::Dot, json!({"query": "a=b"}) },
+    custom_in_value = { "a::b=c::d", Separator::CustomStr("::".to_string()), json!({"a": {"b": "c::d"}}) },
+    brackets_in_value = { "a=[x]", Separator::Dot, json!({"a": "[x]"}) },
+    quoted_equals_in_key = { "\"a=b\"=c", Separator::Dot, json!({"a=b": "c"}) },
+)]
+fn test_value_containing_separator(input: &str, separator: Separator, expected: serde_json::Value) {
+    let jqesque = Jqesque::from_str_with_separator(input, separator).unwrap();
+    let mut json_obj = json!({});
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, expected);
+}
+
+#[parameterized(
+    array = { "x:=[1,2,3]", json!({"x": [1, 2, 3]}) },
+    object = { "x:={\"a\":1}", json!({"x": {"a": 1}}) },
+    string = { "x:=\"1\"", json!({"x": "1"}) },
+    number = { "x:=5", json!({"x": 5}) },
+    spaced = { "x := true", json!({"x": true}) },
+    with_type = { "x:int:=5", json!({"x": 5}) },
+    lenient = { "x=notjson", json!({"x": "notjson"}) },
+    lenient_array = { "x=[1,2,3]", json!({"x": [1, 2, 3]}) },
+)]
+fn test_raw_json_assignment_ok(input: &str, expected: serde_json::Value) {
+    let jqesque = format!(">{}", input).parse::<Jqesque>().unwrap();
+    let mut json_obj = json!({});
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, expected);
+}
+
+#[parameterized(
+    not_json = { "x:=notjson" },
+    truncated = { "x:=[1,2" },
+    empty = { "x:=" },
+)]
+fn test_raw_json_assignment_err(input: &str) {
+    assert!(matches!(
+        input.parse::<Jqesque>(),
+        Err(JqesqueError::ParseError { .. })
+    ));
+}
+
+#[test]
+fn test_raw_json_assignment_error_message() {
+    let err = "x:=notjson".parse::<Jqesque>().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Parsing error: expected a valid JSON value after ':=' at column 4"
+    );
+}
+
+#[test]
+fn test_raw_json_assignment_ignores_infer_types() {
+    let options = ParseOptions::new().infer_types(false);
+    let jqesque = Jqesque::from_str_with_options("x:=5", Separator::Dot, options).unwrap();
+    assert_eq!(jqesque.value, Some(json!(5)));
+}
+
+#[parameterized(
+    int = { "port=(int)8080", json!({"port": 8080}) },
+    negative_int = { "port=(int)-1", json!({"port": -1}) },
+    large_int = { "port=(int)18446744073709551615", json!({"port": 18446744073709551615u64}) },
+    float = { "ratio=(float)1", json!({"ratio": 1.0}) },
+    bool_yes = { "flag=(bool)yes", json!({"flag": true}) },
+    bool_off = { "flag=(bool)OFF", json!({"flag": false}) },
+    str_number = { "name=(str)1234", json!({"name": "1234"}) },
+    str_keeps_spaces = { "name=(str) a ", json!({"name": " a"}) },
+    null = { "gone=(null)", json!({"gone": null}) },
+    json = { "list=(json)[1,2]", json!({"list": [1, 2]}) },
+    spaced = { "port=(int) 8080", json!({"port": 8080}) },
+    unknown_prefix = { "note=(hello)", json!({"note": "(hello)"}) },
+    no_prefix = { "port=8080", json!({"port": 8080}) },
+)]
+fn test_typed_value_ok(input: &str, expected: serde_json::Value) {
+    let jqesque = format!(">{}", input).parse::<Jqesque>().unwrap();
+    let mut json_obj = json!({});
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, expected);
+}
+
+#[parameterized(
+    int = { "port=(int)abc", "Parsing error: expected an integer after (int) at column 11" },
+    int_fraction = { "port=(int)1.5", "Parsing error: expected an integer after (int) at column 11" },
+    float = { "ratio=(float)x", "Parsing error: expected a number after (float) at column 14" },
+    bool = { "flag=(bool)maybe", "Parsing error: expected true, false, yes, no, on, off, 1 or 0 after (bool) at column 12" },
+    null = { "gone=(null)0", "Parsing error: expected null or nothing after (null) at column 12" },
+    json = { "list=(json)[1,", "Parsing error: expected a valid JSON value after (json) at column 12" },
+)]
+fn test_typed_value_err(input: &str, message: &str) {
+    let err = input.parse::<Jqesque>().unwrap_err();
+    assert_eq!(err.to_string(), message);
+}
+
+#[test]
+fn test_typed_value_ignores_infer_types() {
+    let options = ParseOptions::new().infer_types(false);
+    let jqesque =
+        Jqesque::from_str_with_options("port=(int)8080", Separator::Dot, options).unwrap();
+    assert_eq!(jqesque.value, Some(json!(8080)));
+}
+
+#[parameterized(
+    insert_within_limit = { ">a[9]=1", json!({}), true },
+    insert_past_limit = { ">a[10]=1", json!({}), false },
+    insert_relative_to_length = { ">a[15]=1", json!({"a": [0, 1, 2, 3, 4, 5]}), true },
+    merge_past_limit = { "~a[10]={\"b\":1}", json!({}), false },
+    auto_past_limit = { "a[10]=1", json!({}), false },
+    nested_past_limit = { ">a[0][10]=1", json!({}), false },
+    replace_existing = { ">a[20]=1", json!({"a": [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]}), true },
+)]
+fn test_max_array_grow(input: &str, json_obj: serde_json::Value, ok: bool) {
+    let mut json_obj = json_obj;
+    let options = ApplyOptions::new().max_array_grow(10);
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    let result = jqesque.apply_to_with_options(&mut json_obj, &options);
+    assert_eq!(result.is_ok(), ok, "{:?}", result);
+    if !ok {
+        assert!(matches!(
+            result,
+            Err(JqesqueError::IndexTooLargeError { limit: 10, .. })
+        ));
+    }
+}
+
+#[test]
+fn test_max_array_grow_default() {
+    let jqesque = ">a[100000000]=1".parse::<Jqesque>().unwrap();
+    assert_eq!(
+        jqesque.apply_to(&mut json!({})),
+        Err(JqesqueError::IndexTooLargeError {
+            index: 100000000,
+            limit: 100_000
+        })
+    );
+}
+
+#[parameterized(
+    index_as_key = { ">years[2024]=x", true, json!({"years": {"2024": "x"}}) },
+    nested_index_as_key = { ">a[1][2].b=x", true, json!({"a": {"1": {"2": {"b": "x"}}}}) },
+    index_as_index = { ">years[2]=x", false, json!({"years": [null, null, "x"]}) },
+    dotted_number_is_key = { ">years.2024=x", false, json!({"years": {"2024": "x"}}) },
+    append_unaffected = { ">log[#]=x", true, json!({"log": ["x"]}) },
+)]
+fn test_force_object_keys(input: &str, force_object_keys: bool, expected: serde_json::Value) {
+    let options = ParseOptions::new().force_object_keys(force_object_keys);
+    let jqesque = Jqesque::parse_with_options(input, &options).unwrap();
+    let mut json_obj = json!({});
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, expected);
+}
+
+#[test]
+fn test_force_object_keys_pointer() {
+    let options = ParseOptions::new().force_object_keys(true);
+    let jqesque = Jqesque::parse_with_options("&years[2025]<years[2024]", &options).unwrap();
+    assert_eq!(jqesque.to_pointer().to_string(), "/years/2025");
+    let mut json_obj = json!({"years": {"2024": 1}});
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, json!({"years": {"2024": 1, "2025": 1}}));
+}
+
+#[parameterized(
+    remove = { "-foo.bar baz", "-foo.bar", " baz" },
+    copy = { "&a<b c", "&a<b", " c" },
+    unseparated = { "-a[0]x", "-a[0]", "x" },
+    type_annotation = { "-a:int x", "-a:int", " x" },
+)]
+fn test_trailing_input(input: &str, consumed: &str, remaining: &str) {
+    assert_eq!(
+        input.parse::<Jqesque>(),
+        Err(JqesqueError::TrailingInputError {
+            consumed: consumed.to_string(),
+            remaining: remaining.to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_trailing_input_path() {
+    assert_eq!(
+        Jqesque::get_first(&["a.b c"], Separator::Dot, &json!({})),
+        Err(JqesqueError::TrailingInputError {
+            consumed: "a.b".to_string(),
+            remaining: " c".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_trailing_input_is_part_of_value() {
+    let jqesque = ">foo.bar=baz qux".parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.value, Some(json!("baz qux")));
+}
+
+#[test]
+fn test_trailing_input_error_message() {
+    let err = "-foo.bar baz".parse::<Jqesque>().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unexpected input \" baz\" after \"-foo.bar\""
+    );
+}
+
+#[parameterized(
+    insert = { ">a.b=1", json!({"a": {"b": 1}, "c": 2}), Operation::Insert },
+    auto = { "c=3", json!({"a": 1, "c": 3}), Operation::Replace },
+    remove = { "-c", json!({"a": 1}), Operation::Remove },
+    merge = { "~a={\"x\":1}", json!({"a": {"x": 1}, "c": 2}), Operation::Merge },
+)]
+fn test_apply_to_map(input: &str, expected: serde_json::Value, operation: Operation) {
+    let mut map = serde_json::Map::new();
+    map.insert("a".to_string(), json!(1));
+    map.insert("c".to_string(), json!(2));
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.apply_to_map(&mut map), Ok(operation));
+    assert_eq!(serde_json::Value::Object(map), expected);
+}
+
+#[test]
+fn test_apply_to_map_root() {
+    let mut map = serde_json::Map::new();
+    map.insert("a".to_string(), json!(1));
+
+    let replaced = JqesqueBuilder::new()
+        .operation(Operation::Insert)
+        .value(json!({"b": 2}))
+        .build()
+        .unwrap();
+    assert_eq!(replaced.apply_to_map(&mut map), Ok(Operation::Insert));
+    assert_eq!(serde_json::Value::Object(map.clone()), json!({"b": 2}));
+
+    let not_object = JqesqueBuilder::new()
+        .operation(Operation::Insert)
+        .value(json!([1]))
+        .build()
+        .unwrap();
+    assert_eq!(
+        not_object.apply_to_map(&mut map),
+        Err(JqesqueError::TypeMismatchError {
+            expected: ValueType::Object,
+            actual: json!([1]),
+        })
+    );
+    assert_eq!(serde_json::Value::Object(map), json!({"b": 2}));
+}
+
+#[test]
+fn test_apply_to_map_error_keeps_map() {
+    let mut map = serde_json::Map::new();
+    map.insert("a".to_string(), json!(1));
+    let jqesque = "=b=2".parse::<Jqesque>().unwrap();
+    assert!(jqesque.apply_to_map(&mut map).is_err());
+    assert_eq!(serde_json::Value::Object(map), json!({"a": 1}));
+}
+
+#[parameterized(
+    last_write_wins = { ">a=1;>b=2;>a=3", vec![">a=3", ">b=2"] },
+    remove_cancels_write = { ">b=1;-b", vec![">b=null", "-b"] },
+    remove_cancels_new_write = { ">x.y=1;-x", vec![">x=null", "-x"] },
+    remove_cancels_child_write = { ">x.y=1;-x.y", vec![">x.y=null", "-x.y"] },
+    remove_without_writes = { "-b.c;-b", vec!["-b"] },
+    merge_after_cancelling_remove = { ">c=1;-c;~c={\"y\":2}", vec![">c={\"y\":2}"] },
+    write_replaces_children = { ">a.b.c=1;>a.b.d=2;>a.b={}", vec![">a.b={}"] },
+    merges_combined = { "~a={\"x\":1};~a={\"y\":2}", vec!["~a={\"x\":1,\"y\":2}"] },
+    merge_folded_into_write = { ">a={\"x\":1};~a={\"y\":2}", vec![">a={\"x\":1,\"y\":2}"] },
+    merge_after_remove = { "-a;~a={\"y\":2}", vec![">a={\"y\":2}"] },
+    merge_not_moved_before_child = { ">a.b.c=1;>x=2;~a={\"d\":1};>a.e=1", vec![">a.b.c=1", ">x=2", "~a={\"d\":1}", ">a.e=1"] },
+    sorted = { ">b.c=1;>a=2;>b=3;>c=4", vec![">a=2", ">b=3", ">c=4"] },
+    children_after_parents = { ">z.y=1;>z={};>z.x=2", vec![">z={}", ">z.x=2"] },
+    key_after_index = { ">a[0]=1;>a.b=2", vec![">a[0]=1", ">a.b=2"] },
+    test_is_barrier = { ">b=1;?a=1;>a=2;>b=3", vec![">b=1", "?a=1", ">a=2", ">b=3"] },
+    append_kept = { ">log[#]=1;>log[#]=1", vec![">log[#]=1", ">log[#]=1"] },
+    add_index_kept = { "+a[0]=1;+a[0]=2", vec!["+a[0]=1", "+a[0]=2"] },
+    typed_not_folded = { ">a:object={};~a={\"x\":1}", vec![">a:object={}", "~a={\"x\":1}"] },
+)]
+fn test_normalize(input: &str, expected: Vec<&str>) {
+    let jqesques = Jqesque::parse_many(input, Separator::Dot).unwrap();
+    let normalized = Jqesque::normalize(jqesques.clone());
+    assert_eq!(
+        normalized.iter().map(|j| j.to_string()).collect::<Vec<_>>(),
+        expected
+    );
+
+    let base = json!({"a": [0], "b": 0, "log": []});
+    let mut json_obj = base.clone();
+    let mut json_expected = base.clone();
+    let applied = Jqesque::apply_all(&jqesques, &mut json_expected);
+    if applied.is_ok() {
+        Jqesque::apply_all(&normalized, &mut json_obj).unwrap();
+        assert_eq!(json_obj, json_expected);
+    }
+}
+
+#[parameterized(
+    quoted = { "x=\"a b\"", json!("a b") },
+    bare = { "x=a b", json!("a b") },
+    inner_single_quote = { "name=\"O'Brien\"", json!("O'Brien") },
+    escapes_decoded = { "x=\"say \\\"hi\\\"\\n\"", json!("say \"hi\"\n") },
+    empty_quoted = { "x=\"\"", json!("") },
+    quoted_number = { "x=\"1\"", json!("1") },
+    padded = { "x = \"a\" ", json!("a") },
+    unterminated = { "x=\"a b", json!("\"a b") },
+    trailing_text = { "x=\"a\" b", json!("\"a\" b") },
+    single_quoted = { "x='a b'", json!("'a b'") },
+    inner_quotes = { "x=a \"b\"", json!("a \"b\"") },
+)]
+fn test_quoted_values(input: &str, expected: serde_json::Value) {
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.value, Some(expected));
+}
+
+#[test]
+fn test_quoted_values_without_inference() {
+    let options = ParseOptions::new().infer_types(false);
+    let jqesque = Jqesque::from_str_with_options("x=\"a b\"", Separator::Dot, options).unwrap();
+    assert_eq!(jqesque.value, Some(json!("\"a b\"")));
+}
+
+#[parameterized(
+    insert = { ">a.b=1", vec!["/a/b"] },
+    remove = { "-users[0]", vec!["/users/0"] },
+    last_element = { "-users[-1]", vec!["/users/1"] },
+    append = { ">users[#]=1", vec!["/users/2"] },
+    unresolvable = { "=missing[-1]=1", vec!["/missing/#-1"] },
+    wildcard = { "-users[*].name", vec!["/users/0/name", "/users/1/name"] },
+    wildcard_no_match = { "-nothing.*", vec![] },
+    merge_leaves = { "~settings={\"a\":{\"b\":1,\"c\":[1,{}]},\"d\":null}", vec!["/settings/a/b", "/settings/a/c/0", "/settings/a/c/1", "/settings/d"] },
+    merge_scalar = { "~settings=1", vec!["/settings"] },
+    merge_empty = { "~settings={}", vec!["/settings"] },
+    copy = { "&backup<users[-1]", vec!["/backup", "/users/1"] },
+    move_op = { "@users[0]<users[1]", vec!["/users/0", "/users/1"] },
+    escaped = { ">\"a/b~c\"=1", vec!["/a~1b~0c"] },
+)]
+fn test_affected_pointers(input: &str, expected: Vec<&str>) {
+    let json_obj = json!({"users": [{"name": "a"}, {"name": "b"}]});
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.affected_pointers(&json_obj), expected);
+}
+
+#[parameterized(
+    absent = { ":settings.theme=light", json!({"settings": {"theme": "light", "size": 12, "color": null}}), ApplyOutcome::Applied(Operation::Default) },
+    present = { ":settings.size=10", json!({"settings": {"size": 12, "color": null}}), ApplyOutcome::Unchanged(Operation::Default) },
+    present_null = { ":settings.color=red", json!({"settings": {"size": 12, "color": null}}), ApplyOutcome::Unchanged(Operation::Default) },
+    missing_parents = { ":a.b[1]=x", json!({"settings": {"size": 12, "color": null}, "a": {"b": [null, "x"]}}), ApplyOutcome::Applied(Operation::Default) },
+    append = { ":list[#]=x", json!({"settings": {"size": 12, "color": null}, "list": ["x"]}), ApplyOutcome::Applied(Operation::Default) },
+)]
+fn test_default_operation(input: &str, expected: serde_json::Value, outcome: ApplyOutcome) {
+    let mut json_obj = json!({"settings": {"size": 12, "color": null}});
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.operation, Operation::Default);
+    assert_eq!(jqesque.apply_to_detailed(&mut json_obj), Ok(outcome));
+    assert_eq!(json_obj, expected);
+    assert_eq!(jqesque.to_string().parse::<Jqesque>().unwrap(), jqesque);
+}
+
+#[test]
+fn test_default_operation_returns_op() {
+    let jqesque = ":a=1".parse::<Jqesque>().unwrap();
+    let mut json_obj = json!({});
+    assert_eq!(jqesque.apply_to(&mut json_obj), Ok(Operation::Default));
+    assert_eq!(jqesque.apply_to(&mut json_obj), Ok(Operation::Default));
+    assert_eq!(json_obj, json!({"a": 1}));
+    assert_eq!(jqesque.to_string(), ":a=1");
+    assert_eq!(
+        jqesque.to_json_patch(),
+        Err(JqesqueError::UnsupportedOperationError(Operation::Default))
+    );
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct EditConfig {
+    #[serde(with = "jqesque::serde_string")]
+    edit: Jqesque,
+}
+
+#[parameterized(
+    insert = { ">foo.bar[0]=true" },
+    auto = { "a.b=1" },
+    remove = { "-old.key" },
+    quoted_key = { ">\"a.b\".c=\"x y\"" },
+    typed = { "?count:int=5" },
+    copy = { "&backup<settings.theme" },
+    default = { ":theme=\"light\"" },
+)]
+fn test_serde_string(input: &str) {
+    let config = EditConfig {
+        edit: input.parse::<Jqesque>().unwrap(),
+    };
+    let serialized = serde_json::to_value(&config).unwrap();
+    assert_eq!(serialized, json!({"edit": input}));
+    assert_eq!(
+        serde_json::from_value::<EditConfig>(serialized).unwrap(),
+        config
+    );
+}
+
+#[parameterized(
+    slash = { "a/b[0]=1", Separator::Slash, "a.b[0]=1" },
+    dotted_key = { "\"a.b\"/c=1", Separator::Slash, "\"a.b\".c=1" },
+    custom_str = { "&x::y<z", Separator::CustomStr("::".to_string()), "&x.y<z" },
+)]
+fn test_serde_string_separator(input: &str, separator: Separator, expected: &str) {
+    let config = EditConfig {
+        edit: Jqesque::from_str_with_separator(input, separator).unwrap(),
+    };
+    let serialized = serde_json::to_value(&config).unwrap();
+    assert_eq!(serialized, json!({"edit": expected}));
+    assert_eq!(
+        serde_json::from_value::<EditConfig>(serialized).unwrap(),
+        config
+    );
+}
+
+#[test]
+fn test_serde_string_invalid() {
+    let err = serde_json::from_value::<EditConfig>(json!({"edit": "a..b=1"})).unwrap_err();
+    assert_eq!(err.to_string(), "Parsing error: expected a key at column 3");
+    assert!(serde_json::from_value::<EditConfig>(json!({"edit": 1})).is_err());
+}
+
+#[parameterized(
+    siblings = { "a.b=1;a.c=2", json!({"a": {"b": 1, "c": 2}}) },
+    insert_overwrites = { ">a.b=1;>a={\"c\":2}", json!({"a": {"c": 2}}) },
+    merge_combines = { ">a.b=1;~a={\"c\":2}", json!({"a": {"b": 1, "c": 2}}) },
+    default_keeps = { ">a=1;:a=2;:b=3", json!({"a": 1, "b": 3}) },
+    arrays = { ">list[#]=1;>list[#]=2;>list[-1]=3", json!({"list": [1, 3]}) },
+    patch_ops_skipped = { ">a=1;+b=2;=a=3;-a;?a=1", json!({"a": 1}) },
+    unresolvable_skipped = { ">a[-1]=1;>b=2", json!({"b": 2}) },
+    nothing = { "-a;?b=1", json!(null) },
+)]
+fn test_combined_json(input: &str, expected: serde_json::Value) {
+    let jqesques = Jqesque::parse_many(input, Separator::Dot).unwrap();
+    assert_eq!(Jqesque::combined_json(&jqesques), expected);
+}
+
+#[parameterized(
+    insert = { ">z.b=1", "{\"a\":{\"y\":1,\"z\":2},\"m\":[{\"c\":1,\"d\":2}],\"z\":{\"b\":1}}" },
+    merge = { "~a={\"x\":0,\"zz\":3}", "{\"a\":{\"x\":0,\"y\":1,\"z\":2,\"zz\":3},\"m\":[{\"c\":1,\"d\":2}]}" },
+    auto = { "m[0].b=0", "{\"a\":{\"y\":1,\"z\":2},\"m\":[{\"b\":0,\"c\":1,\"d\":2}]}" },
+)]
+fn test_key_order_sorted(input: &str, expected: &str) {
+    let options = ApplyOptions::new().key_order(KeyOrder::Sorted);
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    let mut json_obj: serde_json::Value =
+        serde_json::from_str(r#"{"m": [{"d": 2, "c": 1}], "a": {"z": 2, "y": 1}}"#).unwrap();
+    jqesque
+        .apply_to_with_options(&mut json_obj, &options)
+        .unwrap();
+    assert_eq!(serde_json::to_string(&json_obj).unwrap(), expected);
+}
+
+#[test]
+fn test_key_order_failure_leaves_document() {
+    let options = ApplyOptions::new().key_order(KeyOrder::Sorted);
+    let jqesque = "=missing=1".parse::<Jqesque>().unwrap();
+    let mut json_obj = json!({"b": 1, "a": 2});
+    assert!(jqesque
+        .apply_to_with_options(&mut json_obj, &options)
+        .is_err());
+    assert_eq!(json_obj, json!({"b": 1, "a": 2}));
+}
+
+#[parameterized(
+    matching = { "-?lock.owner=me", json!({"lock": {}, "count": 1}) },
+    matching_number = { "-?count=1", json!({"lock": {"owner": "me"}}) },
+    matching_object = { "-?lock={\"owner\":\"me\"}", json!({"count": 1}) },
+)]
+fn test_remove_if_ok(input: &str, expected: serde_json::Value) {
+    let mut json_obj = json!({"lock": {"owner": "me"}, "count": 1});
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.operation, Operation::RemoveIf);
+    assert_eq!(jqesque.apply_to(&mut json_obj), Ok(Operation::RemoveIf));
+    assert_eq!(json_obj, expected);
+    assert_eq!(jqesque.to_string().parse::<Jqesque>().unwrap(), jqesque);
+}
+
+#[test]
+fn test_remove_if_err() {
+    let original = json!({"lock": {"owner": "me"}});
+    let mut json_obj = original.clone();
+    let jqesque = "-?lock.owner=you".parse::<Jqesque>().unwrap();
+    assert_eq!(
+        jqesque.apply_to(&mut json_obj),
+        Err(JqesqueError::TestFailedError {
+            expected: json!("you"),
+            actual: json!("me"),
+        })
+    );
+    assert_eq!(json_obj, original);
+
+    let missing = "-?lock.other=me".parse::<Jqesque>().unwrap();
+    assert!(matches!(
+        missing.apply_to(&mut json_obj),
+        Err(JqesqueError::InvalidPathError(..))
+    ));
+    let options = ApplyOptions::new().remove_if_exists(true);
+    assert_eq!(
+        missing.apply_to_with_options(&mut json_obj, &options),
+        Ok(ApplyOutcome::Unchanged(Operation::RemoveIf))
+    );
+    assert_eq!(json_obj, original);
+}
+
+#[test]
+fn test_remove_if_representations() {
+    let jqesque = "-?lock.owner=me".parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.to_string(), "-?lock.owner=\"me\"");
+    let expected = json!([
+        {"op": "test", "path": "/lock/owner", "value": "me"},
+        {"op": "remove", "path": "/lock/owner"}
+    ]);
+    assert_eq!(jqesque.as_json(), expected);
+    assert_eq!(
+        serde_json::to_value(jqesque.to_json_patch().unwrap()).unwrap(),
+        expected
+    );
+    assert_eq!(
+        Jqesque::from_json_spec(&jqesque.to_json_spec()).unwrap(),
+        jqesque
+    );
+    assert!("-?lock.owner".parse::<Jqesque>().is_err());
+}
+
+#[parameterized(
+    key = { PathToken::Key("foo".to_string()), Some("foo"), None, "foo" },
+    index = { PathToken::Index(3), None, Some(3), "[3]" },
+    relative = { PathToken::LengthRelative(-1), None, None, "[#-1]" },
+    length = { PathToken::LengthRelative(0), None, None, "[#]" },
+    append = { PathToken::Append, None, None, "[-]" },
+    wildcard = { PathToken::Wildcard, None, None, "*" },
+)]
+fn test_path_token_getters(
+    token: PathToken,
+    key: Option<&str>,
+    index: Option<usize>,
+    display: &str,
+) {
+    assert_eq!(token.as_key(), key);
+    assert_eq!(token.as_index(), index);
+    assert_eq!(token.is_key(), key.is_some());
+    assert_eq!(token.is_index(), index.is_some());
+    assert_eq!(token.to_string(), display);
+}
+
+#[parameterized(
+    test = { "?name=1", json!({"Name": 1, "Other": {"Key": [true]}}) },
+    test_nested = { "?other.key[0]=true", json!({"Name": 1, "Other": {"Key": [true]}}) },
+    remove = { "-name", json!({"Other": {"Key": [true]}}) },
+    replace = { "=OTHER.key=2", json!({"Name": 1, "Other": {"Key": 2}}) },
+    remove_if = { "-?NAME=1", json!({"Other": {"Key": [true]}}) },
+    insert_unaffected = { ">name=2", json!({"Name": 1, "name": 2, "Other": {"Key": [true]}}) },
+)]
+fn test_case_insensitive_keys(input: &str, expected: serde_json::Value) {
+    let mut json_obj = json!({"Name": 1, "Other": {"Key": [true]}});
+    let options = ApplyOptions::new().case_insensitive_keys(true);
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    assert!(jqesque
+        .apply_to_with_options(&mut json_obj, &options)
+        .is_ok());
+    assert_eq!(json_obj, expected);
+}
+
+#[test]
+fn test_case_insensitive_keys_err() {
+    let options = ApplyOptions::new().case_insensitive_keys(true);
+
+    let mut json_obj = json!({"Name": 1, "NAME": 2});
+    let jqesque = "?name=1".parse::<Jqesque>().unwrap();
+    assert_eq!(
+        jqesque.apply_to_with_options(&mut json_obj, &options),
+        Err(JqesqueError::AmbiguousKeyError {
+            key: "name".to_string(),
+            matches: vec!["NAME".to_string(), "Name".to_string()],
+        })
+    );
+
+    // An exact match is never ambiguous
+    let jqesque = "?Name=1".parse::<Jqesque>().unwrap();
+    assert!(jqesque
+        .apply_to_with_options(&mut json_obj, &options)
+        .is_ok());
+
+    // Keys are case-sensitive by default
+    let jqesque = "?name=1".parse::<Jqesque>().unwrap();
+    assert!(jqesque.apply_to(&mut json!({"Name": 1})).is_err());
+}
+
+#[parameterized(
+    insert = { Operation::Insert, Some(json!(1)) },
+    remove = { Operation::Remove, None },
+    test = { Operation::Test, Some(json!("x")) },
+)]
+fn test_from_parts_ok(operation: Operation, value: Option<serde_json::Value>) {
+    let tokens = vec![PathToken::Key("a".to_string()), PathToken::Index(0)];
+    let jqesque = Jqesque::from_parts(operation.clone(), tokens.clone(), value.clone()).unwrap();
+    assert_eq!(jqesque.into_parts(), (operation, tokens, value));
+}
+
+#[parameterized(
+    insert = { Operation::Insert, JqesqueError::MissingValueError(Operation::Insert) },
+    auto = { Operation::Auto, JqesqueError::MissingValueError(Operation::Auto) },
+    copy = { Operation::Copy, JqesqueError::MissingFromError(Operation::Copy) },
+)]
+fn test_from_parts_err(operation: Operation, expected: JqesqueError) {
+    let tokens = vec![PathToken::Key("a".to_string())];
+    assert_eq!(Jqesque::from_parts(operation, tokens, None), Err(expected));
+}
+
+#[parameterized(
+    object = { "color=blue", "/settings/theme", json!({"settings": {"theme": {"color": "blue"}, "list": [1]}}) },
+    array = { "+[0]=0", "/settings/list", json!({"settings": {"theme": {"color": "red"}, "list": [0, 1]}}) },
+    root = { "-settings.list", "", json!({"settings": {"theme": {"color": "red"}}}) },
+)]
+fn test_apply_to_at(input: &str, root: &str, expected: serde_json::Value) {
+    let mut json_obj = json!({"settings": {"theme": {"color": "red"}, "list": [1]}});
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    let root = jsonptr::Pointer::parse(root).unwrap();
+    assert!(jqesque.apply_to_at(&mut json_obj, root).is_ok());
+    assert_eq!(json_obj, expected);
+}
+
+#[test]
+fn test_apply_to_at_missing_root() {
+    let original = json!({"settings": {}});
+    let mut json_obj = original.clone();
+    let jqesque = "color=blue".parse::<Jqesque>().unwrap();
+    let root = jsonptr::Pointer::parse("/settings/theme").unwrap();
+    assert!(matches!(
+        jqesque.apply_to_at(&mut json_obj, root),
+        Err(JqesqueError::InvalidPathError(..))
+    ));
+    assert_eq!(json_obj, original);
+}
+
+#[parameterized(
+    object = { "x={\"a\":1}", json!({"a": 1}) },
+    array = { "x=[1, 2]", json!([1, 2]) },
+    string = { "x=\"quoted\"", json!("quoted") },
+    number = { "x=42", json!(42) },
+    bare_word = { "x=hello", json!("hello") },
+    typed = { "x=(str){bad}", json!("{bad}") },
+)]
+fn test_strict_values_ok(input: &str, expected: serde_json::Value) {
+    let options = ParseOptions::new().strict_values(true);
+    let jqesque = Jqesque::parse_with_options(input, &options).unwrap();
+    assert_eq!(jqesque.value, Some(expected));
+}
+
+#[parameterized(
+    object = { "x={bad}", "{bad}" },
+    truncated = { "data={\"a\":}", "{\"a\":}" },
+    array = { "x=[1,", "[1," },
+    number = { "x=1.2.3", "1.2.3" },
+)]
+fn test_strict_values_err(input: &str, text: &str) {
+    let options = ParseOptions::new().strict_values(true);
+    match Jqesque::parse_with_options(input, &options) {
+        Err(JqesqueError::InvalidJsonValueError { input, .. }) => assert_eq!(input, text),
+        other => panic!("expected an InvalidJsonValueError, got {:?}", other),
+    }
+
+    // Without strict values, the value falls back to a string
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.value, Some(json!(text)));
+}
+
+#[parameterized(
+    replace = { "=a=2", Operation::Replace, json!([{"op": "replace", "path": "/a", "value": 2}]) },
+    insert_padded = { ">b[1]=x", Operation::Insert, json!([{"op": "add", "path": "/b", "value": [null, "x"]}]) },
+    remove = { "-a", Operation::Remove, json!([{"op": "remove", "path": "/a"}]) },
+    default_existing = { ":a=5", Operation::Default, json!([]) },
+    replace_same = { "=a=1", Operation::Replace, json!([]) },
+    test = { "?a=1", Operation::Test, json!([]) },
+)]
+fn test_apply_and_diff(input: &str, expected_operation: Operation, expected: serde_json::Value) {
+    let mut json_obj = json!({"a": 1});
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    let (operation, patch) = jqesque.apply_and_diff(&mut json_obj).unwrap();
+    assert_eq!(operation, expected_operation);
+    assert_eq!(serde_json::to_value(&patch).unwrap(), expected);
+
+    // The patch replays the change on the original document
+    let mut replayed = json!({"a": 1});
+    json_patch::patch(&mut replayed, &patch).unwrap();
+    assert_eq!(replayed, json_obj);
+}
+
+/// Tests for assignments to the document root, written as the separator on its own.
+#[parameterized(
+    auto = { ".=[1,2,3]", Separator::Dot, json!([1, 2, 3]) },
+    insert = { ">.=[1,2,3]", Separator::Dot, json!([1, 2, 3]) },
+    replace = { "=/=[1,2,3]", Separator::Slash, json!([1, 2, 3]) },
+    add = { "+.={\"b\":2}", Separator::Dot, json!({"b": 2}) },
+    merge = { "~.={\"b\":2}", Separator::Dot, json!({"a": 1, "b": 2}) },
+    test = { "?.={\"a\":1}", Separator::Dot, json!({"a": 1}) },
+    default = { ":.=[]", Separator::Dot, json!({"a": 1}) },
+    custom = { "|=null", Separator::Custom('|'), json!(null) },
+    custom_str = { "=::=null", Separator::CustomStr("::".to_string()), json!(null) },
+)]
+fn test_root_assignment(input: &str, separator: Separator, expected: serde_json::Value) {
+    let mut json_obj = json!({"a": 1});
+    let jqesque = Jqesque::from_str_with_separator(input, separator.clone()).unwrap();
+    assert!(jqesque.tokens().is_empty());
+    assert!(jqesque.apply_to(&mut json_obj).is_ok());
+    assert_eq!(json_obj, expected);
+    assert_eq!(
+        Jqesque::from_str_with_separator(&jqesque.to_string(), separator).unwrap(),
+        jqesque
+    );
+}
+
+#[test]
+fn test_root_assignment_err() {
+    let jqesque = "?.=[]".parse::<Jqesque>().unwrap();
+    assert!(matches!(
+        jqesque.apply_to(&mut json!({"a": 1})),
+        Err(JqesqueError::TestFailedError { .. })
+    ));
+    assert!(".foo=1".parse::<Jqesque>().is_err());
+    assert_eq!(">.=[1]".parse::<Jqesque>().unwrap().to_string(), ">.=[1]");
+}
+
+#[test]
+fn test_parse_many_comments() {
+    let input = "# defaults\nfoo=bar # set foo\n>list[#]=\"#1\" # append";
+    let jqesques = Jqesque::parse_many(input, Separator::Dot).unwrap();
+    let mut json_obj = json!({});
+    Jqesque::apply_all(&jqesques, &mut json_obj).unwrap();
+    assert_eq!(json_obj, json!({"foo": "bar", "list": ["#1"]}));
+}
+
+#[parameterized(
+    remove_ancestor = { ">a.b=1", "-a", true },
+    write_ancestor = { "=a={}", ">a.b.c=1", true },
+    same_path = { ">a=1", "~a={\"b\":1}", true },
+    siblings = { ">a.b=1", ">a.c=2", false },
+    different_indices = { "=a[0]=1", "=a[1]=2", false },
+    key_and_index = { ">a.b=1", ">a[0]=1", true },
+    shifting_add = { "+a[0]=1", "=a[3]=2", true },
+    shifting_remove = { "-a[2]", "?a[0]=1", true },
+    append = { ">a[-]=1", "=a[0]=2", true },
+    wildcard = { "-a.*.x", "=a.b.x=1", true },
+    tests = { "?a=1", "?a.b=2", false },
+    test_and_write = { "?a.b=1", ">a.b=2", true },
+    copy_source = { "&b<a.x", "=a.x=1", true },
+    copy_sources = { "&b<a", "&c<a", false },
+    move_source = { "@b<a", "?a=1", true },
+    unrelated = { ">a=1", "-b", false },
+)]
+fn test_conflicts_with(first: &str, second: &str, expected: bool) {
+    let first = first.parse::<Jqesque>().unwrap();
+    let second = second.parse::<Jqesque>().unwrap();
+    assert_eq!(first.conflicts_with(&second), expected);
+    assert_eq!(second.conflicts_with(&first), expected);
+}
+
+/// Tests for appending with empty brackets, `arr[]`.
+#[parameterized(
+    insert = { ">a[]=3", json!({"a": [1, 2, 3]}) },
+    add = { "+a[]=3", json!({"a": [1, 2, 3]}) },
+    nested = { ">a[][]=3", json!({"a": [1, 2, [3]]}) },
+    missing = { ">b[]=3", json!({"a": [1, 2], "b": [3]}) },
+    middle = { ">a[].b=3", json!({"a": [1, 2, {"b": 3}]}) },
+)]
+fn test_empty_bracket_append(input: &str, expected: serde_json::Value) {
+    let mut json_obj = json!({"a": [1, 2]});
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    assert_eq!(
+        jqesque
+            .tokens()
+            .iter()
+            .filter(|t| **t == PathToken::Append)
+            .count(),
+        input.matches("[]").count()
+    );
+    assert!(jqesque.apply_to(&mut json_obj).is_ok());
+    assert_eq!(json_obj, expected);
+}
+
+#[test]
+fn test_empty_bracket_chained_appends() {
+    let jqesques = Jqesque::parse_many(">a[].b=1;>a[].b=2", Separator::Dot).unwrap();
+    let mut json_obj = json!({});
+    Jqesque::apply_all(&jqesques, &mut json_obj).unwrap();
+    assert_eq!(json_obj, json!({"a": [{"b": 1}, {"b": 2}]}));
+}
+
+#[parameterized(
+    insert = { Operation::Insert },
+    merge = { Operation::Merge },
+    add = { Operation::Add },
+    remove = { Operation::Remove },
+    replace = { Operation::Replace },
+    test = { Operation::Test },
+    copy = { Operation::Copy },
+    move_ = { Operation::Move },
+    default = { Operation::Default },
+    remove_if = { Operation::RemoveIf },
+    test_len = { Operation::TestLen },
+    test_replace = { Operation::TestReplace },
+    auto = { Operation::Auto },
+)]
+fn test_operation_from_str(operation: Operation) {
+    assert_eq!(operation.to_string().parse::<Operation>(), Ok(operation));
+}
+
+#[parameterized(
+    unknown = { "upsert" },
+    uppercase = { "Merge" },
+    sigil = { ">" },
+    empty = { "" },
+)]
+fn test_operation_from_str_err(input: &str) {
+    assert_eq!(
+        input.parse::<Operation>(),
+        Err(JqesqueError::UnknownOperationError(input.to_string()))
+    );
+}
+
+#[parameterized(
+    array = { "?#items=3" },
+    object = { "?#settings=2" },
+    empty = { "?#empty=0" },
+    nested = { "?#settings.list=1" },
+    relative = { "?#items[#-1]=2" },
+)]
+fn test_test_len_ok(input: &str) {
+    let mut json_obj = json!({
+        "items": [1, 2, [3, 4]],
+        "settings": {"a": 1, "list": ["x"]},
+        "empty": {}
+    });
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.operation, Operation::TestLen);
+    assert_eq!(jqesque.apply_to(&mut json_obj), Ok(Operation::TestLen));
+    assert_eq!(jqesque.to_string(), input);
+}
+
+#[test]
+fn test_test_len_err() {
+    let mut json_obj = json!({"items": [1, 2], "name": "x"});
+    let jqesque = "?#items=3".parse::<Jqesque>().unwrap();
+    assert_eq!(
+        jqesque.apply_to(&mut json_obj),
+        Err(JqesqueError::TestFailedError {
+            expected: json!(3),
+            actual: json!(2),
+        })
+    );
+
+    for input in ["?#name=1", "?#missing=0"] {
+        let jqesque = input.parse::<Jqesque>().unwrap();
+        assert!(matches!(
+            jqesque.apply_to(&mut json_obj),
+            Err(JqesqueError::InvalidPathError(..))
+        ));
+    }
+
+    for input in ["?#items=three", "?#items=-1", "?#items", "?#items=1.5"] {
+        assert!(input.parse::<Jqesque>().is_err(), "{}", input);
+    }
+    assert_eq!(
+        "?#items=3".parse::<Jqesque>().unwrap().as_json(),
+        json!({"test_len": {"path": "/items", "length": 3}})
+    );
+    assert!(matches!(
+        "?#items=3".parse::<Jqesque>().unwrap().to_json_patch(),
+        Err(JqesqueError::UnsupportedOperationError(Operation::TestLen))
+    ));
+}
+
+#[test]
+fn test_value_accessors() {
+    let mut jqesque = "port=80".parse::<Jqesque>().unwrap();
+    *jqesque.value_mut().unwrap() = json!(8080);
+    assert_eq!(jqesque.to_string(), "port=8080");
+
+    assert_eq!(jqesque.take_value(), Some(json!(8080)));
+    assert_eq!(jqesque.value(), &None);
+    assert_eq!(jqesque.value_mut(), None);
+    assert_eq!(jqesque.take_value(), None);
+
+    jqesque.set_value("high");
+    assert_eq!(jqesque.value(), &Some(json!("high")));
+    jqesque.set_value(json!({"a": [1]}));
+
+    let mut json_obj = json!({});
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, json!({"port": {"a": [1]}}));
+}
+
+#[parameterized(
+    merge_number_to_string = { "~port=8080", json!({"port": "8080"}) },
+    insert_number_to_string = { ">port=8080", json!({"port": "8080"}) },
+    auto_bool_to_string = { "name=true", json!({"name": "true"}) },
+    string_to_number = { "=count=\"5\"", json!({"count": 5}) },
+    string_to_float = { "=ratio=\"0.5\"", json!({"ratio": 0.5}) },
+    string_to_bool = { "=enabled=\"false\"", json!({"enabled": false}) },
+    unconvertible = { "=count=many", json!({"count": "many"}) },
+    nested = { "~server={\"port\":8080,\"tls\":\"true\"}", json!({"server": {"port": "8080", "tls": true}}) },
+    new_key = { ">other=1", json!({"other": 1}) },
+    annotated = { "=count:str=\"5\"", json!({"count": "5"}) },
+)]
+fn test_coerce_to_existing_type(input: &str, expected: serde_json::Value) {
+    let mut json_obj = json!({
+        "port": "80",
+        "name": "x",
+        "count": 1,
+        "ratio": 1.0,
+        "enabled": true,
+        "server": {"port": "80", "tls": false}
+    });
+    let options = ApplyOptions::new().coerce_to_existing_type(true);
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    jqesque
+        .apply_to_with_options(&mut json_obj, &options)
+        .unwrap();
+    for (key, value) in expected.as_object().unwrap() {
+        assert_eq!(&json_obj[key], value, "{}", key);
+    }
+}
+
+#[test]
+fn test_coerce_to_existing_type_disabled() {
+    let mut json_obj = json!({"port": "80"});
+    let jqesque = "~port=8080".parse::<Jqesque>().unwrap();
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, json!({"port": 8080}));
+}
+
+/// Tests for `Separator::AnyOf`, accepting several separators in one path.
+#[parameterized(
+    mixed = { "a/b.c=1", json!({"a": {"b": {"c": 1}}}) },
+    dots = { "a.b.c=1", json!({"a": {"b": {"c": 1}}}) },
+    slashes = { "a/b/c=1", json!({"a": {"b": {"c": 1}}}) },
+    with_index = { "a/b[0].c=1", json!({"a": {"b": [{"c": 1}]}}) },
+    escaped = { "a/b\\.c=1", json!({"a": {"b.c": 1}}) },
+    root = { "/={\"x\":1}", json!({"x": 1}) },
+)]
+fn test_any_of_separator(input: &str, expected: serde_json::Value) {
+    let separator = Separator::AnyOf(vec!['/', '.']);
+    let jqesque = Jqesque::from_str_with_separator(input, separator).unwrap();
+    let mut json_obj = json!({});
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, expected);
+}
+
+#[test]
+fn test_any_of_separator_formatting() {
+    let separator = Separator::AnyOf(vec!['/', '.']);
+    let jqesque = Jqesque::from_str_with_separator("a/b.c=1", separator.clone()).unwrap();
+    assert_eq!(
+        jqesque.tokens(),
+        &[
+            PathToken::Key("a".to_string()),
+            PathToken::Key("b".to_string()),
+            PathToken::Key("c".to_string()),
+        ]
+    );
+    assert_eq!(jqesque.path_string(separator.clone()), "a/b/c");
+    assert_eq!(separator.alternatives(), vec!["/", "."]);
+
+    let jqesque = Jqesque::from_str_with_separator("\"a.b\"/c=1", separator.clone()).unwrap();
+    assert_eq!(jqesque.path_string(separator.clone()), "\"a.b\"/c");
+    assert!(jqesque::lint_separators("a/b.c=1", separator).is_empty());
+}
+
+#[parameterized(
+    remove = { "-missing", Operation::Remove },
+    remove_nested = { "-a.missing.deeper", Operation::Remove },
+    test = { "?missing=1", Operation::Test },
+    test_len = { "?#missing=0", Operation::TestLen },
+    remove_if = { "-?missing=1", Operation::RemoveIf },
+    test_replace = { "?=missing=1=>2", Operation::TestReplace },
+    wildcard_test = { "?a.*.x=1", Operation::Test },
+)]
+fn test_on_missing_skip(input: &str, operation: Operation) {
+    let original = json!({"a": {"b": 1}});
+    let mut json_obj = original.clone();
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    let options = ApplyOptions::new().on_missing(MissingPolicy::Skip);
+    assert_eq!(
+        jqesque.apply_to_with_options(&mut json_obj, &options),
+        Ok(ApplyOutcome::Skipped(operation))
+    );
+    assert_eq!(json_obj, original);
+
+    // By default, the missing path is an error
+    assert!(jqesque.apply_to(&mut json_obj).is_err());
+}
+
+#[test]
+fn test_on_missing_skip_batch() {
+    let options = ApplyOptions::new().on_missing(MissingPolicy::Skip);
+    let jqesques = Jqesque::parse_many("-gone;?a=1;?b=2;>c=3", Separator::Dot).unwrap();
+
+    let mut json_obj = json!({"a": 1});
+    let outcomes = Jqesque::apply_all_with_options(&jqesques, &mut json_obj, &options).unwrap();
+    assert_eq!(
+        outcomes,
+        vec![
+            ApplyOutcome::Skipped(Operation::Remove),
+            ApplyOutcome::Applied(Operation::Test),
+            ApplyOutcome::Skipped(Operation::Test),
+            ApplyOutcome::Applied(Operation::Insert),
+        ]
+    );
+    assert_eq!(json_obj, json!({"a": 1, "c": 3}));
+
+    // A test of an existing value still fails
+    let jqesques = Jqesque::parse_many("?a=2;>c=3", Separator::Dot).unwrap();
+    assert!(matches!(
+        Jqesque::apply_all_with_options(&jqesques, &mut json!({"a": 1}), &options),
+        Err(JqesqueError::BatchError { index: 0, .. })
+    ));
+}
+
+#[test]
+fn test_parse_lines() {
+    let lines = vec![
+        "# settings".to_string(),
+        ">a.b=1 # first".to_string(),
+        "   ".to_string(),
+        "a..c=2".to_string(),
+        "x={\"k\": \"v # not a comment\"}".to_string(),
+    ];
+    let options = ParseOptions::new();
+    let mut results = Jqesque::parse_lines(lines.into_iter(), &options);
+
+    assert_eq!(results.next().unwrap().unwrap().to_string(), ">a.b=1");
+    match results.next().unwrap() {
+        Err(JqesqueError::LineError { line, source }) => {
+            assert_eq!(line, 4);
+            assert!(matches!(*source, JqesqueError::ParseError { .. }));
+        }
+        other => panic!("expected a LineError, got {:?}", other),
+    }
+    assert_eq!(
+        results.next().unwrap().unwrap().value,
+        Some(json!({"k": "v # not a comment"}))
+    );
+    assert!(results.next().is_none());
+}
+
+#[test]
+fn test_parse_lines_is_lazy() {
+    // An endless iterator is fine, as long as only a few assignments are taken
+    let lines = (0..).map(|index| format!(">items[{}]={}", index, index));
+    let options = ParseOptions::new();
+    let parsed: Vec<Jqesque> = Jqesque::parse_lines(lines, &options)
+        .take(3)
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    let mut json_obj = json!({});
+    Jqesque::apply_all(&parsed, &mut json_obj).unwrap();
+    assert_eq!(json_obj, json!({"items": [0, 1, 2]}));
+}
+
+/// Tests for `\uXXXX` escapes in quoted keys.
+#[parameterized(
+    latin = { ">\"\\u00e9\"=1", json!({"é": 1}) },
+    inside_key = { ">\"caf\\u00E9\".menu=1", json!({"café": {"menu": 1}}) },
+    single_quotes = { ">'\\u0041b'=1", json!({"Ab": 1}) },
+    escaped_separator = { ">\"a\\u002eb\"=1", json!({"a.b": 1}) },
+    surrogate_pair = { ">\"\\ud83d\\ude00\"=1", json!({"😀": 1}) },
+)]
+fn test_unicode_escape(input: &str, expected: serde_json::Value) {
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.as_json(), expected);
+}
+
+#[parameterized(
+    lone_high_surrogate = { ">\"\\ud800\"=1" },
+    lone_low_surrogate = { ">\"\\udc00\"=1" },
+    high_surrogate_without_low = { ">\"\\ud83d\\u0041\"=1" },
+    too_few_digits = { ">\"\\u00e\"=1" },
+    not_hex = { ">\"\\uzzzz\"=1" },
+)]
+fn test_unicode_escape_err(input: &str) {
+    assert!(matches!(
+        input.parse::<Jqesque>(),
+        Err(JqesqueError::ParseError { .. })
+    ));
+}
+
+/// Tests for numbers with a leading unary plus.
+#[parameterized(
+    integer = { "delta=+5", json!({"delta": 5}) },
+    float = { "delta=+1.5", json!({"delta": 1.5}) },
+    with_add_operation = { "+delta=+5", json!({"delta": 5}) },
+    not_a_number = { "delta=+5x", json!({"delta": "+5x"}) },
+    double_sign = { "delta=+-5", json!({"delta": "+-5"}) },
+    plus_only = { "delta=+", json!({"delta": "+"}) },
+    not_numeric_json = { "delta=+[1]", json!({"delta": "+[1]"}) },
+)]
+fn test_unary_plus_number(input: &str, expected: serde_json::Value) {
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    let mut json_obj = json!({});
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, expected);
+}
+
+/// Tests for the observer told about each node an assignment touches.
+#[parameterized(
+    insert_into_existing = { ">foo.bar=1", json!({"foo": {"bar": 0}}), vec![("", NodeEvent::Visited), ("/foo", NodeEvent::Visited), ("/foo/bar", NodeEvent::Overwritten)] },
+    insert_creates_parents = { ">a.b=1", json!({}), vec![("", NodeEvent::Visited), ("/a", NodeEvent::Created), ("/a/b", NodeEvent::Created)] },
+    insert_pads_array = { ">list[2]=x", json!({"list": ["a"]}), vec![("", NodeEvent::Visited), ("/list", NodeEvent::Visited), ("/list/1", NodeEvent::Created), ("/list/2", NodeEvent::Created)] },
+    insert_replaces_scalar = { ">foo.bar=1", json!({"foo": "text"}), vec![("", NodeEvent::Visited), ("/foo", NodeEvent::Overwritten), ("/foo/bar", NodeEvent::Created)] },
+    insert_append = { ">list[-]=b", json!({"list": ["a"]}), vec![("", NodeEvent::Visited), ("/list", NodeEvent::Visited), ("/list/1", NodeEvent::Created)] },
+    merge_object = { "~foo={\"a\":1,\"b\":2}", json!({"foo": {"a": 0}}), vec![("", NodeEvent::Visited), ("/foo", NodeEvent::Visited), ("/foo/a", NodeEvent::Overwritten), ("/foo/b", NodeEvent::Created)] },
+    merge_array_by_index = { "~list=[1,2]", json!({"list": [0]}), vec![("", NodeEvent::Visited), ("/list", NodeEvent::Visited), ("/list/0", NodeEvent::Overwritten), ("/list/1", NodeEvent::Created)] },
+    default_missing = { ":foo=1", json!({}), vec![("", NodeEvent::Visited), ("/foo", NodeEvent::Created)] },
+    default_existing = { ":foo=1", json!({"foo": 0}), vec![] },
+    remove = { "-foo", json!({"foo": 0}), vec![] },
+)]
+fn test_apply_to_with_observer(
+    input: &str,
+    json_obj: serde_json::Value,
+    expected: Vec<(&str, NodeEvent)>,
+) {
+    let mut json_obj = json_obj;
+    let mut events = Vec::new();
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    jqesque
+        .apply_to_with_observer(&mut json_obj, &mut |pointer, event| {
+            events.push((pointer.to_string(), event))
+        })
+        .unwrap();
+
+    let expected: Vec<(String, NodeEvent)> = expected
+        .into_iter()
+        .map(|(pointer, event)| (pointer.to_string(), event))
+        .collect();
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn test_apply_to_with_observer_matches_apply_to() {
+    let jqesque = ">a.list[3].b=1".parse::<Jqesque>().unwrap();
+    let mut observed = json!({"a": {"list": [1]}});
+    let mut expected = observed.clone();
+
+    jqesque
+        .apply_to_with_observer(&mut observed, &mut |_, _| {})
+        .unwrap();
+    jqesque.apply_to(&mut expected).unwrap();
+    assert_eq!(observed, expected);
+}
+
+/// Tests for applying inserts through the `JsonLike` trait.
+#[parameterized(
+    nested = { ">a.b=1", json!({"a": {"c": 2}}), json!({"a": {"b": 1, "c": 2}}) },
+    padded = { ">list[2]=x", json!({"list": ["a"]}), json!({"list": ["a", null, "x"]}) },
+    relative = { ">list[#-1]=z", json!({"list": ["a", "b"]}), json!({"list": ["a", "z"]}) },
+    replaces_scalar = { ">a.b=1", json!({"a": 0}), json!({"a": {"b": 1}}) },
+)]
+fn test_apply_to_json_like(input: &str, json_obj: serde_json::Value, expected: serde_json::Value) {
+    let mut json_obj = json_obj;
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    assert_eq!(
+        jqesque.apply_to_json_like(&mut json_obj).unwrap(),
+        Operation::Insert
+    );
+    assert_eq!(json_obj, expected);
+}
+
+#[test]
+fn test_apply_to_json_like_unsupported() {
+    let mut json_obj = json!({"a": 1});
+    let jqesque = "-a".parse::<Jqesque>().unwrap();
+    assert!(matches!(
+        jqesque.apply_to_json_like(&mut json_obj),
+        Err(JqesqueError::UnsupportedDocumentOperationError(
+            Operation::Remove
+        ))
+    ));
+    assert_eq!(json_obj, json!({"a": 1}));
+}
+
+#[test]
+fn test_json_like_accessors() {
+    let mut doc = serde_json::Value::empty_object();
+    assert_eq!(
+        doc.object_insert("k".to_string(), serde_json::Value::from_json(json!("v"))),
+        None
+    );
+    assert_eq!(doc.object_get("k"), Some(&json!("v")));
+    assert_eq!(doc.array_len(), None);
+    assert!(doc.is_object() && !doc.is_array());
+
+    let mut list = serde_json::Value::empty_array();
+    list.array_push(json!(true));
+    assert_eq!(list.array_get_mut(0), Some(&mut json!(true)));
+    assert_eq!(list.array_len(), Some(1));
+    assert_eq!(list.object_get("k"), None);
+}
+
+#[test]
+fn test_pointer_cache_follows_tokens() {
+    let mut jqesque = "=a.b=1".parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.to_pointer().as_str(), "/a/b");
+
+    // Changing the tokens, with the setter or directly, changes the pointer
+    jqesque.set_tokens(vec![PathToken::Key("c".to_string())]);
+    assert_eq!(jqesque.to_pointer().as_str(), "/c");
+    jqesque.tokens = vec![PathToken::Key("a".to_string())];
+    assert_eq!(jqesque.to_pointer().as_str(), "/a");
+
+    let mut json_obj = json!({"a": 0, "c": 0});
+    jqesque.apply_to(&mut json_obj).unwrap();
+    assert_eq!(json_obj, json!({"a": 1, "c": 0}));
+}
+
+#[test]
+fn test_pointer_cache_ignored_by_equality() {
+    let cached = "=a.b=1".parse::<Jqesque>().unwrap();
+    let fresh = cached.clone();
+    cached.to_pointer();
+
+    assert_eq!(cached, fresh);
+    assert_eq!(cached.clone(), fresh);
+    assert_eq!(format!("{:?}", cached), format!("{:?}", fresh));
+    assert_eq!(
+        serde_json::to_value(&cached).unwrap(),
+        serde_json::to_value(&fresh).unwrap()
+    );
+}
+
+#[test]
+fn test_pointer_cache_across_documents() {
+    let jqesque = "=list[#-1]=x".parse::<Jqesque>().unwrap();
+    let mut short = json!({"list": [1]});
+    let mut long = json!({"list": [1, 2, 3]});
+
+    jqesque.apply_to(&mut short).unwrap();
+    jqesque.apply_to(&mut long).unwrap();
+    assert_eq!(short, json!({"list": ["x"]}));
+    assert_eq!(long, json!({"list": [1, 2, "x"]}));
+}
+
+/// Tests for the limits on how deep and long an assignment may be. Parsing stops at the first
+/// token over the limit, so the depth reported is the limit plus one however long the path is.
+#[parameterized(
+    deep_keys = { format!("{}=1", vec!["a"; 10_000].join(".")), 129 },
+    bracket_chain = { format!(">a{}=1", "[0]".repeat(5_000)), 129 },
+    just_over = { format!("{}=1", vec!["a"; 129].join(".")), 129 },
+    deep_copy_source = { format!("&a<{}", vec!["b"; 200].join(".")), 129 },
+    pathological_keys = { format!("{}=1", vec!["a"; 2_000_000].join(".")), 129 },
+    pathological_brackets = { format!(">a{}=1", "[0]".repeat(2_000_000)), 129 },
+    invalid_after_limit = { format!("{}..=1", vec!["a"; 200].join(".")), 129 },
+)]
+fn test_max_depth_default(input: String, depth: usize) {
+    assert_eq!(
+        input.parse::<Jqesque>(),
+        Err(JqesqueError::InputTooComplexError { depth, limit: 128 })
+    );
+}
+
+#[parameterized(
+    at_limit = { "a.b.c=1", 3, true },
+    over_limit = { "a.b.c=1", 2, false },
+    root = { ".={}", 0, true },
+    indices_count = { ">a[0][1]=1", 2, false },
+)]
+fn test_max_depth(input: &str, max_depth: usize, ok: bool) {
+    let options = ParseOptions::new().max_depth(max_depth);
+    let result = Jqesque::parse_with_options(input, &options);
+    assert_eq!(result.is_ok(), ok, "{:?}", result);
+}
+
+#[test]
+fn test_max_depth_at_default_limit() {
+    let input = format!("{}=1", vec!["a"; 128].join("."));
+    assert!(input.parse::<Jqesque>().is_ok());
+}
+
+#[parameterized(
+    at_limit = { "a=12345", 7, true },
+    over_limit = { "a=123456", 7, false },
+    trailing_whitespace_counts = { "a=12345 ", 7, false },
+)]
+fn test_max_input_length(input: &str, max_input_length: usize, ok: bool) {
+    let options = ParseOptions::new().max_input_length(max_input_length);
+    match Jqesque::parse_with_options(input, &options) {
+        Ok(_) => assert!(ok),
+        Err(JqesqueError::InputTooLongError { length, limit }) => {
+            assert!(!ok);
+            assert_eq!(length, input.len());
+            assert_eq!(limit, max_input_length);
+        }
+        Err(e) => panic!("unexpected error: {:?}", e),
+    }
+}
+
+/// Tests for which flavor of `Value::Number` a numeric value gets.
+#[parameterized(
+    inferred_int = { "n=3", false },
+    inferred_float = { "n=3.0", true },
+    forced_float = { "n=(float)3", true },
+    forced_float_negative = { "n=(float)-3", true },
+    forced_int = { "n=(int)3.0", false },
+    forced_int_exponent = { "n=(int)1e3", false },
+    forced_int_negative = { "n=(int)-2.0", false },
+)]
+fn test_number_flavor(input: &str, is_float: bool) {
+    let value = input.parse::<Jqesque>().unwrap().value.unwrap();
+    assert_eq!(value.is_f64(), is_float, "{}", value);
+    assert_eq!(value.is_i64() || value.is_u64(), !is_float, "{}", value);
+}
+
+#[parameterized(
+    whole = { "n=(int)3.0", json!(3) },
+    exponent = { "n=(int)1e3", json!(1000) },
+    large = { "n=(int)1e19", json!(10_000_000_000_000_000_000u64) },
+)]
+fn test_int_from_whole_float(input: &str, expected: serde_json::Value) {
+    assert_eq!(input.parse::<Jqesque>().unwrap().value, Some(expected));
+}
+
+#[parameterized(
+    fraction = { "n=(int)3.5" },
+    too_large = { "n=(int)1e20" },
+    infinite = { "n=(int)inf" },
+    not_a_number = { "n=(int)NaN" },
+)]
+fn test_int_from_float_err(input: &str) {
+    assert!(matches!(
+        input.parse::<Jqesque>(),
+        Err(JqesqueError::ParseError { .. })
+    ));
+}
+
+/// Tests for iter_leaves, rendering each leaf as its path and value.
+#[parameterized(
+    mixed = {
+        json!({"b": [1, {"c": null, "a": "x"}], "a": {"z": true, "y": [[]]}, "e": {}}),
+        vec!["a.y[0]=[]", "a.z=true", "b[0]=1", "b[1].a=\"x\"", "b[1].c=null", "e={}"]
+    },
+    scalar_root = { json!(5), vec!["=5"] },
+    array_root = { json!([1, [2, 3], 4]), vec!["[0]=1", "[1][0]=2", "[1][1]=3", "[2]=4"] },
+    empty_array_root = { json!([]), vec!["=[]"] },
+    empty_root = { json!({}), vec![] },
+)]
+fn test_iter_leaves(document: serde_json::Value, expected: Vec<&str>) {
+    let leaves: Vec<String> = Jqesque::iter_leaves(&document)
+        .map(|(tokens, leaf)| {
+            let path: Vec<String> = tokens.iter().map(|token| token.to_string()).collect();
+            format!("{}={}", path.join(".").replace(".[", "["), leaf)
+        })
+        .collect();
+    assert_eq!(leaves, expected);
+}
+
+#[test]
+fn test_iter_leaves_borrows() {
+    let document = json!({"a": {"b": [1, 2]}, "c": "text"});
+    for (tokens, leaf) in Jqesque::iter_leaves(&document) {
+        let pointer = jqesque::tokens_to_pointer(&tokens);
+        assert!(std::ptr::eq(
+            document.pointer(pointer.as_str()).unwrap(),
+            leaf
+        ));
+    }
+    assert_eq!(Jqesque::iter_leaves(&document).count(), 3);
+}
+
+#[test]
+fn test_merge_string_concat_note() {
+    let mut json_obj = json!({"note": "ok"});
+    let jqesque = "~note=\" done\"".parse::<Jqesque>().unwrap();
+    let options =
+        ApplyOptions::new().merge(MergeOptions::new().strategy(MergeStrategy::StringConcat));
+
+    jqesque
+        .apply_to_with_options(&mut json_obj, &options)
+        .unwrap();
+    assert_eq!(json_obj, json!({"note": "ok done"}));
+}
+
+/// Tests for keys starting with an escaped operator character.
+#[parameterized(
+    add = { "\\+weird=1", Operation::Auto, vec![PathToken::Key("+weird".to_string())] },
+    insert_with_operator = { ">\\+weird=1", Operation::Insert, vec![PathToken::Key("+weird".to_string())] },
+    replace_sigil = { "\\=eq=1", Operation::Auto, vec![PathToken::Key("=eq".to_string())] },
+    remove_sigil = { "\\-x=1", Operation::Auto, vec![PathToken::Key("-x".to_string())] },
+    test_sigil = { "\\?q=1", Operation::Auto, vec![PathToken::Key("?q".to_string())] },
+    later_segment = { ">a.\\~b=1", Operation::Insert, vec![PathToken::Key("a".to_string()), PathToken::Key("~b".to_string())] },
+    operator_only = { "\\@=1", Operation::Auto, vec![PathToken::Key("@".to_string())] },
+    with_index = { "\\&list[0]=1", Operation::Auto, vec![PathToken::Key("&list".to_string()), PathToken::Index(0)] },
+)]
+fn test_escaped_operator(input: &str, operation: Operation, tokens: Vec<PathToken>) {
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.operation, operation);
+    assert_eq!(jqesque.tokens, tokens);
+
+    // The key is quoted when formatted, so it parses back the same
+    let formatted = jqesque.to_string().parse::<Jqesque>().unwrap();
+    assert_eq!(formatted.tokens, tokens);
+}
+
+#[test]
+fn test_escaped_operator_apply() {
+    let mut json_obj = json!({});
+    "\\+weird=1"
+        .parse::<Jqesque>()
+        .unwrap()
+        .apply_to(&mut json_obj)
+        .unwrap();
+    assert_eq!(json_obj, json!({"+weird": 1}));
+}
+
+#[parameterized(
+    inside_key = { "a\\+b=1" },
+    after_first_char = { "a\\~=1" },
+)]
+fn test_escaped_operator_err(input: &str) {
+    assert!(matches!(
+        input.parse::<Jqesque>(),
+        Err(JqesqueError::ParseError { .. })
+    ));
+}
+
+/// Tests for counting the nodes an assignment creates.
+#[parameterized(
+    fresh_nested = { ">a.b.c=1", json!({}), 3 },
+    partly_existing = { ">a.b.c=1", json!({"a": {}}), 2 },
+    overwrite = { ">a.b.c=1", json!({"a": {"b": {"c": 0}}}), 0 },
+    overwrite_scalar_parent = { ">a.b=1", json!({"a": 0}), 1 },
+    array_padding = { ">list[99]=x", json!({}), 101 },
+    append = { ">list[-]=x", json!({"list": [1]}), 1 },
+    merge_new_keys = { "~a={\"b\":1,\"c\":2}", json!({"a": {"b": 0}}), 1 },
+    default_existing = { ":a=1", json!({"a": 0}), 0 },
+    replace = { "=a=1", json!({"a": 0}), 0 },
+)]
+fn test_apply_to_counted(input: &str, json_obj: serde_json::Value, count: usize) {
+    let mut json_obj = json_obj;
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    let (_, created) = jqesque.apply_to_counted(&mut json_obj).unwrap();
+    assert_eq!(created, count);
+}
+
+#[parameterized(
+    number = { "?=version=3=>4", json!({"version": 4, "name": "a=>b"}) },
+    string = { "?=name=\"a=>b\"=>c", json!({"version": 3, "name": "c"}) },
+    spaced = { "?=version = 3 => {\"major\": 4}", json!({"version": {"major": 4}, "name": "a=>b"}) },
+    typed = { "?=version=(int)3=>(str)4", json!({"version": "4", "name": "a=>b"}) },
+)]
+fn test_test_replace_ok(input: &str, expected: serde_json::Value) {
+    let mut json_obj = json!({"version": 3, "name": "a=>b"});
+    let jqesque = input.parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.operation, Operation::TestReplace);
+    assert_eq!(jqesque.apply_to(&mut json_obj), Ok(Operation::TestReplace));
+    assert_eq!(json_obj, expected);
+    assert_eq!(jqesque.to_string().parse::<Jqesque>().unwrap(), jqesque);
+    assert_eq!(
+        Jqesque::from_json_spec(&jqesque.to_json_spec()).unwrap(),
+        jqesque
+    );
+}
+
+#[parameterized(
+    number = { "?=port=8080=>9090", json!({"port": "8080"}), json!({"port": "9090"}) },
+    quoted = { "?=arrow=\"a=>b\"=>c", json!({"arrow": "\"a=>b\""}), json!({"arrow": "c"}) },
+    escaped_quote = { "?=q=\"a\\\"=>\"=>d", json!({"q": "\"a\\\"=>\""}), json!({"q": "d"}) },
+)]
+fn test_test_replace_without_inference(
+    input: &str,
+    document: serde_json::Value,
+    expected: serde_json::Value,
+) {
+    let options = ParseOptions::new().infer_types(false);
+    let jqesque = Jqesque::parse_with_options(input, &options).unwrap();
+    let mut json_obj = document;
+    assert_eq!(jqesque.apply_to(&mut json_obj), Ok(Operation::TestReplace));
+    assert_eq!(json_obj, expected);
+}
+
+#[test]
+fn test_test_replace_err() {
+    let original = json!({"version": 3});
+    let mut json_obj = original.clone();
+    let jqesque = "?=version=2=>4".parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.expected, Some(json!(2)));
+    assert_eq!(
+        jqesque.apply_to(&mut json_obj),
+        Err(JqesqueError::TestFailedError {
+            expected: json!(2),
+            actual: json!(3),
+        })
+    );
+    assert_eq!(json_obj, original);
+
+    let missing = "?=other=2=>4".parse::<Jqesque>().unwrap();
+    assert!(matches!(
+        missing.apply_to(&mut json_obj),
+        Err(JqesqueError::InvalidPathError(..))
+    ));
+    assert_eq!(json_obj, original);
+
+    for input in ["?=version=3", "?=version==>4", "?=version"] {
+        assert!(input.parse::<Jqesque>().is_err(), "{}", input);
+    }
+    assert_eq!(
+        Jqesque::from_parts(
+            Operation::TestReplace,
+            vec![PathToken::Key("a".into())],
+            Some(json!(1))
+        ),
+        Err(JqesqueError::MissingExpectedError(Operation::TestReplace))
+    );
+    assert_eq!(
+        JqesqueBuilder::new()
+            .key("a")
+            .value(1)
+            .operation(Operation::TestReplace)
+            .build(),
+        Err(JqesqueError::MissingExpectedError(Operation::TestReplace))
+    );
+}
+
+#[test]
+fn test_test_replace_representations() {
+    let jqesque = "?=config.version=3=>4".parse::<Jqesque>().unwrap();
+    assert_eq!(jqesque.to_string(), "?=config.version=3=>4");
+    let expected = json!([
+        {"op": "test", "path": "/config/version", "value": 3},
+        {"op": "replace", "path": "/config/version", "value": 4}
+    ]);
+    assert_eq!(jqesque.as_json(), expected);
+    assert_eq!(
+        serde_json::to_value(jqesque.to_json_patch().unwrap()).unwrap(),
+        expected
+    );
+
+    let built = JqesqueBuilder::new()
+        .key("config")
+        .key("version")
+        .value(4)
+        .expected(3)
+        .operation(Operation::TestReplace)
+        .build()
+        .unwrap();
+    assert_eq!(built, jqesque);
+}
+
+#[parameterized(
+    dot = { "a.b[0]=1", Separator::Dot },
+    slash = { "a/b[0]=1", Separator::Slash },
+    custom = { "a|b[0]=1", Separator::Custom('|') },
+    custom_str = { "a::b[0]=1", Separator::CustomStr("::".to_string()) },
+)]
+fn test_stored_separator(input: &str, separator: Separator) {
+    let jqesque = Jqesque::from_str_with_separator(input, separator.clone()).unwrap();
+    assert_eq!(jqesque.separator(), &separator);
+    assert_eq!(jqesque.to_string(), input);
+    assert_eq!(jqesque, "a.b[0]=1".parse::<Jqesque>().unwrap());
+
+    let serialized = serde_json::to_value(&jqesque).unwrap();
+    let deserialized: Jqesque = serde_json::from_value(serialized).unwrap();
+    assert_eq!(deserialized.separator(), &separator);
+    assert_eq!(deserialized.to_string(), input);
+
+    let dotted = jqesque.with_separator(Separator::Dot);
+    assert_eq!(dotted.separator(), &Separator::Dot);
+    assert_eq!(dotted.to_string(), "a.b[0]=1");
+}
+
+#[test]
+fn test_stored_separator_default() {
+    let jqesque = JqesqueBuilder::new().key("a").value(1).build().unwrap();
+    assert_eq!(jqesque.separator(), &Separator::Dot);
+    let serialized = serde_json::to_value(&jqesque).unwrap();
+    assert!(serialized.get("separator").is_none());
+
+    let copy = Jqesque::from_str_with_separator("&a/b<c/d", Separator::Slash).unwrap();
+    assert_eq!(copy.to_string(), "&a/b<c/d");
+}
+
+#[parameterized(
+    bare = { "name", Separator::Dot, "name" },
+    digits = { "2024", Separator::Dot, "2024" },
+    dashed = { "a-b_c", Separator::Dot, "a-b_c" },
+    dotted = { "a.b", Separator::Dot, "\"a.b\"" },
+    dotted_slash = { "a.b", Separator::Slash, "\"a.b\"" },
+    slashed = { "a/b", Separator::Slash, "\"a/b\"" },
+    spaces = { "a key", Separator::Dot, "\"a key\"" },
+    quotes = { "say \"hi\"", Separator::Dot, "\"say \\\"hi\\\"\"" },
+    backslash = { "a\\b", Separator::Dot, "\"a\\\\b\"" },
+    control = { "a\nb", Separator::Dot, "\"a\\u000ab\"" },
+    leading_dash = { "-a", Separator::Dot, "\"-a\"" },
+    append = { "-", Separator::Dot, "\"-\"" },
+    wildcard = { "*", Separator::Dot, "\"*\"" },
+    empty = { "", Separator::Dot, "\"\"" },
+    operator = { "=x", Separator::Dot, "\"=x\"" },
+)]
+fn test_quote_key(key: &str, separator: Separator, expected: &str) {
+    let quoted = jqesque::quote_key(key, separator.clone());
+    assert_eq!(quoted, expected);
+
+    let input = format!("{}{}{}=1", quoted, separator.as_str(), quoted);
+    let jqesque = Jqesque::from_str_with_separator(&input, separator).unwrap();
+    assert_eq!(jqesque.operation, Operation::Auto);
+    assert_eq!(
+        jqesque.tokens,
+        vec![
+            PathToken::Key(key.to_string()),
+            PathToken::Key(key.to_string())
+        ]
+    );
+}
+
+#[test]
+fn test_error_sources() {
+    use std::error::Error;
+
+    let source = |error: &JqesqueError| {
+        error
+            .source()
+            .and_then(|source| source.downcast_ref::<ErrorSource>())
+            .map(|source| source.to_string())
+    };
+
+    let error = "?missing.key=1"
+        .parse::<Jqesque>()
+        .unwrap()
+        .apply_to(&mut json!({}))
+        .unwrap_err();
+    assert!(matches!(error, JqesqueError::InvalidPathError(_, Some(_))));
+    let resolve = error
+        .source()
+        .unwrap()
+        .downcast_ref::<ErrorSource>()
+        .unwrap();
+    assert!(resolve
+        .downcast_ref::<jsonptr::resolve::ResolveError>()
+        .is_some());
+
+    let error = "+array[10]=1"
+        .parse::<Jqesque>()
+        .unwrap()
+        .apply_to(&mut json!({"array": []}))
+        .unwrap_err();
+    assert_eq!(source(&error).as_deref(), Some("path is invalid"));
+
+    let options = ParseOptions::new().strict_values(true);
+    let error = Jqesque::parse_with_options("data={\"a\":}", &options).unwrap_err();
+    let json_error = error
+        .source()
+        .unwrap()
+        .downcast_ref::<ErrorSource>()
+        .unwrap();
+    assert!(json_error.downcast_ref::<serde_json::Error>().is_some());
+
+    // Batch errors chain to the error of the assignment, and on to its source
+    let jqesques = ["a=1", "?b.c=2"].map(|input| input.parse::<Jqesque>().unwrap());
+    let error = Jqesque::apply_all(&jqesques, &mut json!({})).unwrap_err();
+    let inner = error
+        .source()
+        .unwrap()
+        .downcast_ref::<Box<JqesqueError>>()
+        .unwrap();
+    assert!(matches!(
+        **inner,
+        JqesqueError::InvalidPathError(_, Some(_))
+    ));
+    assert!(inner.source().is_some());
+
+    // Errors without an underlying cause have no source
+    let error = "a..b=1".parse::<Jqesque>().unwrap_err();
+    assert!(error.source().is_none());
+    let error = Jqesque::from_str_with_separator("=missing[#-1]=1", Separator::Dot)
+        .unwrap()
+        .apply_to(&mut json!({}))
+        .unwrap_err();
+    assert_eq!(
+        error,
+        JqesqueError::InvalidPathError(
+            "no array to resolve length-relative index at token 1".to_string(),
+            None
+        )
+    );
+    assert!(error.source().is_none());
+}
+
+#[test]
+fn test_error_source_eq() {
+    let a = ErrorSource::new(json_patch::PatchErrorKind::InvalidPointer);
+    let b = ErrorSource::new(json_patch::PatchErrorKind::InvalidPointer);
+    let c = ErrorSource::new(json_patch::PatchErrorKind::InvalidFromPointer);
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_eq!(a.to_string(), "path is invalid");
+    assert!(a.get_ref().source().is_none());
+}